@@ -37,6 +37,8 @@ pub enum CommandError {
 	},
 	#[error("Incomplete command")]
 	IncompleteCommand,
+	#[error("You don't have permission to use '{name}'")]
+	PermissionDenied { name: String },
 }
 
 impl CommandRegistry {
@@ -53,11 +55,19 @@ impl CommandRegistry {
 		builder(root_cmd);
 	}
 
+	/// The permission a sender needs to run a root command and everything below it
+	pub fn permission_node(name: &str) -> String {
+		format!("command.{}", name)
+	}
+
 	pub fn execute(&self, sender: Arc<dyn CommandSender>, input: &str) -> Result<(), CommandError> {
 		let parts: Vec<&str> = input.split_whitespace().collect();
 		if parts.is_empty() {
 			return Ok(());
 		}
+		if self.root.children.contains_key(parts[0]) && !sender.has_permission(&Self::permission_node(parts[0])) {
+			return Err(CommandError::PermissionDenied { name: parts[0].to_string() });
+		}
 
 		let mut current_node = &self.root;
 		let mut parsed_args = HashMap::new();
@@ -57,7 +57,6 @@ pub struct ServerAuthManager {
 	api: SessionService,
 	session: Arc<RwLock<Option<ServerSession>>>,
 	cert_fingerprint: String,
-	instance_audience_id: String,
 	auth_store: Option<AuthFileStore>,
 }
 
@@ -68,7 +67,6 @@ impl ServerAuthManager {
 			api: SessionService::new()?,
 			session: Arc::new(RwLock::new(None)),
 			cert_fingerprint,
-			instance_audience_id: Uuid::new_v4().to_string(),
 			auth_store,
 		}))
 	}
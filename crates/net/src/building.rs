@@ -0,0 +1,77 @@
+use protocol::v2::{
+	BlockRotation,
+	GameMode,
+	PositionF,
+	Vector3i,
+};
+use thiserror::Error;
+use world::CHUNK_HEIGHT;
+
+/// How far adventure players reach, from their eyes to the center of a block.
+const ADVENTURE_REACH: f64 = 5.0;
+/// How far creative players reach, from their eyes to the center of a block.
+const CREATIVE_REACH: f64 = 10.0;
+/// Height of a player's eyes above their feet.
+const EYE_HEIGHT: f64 = 1.6;
+/// Height of a player's body, blocks placed inside it are refused.
+const PLAYER_HEIGHT: f64 = 1.8;
+/// Half the width of a player's body.
+const PLAYER_HALF_WIDTH: f64 = 0.3;
+/// Share of a block's health an adventure player's hit takes away.
+pub const DAMAGE_PER_HIT: f32 = 0.25;
+/// Durability the held tool loses for every block an adventure player breaks.
+pub const TOOL_WEAR_PER_BLOCK: f64 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum BuildError {
+	#[error("no block position")]
+	NoPosition,
+	#[error("block is outside the world")]
+	OutOfWorld,
+	#[error("block is out of reach")]
+	OutOfReach,
+	#[error("block isn't loaded")]
+	NotLoaded,
+	#[error("block is already occupied")]
+	Occupied,
+	#[error("there's no block to break")]
+	NothingToBreak,
+	#[error("unknown block {0}")]
+	UnknownBlock(i32),
+	#[error("block would overlap the player")]
+	OverlapsPlayer,
+	#[error("block {0} isn't held")]
+	NotHeld(i32),
+}
+
+pub fn reach(game_mode: GameMode) -> f64 {
+	match game_mode {
+		GameMode::Adventure => ADVENTURE_REACH,
+		GameMode::Creative => CREATIVE_REACH,
+	}
+}
+
+/// Checks that a block a player wants to edit is in the world and within their reach.
+pub fn check_reach(position: Option<&Vector3i>, player: &PositionF, game_mode: GameMode) -> Result<(i32, i32, i32), BuildError> {
+	let Vector3i { x, y, z } = *position.ok_or(BuildError::NoPosition)?;
+	if !(0..CHUNK_HEIGHT as i32).contains(&y) {
+		return Err(BuildError::OutOfWorld);
+	}
+	let (dx, dy, dz) = (x as f64 + 0.5 - player.x, y as f64 + 0.5 - (player.y + EYE_HEIGHT), z as f64 + 0.5 - player.z);
+	let reach = reach(game_mode);
+	if dx * dx + dy * dy + dz * dz > reach * reach {
+		return Err(BuildError::OutOfReach);
+	}
+	Ok((x, y, z))
+}
+
+/// Whether a block would intersect the body of a player standing at a position.
+pub fn overlaps_player(x: i32, y: i32, z: i32, player: &PositionF) -> bool {
+	let overlaps = |min: f64, max: f64, block: i32| min < (block + 1) as f64 && max > block as f64;
+	overlaps(player.x - PLAYER_HALF_WIDTH, player.x + PLAYER_HALF_WIDTH, x) && overlaps(player.y, player.y + PLAYER_HEIGHT, y) && overlaps(player.z - PLAYER_HALF_WIDTH, player.z + PLAYER_HALF_WIDTH, z)
+}
+
+/// Packs a block rotation the way chunks store it, a quarter turn count per axis.
+pub fn packed_rotation(rotation: Option<&BlockRotation>) -> u8 {
+	rotation.map_or(0, |rotation| (rotation.roll as u8) << 4 | (rotation.pitch as u8) << 2 | rotation.yaw as u8)
+}
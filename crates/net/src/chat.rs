@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use protocol::v2::{
+	interface::{
+		ChatType,
+		ServerMessage,
+	},
+	FormattedMessage,
+	MaybeBool,
+};
+use thiserror::Error;
+use tokio::time::Instant;
+
+/// Longest chat message a player may send, in characters.
+pub const MAX_CHAT_LENGTH: usize = 256;
+/// Messages a player may send in a row before the rate limit kicks in.
+const CHAT_BURST: f64 = 5.0;
+/// How long it takes to earn another message once the burst is used up.
+const CHAT_REFILL: Duration = Duration::from_secs(1);
+const NAME_COLOR: &str = "#FFFF55";
+const ERROR_COLOR: &str = "#FF5555";
+
+#[derive(Debug, Error)]
+pub enum ChatError {
+	#[error("Your message is empty")]
+	Empty,
+	#[error("Your message is longer than {MAX_CHAT_LENGTH} characters")]
+	TooLong,
+	#[error("Your message contains invalid characters")]
+	InvalidCharacters,
+	#[error("You're sending messages too fast")]
+	TooFast,
+}
+
+/// Keeps a player from flooding the chat. Every message costs a token and tokens refill over time, up to a small burst.
+pub struct ChatLimiter {
+	tokens: f64,
+	last: Instant,
+}
+
+impl Default for ChatLimiter {
+	fn default() -> Self {
+		Self {
+			tokens: CHAT_BURST,
+			last: Instant::now(),
+		}
+	}
+}
+
+impl ChatLimiter {
+	/// Takes a token for a message, unless there's none left.
+	pub fn allow(&mut self) -> bool {
+		let now = Instant::now();
+		self.tokens = (self.tokens + (now - self.last).as_secs_f64() / CHAT_REFILL.as_secs_f64()).min(CHAT_BURST);
+		self.last = now;
+		if self.tokens < 1.0 {
+			return false;
+		}
+		self.tokens -= 1.0;
+		true
+	}
+}
+
+/// Trims a chat message and checks that it can be sent.
+pub fn validate_message(message: &str) -> Result<&str, ChatError> {
+	let message = message.trim();
+	if message.is_empty() {
+		Err(ChatError::Empty)
+	} else if message.chars().count() > MAX_CHAT_LENGTH {
+		Err(ChatError::TooLong)
+	} else if message.chars().any(char::is_control) {
+		Err(ChatError::InvalidCharacters)
+	} else {
+		Ok(message)
+	}
+}
+
+/// Unstyled text, optionally colored like `#RRGGBB`.
+pub fn text(text: impl Into<String>, color: Option<&str>) -> FormattedMessage {
+	FormattedMessage {
+		bold: MaybeBool::Null,
+		italic: MaybeBool::Null,
+		monospace: MaybeBool::Null,
+		underlined: MaybeBool::Null,
+		markup_enabled: false,
+		raw_text: Some(text.into()),
+		message_id: None,
+		children: None,
+		params: None,
+		message_params: None,
+		color: color.map(str::to_string),
+		link: None,
+	}
+}
+
+/// A message a player sent, shown as `<name> message`.
+pub fn player_message(sender: &str, message: &str) -> ServerMessage {
+	let mut formatted = text("", None);
+	formatted.children = Some(vec![text("<", None), text(sender, Some(NAME_COLOR)), text("> ", None), text(message, None)]);
+	server_message(formatted)
+}
+
+/// Text from the server itself, like command output.
+pub fn system_message(message: impl Into<String>) -> ServerMessage {
+	server_message(text(message, None))
+}
+
+pub fn error_message(message: impl Into<String>) -> ServerMessage {
+	server_message(text(message, Some(ERROR_COLOR)))
+}
+
+fn server_message(message: FormattedMessage) -> ServerMessage {
+	ServerMessage {
+		chat_type: ChatType::Chat,
+		message: Some(message),
+	}
+}
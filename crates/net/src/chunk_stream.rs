@@ -0,0 +1,140 @@
+use std::{
+	collections::{
+		HashSet,
+		VecDeque,
+	},
+	sync::Arc,
+};
+
+use protocol::v2::{
+	world::UnloadChunk,
+	Packet,
+};
+use tokio::task::JoinSet;
+use tracing::warn;
+use world::{
+	Chunk,
+	ChunkTracker,
+	RegionResult,
+	SharedChunk,
+	World,
+};
+
+/// How many chunks a single player may have loading at once.
+const MAX_IN_FLIGHT: usize = 16;
+
+/// Streams the chunks around a player, nearest first, and tells the client to drop the ones that went out of view.
+///
+/// Every chunk in view counts as a viewer in the [`World`], which keeps it loaded until the player moves away or the
+/// streamer is dropped.
+pub struct ChunkStreamer {
+	world: Arc<World>,
+	tracker: ChunkTracker,
+	queue: VecDeque<(i32, i32)>,
+	loading: JoinSet<((i32, i32), RegionResult<SharedChunk>)>,
+	sent: HashSet<(i32, i32)>,
+}
+
+impl ChunkStreamer {
+	pub fn new(world: Arc<World>) -> Self {
+		Self {
+			world,
+			tracker: ChunkTracker::new(),
+			queue: VecDeque::new(),
+			loading: JoinSet::new(),
+			sent: HashSet::new(),
+		}
+	}
+
+	pub fn world(&self) -> &Arc<World> {
+		&self.world
+	}
+
+	pub fn radius(&self) -> i32 {
+		self.tracker.radius()
+	}
+
+	/// Whether the client has been sent the chunk at `(x, z)`.
+	pub fn is_sent(&self, x: i32, z: i32) -> bool {
+		self.sent.contains(&(x, z))
+	}
+
+	/// Moves the view and returns the `UnloadChunk` packets for chunks the client has to forget.
+	pub fn update(&mut self, center: (i32, i32), radius: i32) -> Vec<Packet> {
+		let update = self.tracker.update(center, radius);
+		if update.is_empty() {
+			return Vec::new();
+		}
+
+		let mut packets = Vec::new();
+		for (x, z) in update.unload {
+			self.world.remove_viewer(x, z);
+			if self.sent.remove(&(x, z)) {
+				packets.push(UnloadChunk { x, z }.into());
+			}
+		}
+		for (x, z) in &update.load {
+			self.world.add_viewer(*x, *z);
+		}
+
+		// Leftovers from before the move may be further away than the new chunks
+		let tracker = &self.tracker;
+		self.queue.retain(|(x, z)| tracker.contains(*x, *z));
+		self.queue.extend(update.load);
+		let distance = |(x, z): &(i32, i32)| (x - center.0).pow(2) + (z - center.1).pow(2);
+		self.queue.make_contiguous().sort_by_key(distance);
+
+		self.dispatch();
+		packets
+	}
+
+	fn dispatch(&mut self) {
+		while self.loading.len() < MAX_IN_FLIGHT {
+			let Some((x, z)) = self.queue.pop_front() else {
+				break;
+			};
+			let world = self.world.clone();
+			self.loading.spawn(async move { ((x, z), world.load_chunk(x, z).await) });
+		}
+	}
+
+	/// Waits for the next chunk that finished loading and is still in view. Returns `None` once nothing is loading.
+	pub async fn next_loaded(&mut self) -> Option<SharedChunk> {
+		loop {
+			let result = self.loading.join_next().await?;
+			self.dispatch();
+			let ((x, z), chunk) = match result {
+				Ok(result) => result,
+				Err(e) => {
+					warn!("Chunk load task failed: {}", e);
+					continue;
+				}
+			};
+			match chunk {
+				Ok(chunk) if self.tracker.contains(x, z) => {
+					self.sent.insert((x, z));
+					return Some(chunk);
+				}
+				Ok(_) => {}
+				Err(e) => warn!("Failed to load chunk {} {}: {}", x, z, e),
+			}
+		}
+	}
+
+	/// Every packet the client needs to display a chunk column.
+	pub fn chunk_packets(chunk: &Chunk) -> Vec<Packet> {
+		let mut packets: Vec<Packet> = vec![chunk.to_set_heightmap().into(), chunk.to_set_tintmap().into(), chunk.to_set_environments().into()];
+		packets.extend(chunk.to_set_chunks().into_iter().map(Packet::from));
+		packets.extend(chunk.to_set_fluids_all().into_iter().map(Packet::from));
+		packets
+	}
+}
+
+impl Drop for ChunkStreamer {
+	fn drop(&mut self) {
+		self.loading.abort_all();
+		for (x, z) in self.tracker.clear() {
+			self.world.remove_viewer(x, z);
+		}
+	}
+}
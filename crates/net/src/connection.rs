@@ -1,8 +1,13 @@
-use std::sync::Arc;
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::Instant,
+};
 
 use anyhow::{
 	anyhow,
 	bail,
+	Context,
 	Result,
 };
 use bytes::{
@@ -11,10 +16,7 @@ use bytes::{
 	Bytes,
 	BytesMut,
 };
-use common_assets::{
-	CommonAsset,
-	CommonAssetStore,
-};
+use command::CommandSender;
 use protocol::{
 	v2,
 	v2::{
@@ -27,16 +29,58 @@ use protocol::{
 			Disconnect,
 			DisconnectType,
 		},
+		interface::ChatMessage,
+		inventory::{
+			DropCreativeItem,
+			DropItemStack,
+			InventoryAction,
+			MoveItemStack,
+			SetActiveSlot,
+			SetCreativeItem,
+			SmartGiveCreativeItem,
+			SmartMoveItemStack,
+		},
+		player::{
+			ClientMovement,
+			ClientPlaceBlock,
+			ClientReady,
+			JoinWorld,
+			LoadHotbar,
+			MouseInteraction,
+			SaveHotbar,
+			SetClientId,
+			SetGameMode,
+			UpdateMovementSettings,
+		},
 		setup::{
 			AssetFinalize,
 			AssetInitialize,
 			AssetPart,
+			PlayerSkin,
 			RequestAssets,
 			WorldLoadFinished,
 			WorldLoadProgress,
 			WorldSettings,
 		},
+		window::{
+			ClientOpenWindow,
+			CloseWindow,
+			SendWindowAction,
+		},
+		world::{
+			ServerSetBlock,
+			ServerSetPaused,
+			SetPaused,
+		},
+		Equipment,
+		GameMode,
+		MouseButtonState,
+		MouseButtonType,
 		Packet,
+		PositionF,
+		Vector3i,
+		WindowAction,
+		WindowType,
 	},
 };
 use quinn::{
@@ -44,7 +88,14 @@ use quinn::{
 	RecvStream,
 	SendStream,
 };
-use tokio::io::AsyncReadExt;
+use tokio::{
+	io::AsyncReadExt,
+	sync::{
+		broadcast::error::RecvError,
+		mpsc,
+		oneshot,
+	},
+};
 use tracing::{
 	error,
 	info,
@@ -52,29 +103,131 @@ use tracing::{
 	warn,
 };
 use uuid::Uuid;
+use world::{
+	chunk_coord,
+	local_coord,
+	view_radius_chunks,
+	BlockState,
+	CraftingBench,
+	EntityState,
+	InventoryError,
+	InventoryResult,
+	ItemStack,
+	PlayerData,
+	PlayerInventory,
+	PlayerPreferences,
+	PlayerStats,
+	SavedPosition,
+	UpdateTarget,
+	Window,
+	BASE_BENCH_TIER,
+	HOTBAR_SECTION_ID,
+	CHUNK_HEIGHT,
+};
+
+use crate::{
+	building,
+	building::BuildError,
+	chat,
+	chat::{
+		ChatError,
+		ChatLimiter,
+	},
+	chunk_stream::ChunkStreamer,
+	context::ServerContext,
+	join,
+	latency::{
+		LatencyTracker,
+		PING_INTERVAL,
+	},
+	movement::{
+		MovementOutcome,
+		MovementTracker,
+	},
+	players::{
+		PlayerHandle,
+		PlayerMessage,
+	},
+	sender::PlayerSender,
+};
 
-use crate::auth::ServerAuthManager;
+/// How many received packets may queue up before the reader waits for the play loop to catch up.
+const INCOMING_PACKET_BUFFER: usize = 256;
+/// View radius used until the client tells us its own, in chunks.
+const DEFAULT_VIEW_RADIUS: i32 = 6;
 
 pub struct PlayerConnection {
 	conn: Connection,
 	send: SendStream,
-	recv: RecvStream,
-	auth: Arc<ServerAuthManager>,
-	common_assets: Arc<CommonAssetStore>,
+	/// Taken by the reader task once the player enters the play loop.
+	recv: Option<RecvStream>,
+	ctx: Arc<ServerContext>,
 	pub username: String,
 	pub uuid: Uuid,
+	/// Set once the player is in the player registry.
+	handle: Option<PlayerHandle>,
+	/// Messages from other tasks, taken by the play loop.
+	messages: Option<mpsc::UnboundedReceiver<PlayerMessage>>,
+	/// View radius requested by the client, in chunks, `None` until it asks for one.
+	view_radius: Option<i32>,
+	movement: MovementTracker,
+	game_mode: GameMode,
+	/// Environment of the column the player stands in, once its chunk is loaded.
+	environment: Option<i32>,
+	/// Network id of the player's entity, assigned when entering the world.
+	entity_id: i32,
+	/// Skin the client picked during setup, shown on the player's entity.
+	skin: Option<Box<PlayerSkin>>,
+	/// Set once the client said it can take chunks, nothing is streamed before.
+	ready_for_chunks: bool,
+	/// Set once the player spawned, which happens when the client is ready for gameplay.
+	spawned: bool,
+	/// Whether the client last said the player sleeps, in a bed.
+	sleeping: bool,
+	chat: ChatLimiter,
+	latency: LatencyTracker,
+	inventory: PlayerInventory,
+	/// Where windows other tasks opened go once they're closed, by window id.
+	window_owners: HashMap<i32, oneshot::Sender<Window>>,
+	/// Stats as of the start of this session, apart from the counters that went up since.
+	stats: PlayerStats,
+	/// When the player entered the world, their play time grows from there.
+	joined_at: Instant,
+	/// Set when the player comes back to where they left, which keeps them from being put on the ground.
+	restored_position: bool,
 }
 
 impl PlayerConnection {
-	pub fn new(conn: Connection, send: SendStream, recv: RecvStream, auth: Arc<ServerAuthManager>, common_assets: Arc<CommonAssetStore>) -> Self {
+	pub fn new(conn: Connection, send: SendStream, recv: RecvStream, ctx: Arc<ServerContext>) -> Self {
 		Self {
 			conn,
 			send,
-			recv,
-			auth,
-			common_assets,
+			recv: Some(recv),
+			ctx,
 			username: String::new(),
 			uuid: Uuid::nil(),
+			handle: None,
+			messages: None,
+			view_radius: None,
+			movement: MovementTracker::new(PositionF {
+				x: 0.0,
+				y: CHUNK_HEIGHT as f64,
+				z: 0.0,
+			}),
+			game_mode: GameMode::Adventure,
+			environment: None,
+			entity_id: 0,
+			skin: None,
+			ready_for_chunks: false,
+			spawned: false,
+			sleeping: false,
+			chat: ChatLimiter::default(),
+			latency: LatencyTracker::default(),
+			inventory: PlayerInventory::new(),
+			window_owners: HashMap::new(),
+			stats: PlayerStats::default(),
+			joined_at: Instant::now(),
+			restored_position: false,
 		}
 	}
 
@@ -147,20 +300,737 @@ impl PlayerConnection {
 
 		info!("Player {} authenticated.", self.username);
 
-		self.run_setup().await?;
+		let (handle, messages, replaced) = match self.ctx.players.register(self.uuid, &self.username, self.conn.clone()) {
+			Ok(registered) => registered,
+			Err(e) => {
+				warn!("Rejected login of {}: {}", self.username, e);
+				self.kick(&e.to_string()).await?;
+				return Err(e.into());
+			}
+		};
+		if let Some(replaced) = replaced {
+			info!("{} logged in again, kicking their older session", self.username);
+			replaced.kick("You logged in from another location").await;
+		}
+		self.handle = Some(handle.clone());
+		self.messages = Some(messages);
+
+		let result = self.session().await;
+		self.ctx.players.unregister(&handle);
+		result
+	}
+
+	async fn session(&mut self) -> Result<()> {
+		if !self.run_setup().await? {
+			info!("{} disconnected during setup", self.username);
+			return Ok(());
+		}
+		self.play().await
+	}
+
+	async fn play(&mut self) -> Result<()> {
+		let data = self.ctx.player_data.load(self.uuid).await.with_context(|| format!("Loading the data of {}", self.username))?;
+		self.restore(data.unwrap_or_default());
+		let mut messages = self.messages.take().ok_or(anyhow!("Player messages already taken"))?;
+		self.ctx.world.add_player(self.uuid);
+		let result = self.play_loop(&mut messages).await;
+		let windows: Vec<i32> = self.window_owners.keys().copied().collect();
+		for id in windows {
+			self.close_window(id);
+		}
+		self.save_data().await;
+		// A kick waits for the messages to close, so a newer session only loads the data once it's saved
+		drop(messages);
+		{
+			let mut entities = self.ctx.world.entities().lock();
+			entities.remove_viewer(self.uuid);
+			if self.spawned {
+				entities.remove(self.entity_id);
+			}
+		}
+		self.ctx.world.remove_player(self.uuid);
+		result
+	}
+
+	async fn play_loop(&mut self, messages: &mut mpsc::UnboundedReceiver<PlayerMessage>) -> Result<()> {
+		let mut packets = self.spawn_reader()?;
+		let mut chunks = ChunkStreamer::new(self.ctx.world.clone());
+		let mut updates = self.ctx.world.subscribe();
+		let mut pings = tokio::time::interval(PING_INTERVAL);
+		self.join_world().await?;
+
+		loop {
+			tokio::select! {
+				packet = packets.recv() => {
+					let Some(packet) = packet else {
+						break;
+					};
+					if !self.handle_packet(packet, &mut chunks).await? {
+						break;
+					}
+				}
+				Some(chunk) = chunks.next_loaded() => {
+					let packets = ChunkStreamer::chunk_packets(&chunk.read());
+					for packet in packets {
+						self.send_packet(packet).await?;
+					}
+					self.update_environment().await?;
+				}
+				update = updates.recv() => match update {
+					Ok(update) if self.is_target(update.target, &chunks) => self.send_packet(update.packet).await?,
+					Ok(_) => {}
+					Err(RecvError::Lagged(missed)) => warn!("{} missed {} world updates", self.username, missed),
+					Err(RecvError::Closed) => break,
+				},
+				_ = pings.tick() => {
+					let ping = self.latency.ping();
+					self.send_packet(ping).await?;
+				}
+				Some(message) = messages.recv() => match message {
+					PlayerMessage::Packet(packet) => self.send_packet(*packet).await?,
+					PlayerMessage::Teleport(position) => self.teleport(position, &mut chunks).await?,
+					PlayerMessage::SetGameMode(game_mode) => self.set_game_mode(game_mode).await?,
+					PlayerMessage::Save(_saved) => self.save_data().await,
+					PlayerMessage::OpenWindow(window, owner) => {
+						let id = self.open_window(*window).await?;
+						self.window_owners.insert(id, owner);
+					}
+					PlayerMessage::Kick(reason) => {
+						info!("Kicked {}: {}", self.username, reason);
+						self.kick(&reason).await?;
+						break;
+					}
+				},
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Handles a packet received during play. Returns `false` once the player disconnected.
+	async fn handle_packet(&mut self, packet: Packet, chunks: &mut ChunkStreamer) -> Result<bool> {
+		match packet {
+			Packet::ViewRadius(packet) => {
+				self.view_radius = Some(view_radius_chunks(packet.value));
+				self.ctx.world.entities().lock().set_view_radius(self.uuid, self.view_radius());
+				self.update_view(chunks).await?;
+			}
+			Packet::ClientReady(packet) => self.handle_client_ready(packet, chunks).await?,
+			Packet::ClientMovement(packet) if self.spawned => self.handle_movement(packet, chunks).await?,
+			Packet::ClientPlaceBlock(packet) if self.spawned => self.handle_place_block(packet).await?,
+			Packet::MouseInteraction(packet) if self.spawned => self.handle_mouse_interaction(packet).await?,
+			Packet::MoveItemStack(packet) => self.handle_move_item(packet).await?,
+			Packet::SmartMoveItemStack(packet) => self.handle_smart_move_item(packet).await?,
+			Packet::DropItemStack(packet) => self.handle_drop_item(packet).await?,
+			Packet::SetActiveSlot(packet) => self.handle_set_active_slot(packet).await?,
+			Packet::InventoryAction(packet) => self.handle_inventory_action(packet).await?,
+			Packet::SetCreativeItem(packet) => self.handle_set_creative_item(packet).await?,
+			Packet::DropCreativeItem(packet) => self.handle_drop_creative_item(packet).await?,
+			Packet::SmartGiveCreativeItem(packet) => self.handle_smart_give_creative_item(packet).await?,
+			Packet::SetGameMode(packet) => self.handle_set_game_mode(packet).await?,
+			Packet::SaveHotbar(packet) => self.handle_save_hotbar(packet),
+			Packet::LoadHotbar(packet) => self.handle_load_hotbar(packet).await?,
+			Packet::ClientOpenWindow(packet) => self.handle_open_window(packet).await?,
+			Packet::CloseWindow(packet) => self.handle_close_window(packet),
+			Packet::SendWindowAction(packet) => self.handle_window_action(packet).await?,
+			Packet::ServerSetPaused(packet) => self.handle_set_paused(packet),
+			Packet::ChatMessage(packet) => self.handle_chat(packet).await?,
+			Packet::Pong(packet) => {
+				if !self.latency.pong(&packet) {
+					trace!("Unexpected pong {} from {}", packet.id, self.username);
+				} else if let Some(handle) = &self.handle {
+					handle.set_latency(self.latency.latency());
+				}
+			}
+			Packet::Disconnect(_) => return Ok(false),
+			packet => trace!("Unhandled packet {} from {}", packet.id(), self.username),
+		}
+		Ok(true)
+	}
+
+	/// Puts the player into the world. Chunks follow once the client is ready for them, see [`Self::handle_client_ready`].
+	async fn join_world(&mut self) -> Result<()> {
+		let world = self.ctx.world.clone();
+		if let Some(handle) = &self.handle {
+			self.ctx.players.set_world(handle, world.uuid());
+		}
+		self.entity_id = world.next_entity_id();
+		self.send_packet(SetClientId { client_id: self.entity_id }).await?;
+		self.set_game_mode(self.game_mode).await?;
+		self.send_packet(JoinWorld {
+			clear_world: true,
+			fade_in_out: true,
+			world_uuid: world.uuid(),
+		})
+		.await?;
+
+		self.send_packet(self.inventory.to_packet()).await?;
+		self.send_packet(SetActiveSlot {
+			inventory_section_id: HOTBAR_SECTION_ID,
+			active_slot: self.inventory.active_hotbar_slot,
+		})
+		.await?;
+
+		let mut packets = self.ctx.ticks.timing_packets();
+		packets.extend(world.clock().lock().time_packets());
+		packets.push(world.weather().lock().editor_override().into());
+		for packet in packets {
+			self.send_packet(packet).await?;
+		}
+		if world.is_paused() {
+			self.send_packet(SetPaused { paused: true }).await?;
+		}
+		Ok(())
+	}
+
+	async fn handle_client_ready(&mut self, packet: ClientReady, chunks: &mut ChunkStreamer) -> Result<()> {
+		if packet.ready_for_chunks && !self.ready_for_chunks {
+			self.ready_for_chunks = true;
+			self.update_view(chunks).await?;
+		}
+		if packet.ready_for_gameplay && !self.spawned {
+			self.spawn(chunks).await?;
+		}
+		Ok(())
+	}
+
+	/// Hands the player control where they left, or on top of the ground at their position if they're new here.
+	async fn spawn(&mut self, chunks: &mut ChunkStreamer) -> Result<()> {
+		let mut position = self.movement.position().clone();
+		let (x, z) = (position.x.floor() as i32, position.z.floor() as i32);
+		let chunk = self.ctx.world.load_chunk(chunk_coord(x), chunk_coord(z)).await?;
+		let ground = chunk.read().maps().height(local_coord(x), local_coord(z));
+		if let Some(ground) = ground.filter(|_| !self.restored_position) {
+			position.y = ground as f64 + 1.0;
+		}
+
+		self.send_packet(UpdateMovementSettings {
+			movement_settings: Box::new(join::default_movement_settings()),
+		})
+		.await?;
+		self.teleport(position, chunks).await?;
+		self.spawned = true;
+		{
+			let state = EntityState::player(&self.username, self.skin.as_deref().cloned(), self.movement.transform().to_model_transform());
+			let mut entities = self.ctx.world.entities().lock();
+			entities.spawn(self.entity_id, state);
+			entities.add_viewer(self.uuid, self.entity_id, self.view_radius());
+		}
+		self.update_equipment();
+		let position = self.movement.position();
+		info!("{} spawned at {:.1}, {:.1}, {:.1}", self.username, position.x, position.y, position.z);
+		Ok(())
+	}
+
+	/// Moves the player, ignoring their movement until the client confirms it arrived.
+	async fn teleport(&mut self, position: PositionF, chunks: &mut ChunkStreamer) -> Result<()> {
+		let teleport = self.movement.teleport(position);
+		self.send_packet(teleport).await?;
+		self.moved(chunks).await
+	}
+
+	async fn handle_movement(&mut self, packet: ClientMovement, chunks: &mut ChunkStreamer) -> Result<()> {
+		match self.movement.apply(&packet) {
+			MovementOutcome::Moved => self.moved(chunks).await?,
+			MovementOutcome::Turned => self.publish_transform(),
+			MovementOutcome::Stale => {}
+			MovementOutcome::Rejected => {
+				warn!("{} moved wrongly, putting them back", self.username);
+				let position = self.movement.position().clone();
+				self.teleport(position, chunks).await?;
+			}
+		}
+		self.update_sleeping();
+		Ok(())
+	}
+
+	/// Lets the world know when the player goes to bed or gets up, so the night is skipped once enough players sleep.
+	fn update_sleeping(&mut self) {
+		let sleeping = self.movement.transform().movement_states.as_ref().is_some_and(|states| states.sleeping);
+		if sleeping != self.sleeping {
+			self.sleeping = sleeping;
+			self.ctx.world.set_sleeping(self.uuid, sleeping);
+		}
+	}
+
+	/// Catches everything that depends on the player's position up with it.
+	async fn moved(&mut self, chunks: &mut ChunkStreamer) -> Result<()> {
+		self.publish_transform();
+		self.update_view(chunks).await?;
+		self.update_environment().await
+	}
+
+	/// Shares the player's transform with the rest of the server and the players that see them.
+	fn publish_transform(&self) {
+		let transform = self.movement.transform();
+		if let Some(handle) = &self.handle {
+			handle.set_transform(transform);
+		}
+		self.ctx.world.entities().lock().set_transform(self.entity_id, transform.to_model_transform(), transform.movement_states.clone());
+	}
+
+	fn is_target(&self, target: UpdateTarget, chunks: &ChunkStreamer) -> bool {
+		match target {
+			UpdateTarget::All => true,
+			UpdateTarget::Chunk(x, z) => chunks.is_sent(x, z),
+			UpdateTarget::Environment(environment) => self.environment == Some(environment),
+			UpdateTarget::Player(uuid) => self.uuid == uuid,
+		}
+	}
+
+	/// Sends the weather of the environment the player stands in whenever they enter a different one.
+	async fn update_environment(&mut self) -> Result<()> {
+		let position = self.movement.position();
+		let environment = self.ctx.world.environment_at(position.x.floor() as i32, position.z.floor() as i32);
+		if environment.is_none() || environment == self.environment {
+			return Ok(());
+		}
+		self.environment = environment;
+		let weather = environment.and_then(|environment| self.ctx.world.weather().lock().update_weather(environment));
+		if let Some(weather) = weather {
+			self.send_packet(weather).await?;
+		}
+		Ok(())
+	}
+
+	/// Places a block for the player, or puts back what's really there if they can't.
+	async fn handle_place_block(&mut self, packet: ClientPlaceBlock) -> Result<()> {
+		let state = BlockState {
+			rotation: building::packed_rotation(packet.rotation.as_ref()),
+			..BlockState::new(packet.placed_block_id as i32)
+		};
+		let result = building::check_reach(packet.position.as_ref(), self.movement.position(), self.game_mode).and_then(|(x, y, z)| {
+			let Some(name) = self.ctx.blocks.name(state.id).filter(|_| !state.is_air()) else {
+				return Err(BuildError::UnknownBlock(state.id));
+			};
+			if self.inventory.held_item().is_none_or(|item| item.item_id != name) {
+				return Err(BuildError::NotHeld(state.id));
+			}
+			match self.ctx.world.block_at(x, y, z) {
+				None => Err(BuildError::NotLoaded),
+				Some(block) if !block.is_air() => Err(BuildError::Occupied),
+				Some(_) if building::overlaps_player(x, y, z, self.movement.position()) => Err(BuildError::OverlapsPlayer),
+				Some(_) => Ok((x, y, z)),
+			}
+		});
+		match result {
+			Ok(position) => {
+				if !self.ctx.world.set_blocks(&[(position, state)]).is_empty() {
+					self.stats.blocks_placed += 1;
+					if self.game_mode != GameMode::Creative {
+						let result = self.inventory.take_held_item(1).map(drop);
+						self.inventory_changed(result).await?;
+					}
+				}
+			}
+			Err(e) => {
+				trace!("Refused block placement from {}: {}", self.username, e);
+				self.resync_block(packet.position.as_ref()).await?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Breaks or damages the block the player hits, depending on their game mode.
+	async fn handle_mouse_interaction(&mut self, packet: MouseInteraction) -> Result<()> {
+		let pressed = |button_type| packet.mouse_button.as_ref().is_some_and(|button| button.mouse_button_type == button_type && button.state == MouseButtonState::Pressed);
+		let (hit, used) = (pressed(MouseButtonType::Left), pressed(MouseButtonType::Right));
+		let Some(position) = packet.world_interaction.as_ref().and_then(|interaction| interaction.block_position.as_ref()) else {
+			return Ok(());
+		};
+		if used {
+			let block = building::check_reach(Some(position), self.movement.position(), self.game_mode).ok().and_then(|(x, y, z)| self.ctx.world.block_at(x, y, z));
+			if let Some(block) = block {
+				self.use_block(block).await?;
+			}
+			return Ok(());
+		}
+		if !hit {
+			return Ok(());
+		}
+		let result = building::check_reach(Some(position), self.movement.position(), self.game_mode).and_then(|(x, y, z)| match self.ctx.world.block_at(x, y, z) {
+			None => Err(BuildError::NotLoaded),
+			Some(block) if block.is_air() => Err(BuildError::NothingToBreak),
+			Some(_) => Ok((x, y, z)),
+		});
+		match result {
+			Ok((x, y, z)) if self.game_mode == GameMode::Creative => {
+				if !self.ctx.world.set_blocks(&[((x, y, z), BlockState::AIR)]).is_empty() {
+					self.stats.blocks_broken += 1;
+				}
+			}
+			Ok((x, y, z)) => {
+				if self.ctx.world.damage_block(x, y, z, building::DAMAGE_PER_HIT) {
+					self.stats.blocks_broken += 1;
+					if self.inventory.damage_held_item(building::TOOL_WEAR_PER_BLOCK) {
+						self.inventory_changed(Ok(())).await?;
+					}
+				}
+			}
+			Err(e) => {
+				trace!("Refused block break from {}: {}", self.username, e);
+				self.resync_block(Some(position)).await?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Sends the player the block that's really at a position, undoing an edit the client already predicted.
+	async fn resync_block(&mut self, position: Option<&Vector3i>) -> Result<()> {
+		let Some(&Vector3i { x, y, z }) = position else {
+			return Ok(());
+		};
+		let Some(block) = self.ctx.world.block_at(x, y, z) else {
+			return Ok(());
+		};
+		self.send_packet(ServerSetBlock {
+			pos: Vector3i { x, y, z },
+			block_id: block.id,
+			filler: block.filler,
+			rotation: block.rotation,
+		})
+		.await
+	}
+
+	async fn handle_move_item(&mut self, packet: MoveItemStack) -> Result<()> {
+		let result = self.inventory.move_item(packet.from_section_id, packet.from_slot_id, packet.quantity, packet.to_section_id, packet.to_slot_id);
+		self.inventory_changed(result).await
+	}
+
+	async fn handle_smart_move_item(&mut self, packet: SmartMoveItemStack) -> Result<()> {
+		let result = self.inventory.smart_move(packet.from_section_id, packet.from_slot_id, packet.quantity, packet.move_type);
+		self.inventory_changed(result).await
+	}
+
+	/// Throws items away. There are no item entities yet, so they're gone for good.
+	async fn handle_drop_item(&mut self, packet: DropItemStack) -> Result<()> {
+		let result = self.inventory.drop_item(packet.inventory_section_id, packet.slot_id, packet.quantity);
+		if let Ok(dropped) = &result {
+			trace!("{} dropped {} {}", self.username, dropped.quantity, dropped.item_id);
+		}
+		self.inventory_changed(result.map(|_| ())).await
+	}
+
+	/// Picks up where the player left: their inventory, game mode, preferences and stats, and their position if it's
+	/// in this world.
+	fn restore(&mut self, data: PlayerData) {
+		self.inventory = data.inventory;
+		self.game_mode = data.game_mode.unwrap_or(self.ctx.options.default_game_mode);
+		// The client's own request during setup wins over the one it made last time
+		self.view_radius = self.view_radius.or(data.preferences.view_radius);
+		if let Some(sort_type) = data.preferences.sort_type {
+			self.inventory.sort_type = sort_type;
+		}
+		if let (Some(SavedPosition { x, y, z }), true) = (data.position, data.world == Some(self.ctx.world.uuid())) {
+			self.movement = MovementTracker::new(PositionF { x, y, z });
+			self.restored_position = true;
+		}
+		self.stats = PlayerStats {
+			joins: data.stats.joins + 1,
+			..data.stats
+		};
+		self.joined_at = Instant::now();
+	}
+
+	/// Everything about the player that's saved, as it is right now.
+	fn player_data(&self) -> PlayerData {
+		let position = self.spawned || self.restored_position;
+		let PositionF { x, y, z } = *self.movement.position();
+		PlayerData {
+			position: position.then_some(SavedPosition { x, y, z }),
+			world: position.then(|| self.ctx.world.uuid()),
+			inventory: self.inventory.clone(),
+			game_mode: Some(self.game_mode),
+			stats: PlayerStats {
+				play_time_secs: self.stats.play_time_secs + self.joined_at.elapsed().as_secs(),
+				..self.stats.clone()
+			},
+			preferences: PlayerPreferences {
+				view_radius: self.view_radius,
+				sort_type: Some(self.inventory.sort_type),
+			},
+		}
+	}
+
+	async fn save_data(&self) {
+		if let Err(e) = self.ctx.player_data.save(self.uuid, self.player_data()).await {
+			error!("Failed to save the data of {}: {}", self.username, e);
+		}
+	}
+
+	/// Refuses creative item actions from players who aren't in creative.
+	fn creative_only(&self) -> InventoryResult<()> {
+		if self.game_mode == GameMode::Creative { Ok(()) } else { Err(InventoryError::NotCreative) }
+	}
+
+	async fn handle_set_creative_item(&mut self, packet: SetCreativeItem) -> Result<()> {
+		let result = self
+			.creative_only()
+			.and_then(|()| ItemStack::creative(&packet.item))
+			.and_then(|stack| self.inventory.set_creative_item(packet.inventory_section_id, packet.slot_id, stack, packet.r#override));
+		self.inventory_changed(result).await
+	}
+
+	/// Creative players drop items straight from the library, which vanish like every other dropped item for now.
+	async fn handle_drop_creative_item(&mut self, packet: DropCreativeItem) -> Result<()> {
+		match self.creative_only().and_then(|()| ItemStack::creative(&packet.item)) {
+			Ok(Some(dropped)) => trace!("{} dropped {} creative {}", self.username, dropped.quantity, dropped.item_id),
+			Ok(None) => {}
+			Err(e) => trace!("Refused creative item drop from {}: {}", self.username, e),
+		}
+		Ok(())
+	}
+
+	async fn handle_smart_give_creative_item(&mut self, packet: SmartGiveCreativeItem) -> Result<()> {
+		let result = self.creative_only().and_then(|()| ItemStack::creative(&packet.item)).and_then(|stack| {
+			let stack = stack.ok_or(InventoryError::InvalidQuantity(packet.item.quantity))?;
+			let quantity = stack.quantity;
+			match self.inventory.give(stack) {
+				Some(rest) if rest.quantity == quantity => Err(InventoryError::NoRoom),
+				_ => Ok(()),
+			}
+		});
+		self.inventory_changed(result).await
+	}
+
+	/// Saved rows are kept with the inventory, so they're still there next session.
+	fn handle_save_hotbar(&mut self, packet: SaveHotbar) {
+		let result = self.creative_only().and_then(|()| self.inventory.save_hotbar(packet.inventory_row));
+		if let Err(e) = result {
+			trace!("Refused hotbar save from {}: {}", self.username, e);
+		}
+	}
+
+	async fn handle_load_hotbar(&mut self, packet: LoadHotbar) -> Result<()> {
+		let result = self.creative_only().and_then(|()| self.inventory.load_hotbar(packet.inventory_row));
+		self.inventory_changed(result).await
+	}
+
+	/// Players with the `gamemode` command may switch their own game mode from the client as well.
+	async fn handle_set_game_mode(&mut self, packet: SetGameMode) -> Result<()> {
+		let Some(handle) = self.handle.clone() else {
+			return Ok(());
+		};
+		let sender = PlayerSender::new(handle, self.ctx.options.is_operator(self.uuid, &self.username));
+		let game_mode = if sender.has_permission("command.gamemode") {
+			info!("{} switched to {:?} mode", self.username, packet.game_mode);
+			packet.game_mode
+		} else {
+			trace!("Refused game mode change to {:?} from {}", packet.game_mode, self.username);
+			self.game_mode
+		};
+		self.set_game_mode(game_mode).await
+	}
+
+	/// Switches the player's game mode, which decides their reach, how they break blocks and whether they get the
+	/// creative item library.
+	async fn set_game_mode(&mut self, game_mode: GameMode) -> Result<()> {
+		self.game_mode = game_mode;
+		if let Some(handle) = &self.handle {
+			handle.set_game_mode(game_mode);
+		}
+		self.send_packet(SetGameMode { game_mode }).await
+	}
 
-		while let Ok(_) = self.read_packet().await {}
+	async fn handle_set_active_slot(&mut self, packet: SetActiveSlot) -> Result<()> {
+		match self.inventory.set_active_slot(packet.inventory_section_id, packet.active_slot) {
+			Ok(()) => self.update_equipment(),
+			Err(e) => {
+				trace!("Refused slot selection from {}: {}", self.username, e);
+				self.send_packet(SetActiveSlot {
+					inventory_section_id: HOTBAR_SECTION_ID,
+					active_slot: self.inventory.active_hotbar_slot,
+				})
+				.await?;
+			}
+		}
+		Ok(())
+	}
+
+	async fn handle_inventory_action(&mut self, packet: InventoryAction) -> Result<()> {
+		let result = self.inventory.section_action(packet.inventory_section_id, packet.inventory_action_type, packet.action_data);
+		self.inventory_changed(result).await
+	}
 
+	/// Sends the player their whole inventory and their open containers after an action. For a refused one, that undoes
+	/// what the client already predicted.
+	async fn inventory_changed(&mut self, result: InventoryResult<()>) -> Result<()> {
+		if let Err(e) = result {
+			trace!("Refused inventory action from {}: {}", self.username, e);
+		}
+		self.update_equipment();
+		self.send_packet(self.inventory.to_packet()).await?;
+		if let Some(id) = self.inventory.windows.container_id() {
+			let packet = self.inventory.windows.get(id).map(Window::update_packet);
+			if let Some(packet) = packet {
+				self.send_packet(packet).await?;
+			}
+		}
 		Ok(())
 	}
 
+	/// Opens a window for the player and returns its id.
+	async fn open_window(&mut self, window: Window) -> Result<i32> {
+		let packet = self.inventory.windows.open(window).open_packet();
+		let id = packet.id;
+		self.send_packet(packet).await?;
+		Ok(id)
+	}
+
+	/// Closes a window, handing it back to whoever opened it.
+	fn close_window(&mut self, id: i32) {
+		let window = self.inventory.windows.close(id);
+		if let (Some(window), Some(owner)) = (window, self.window_owners.remove(&id)) {
+			// An owner that stopped waiting doesn't want it back
+			let _ = owner.send(window);
+		}
+	}
+
+	/// Opens the windows players may open themselves, which is only the pocket crafting window.
+	async fn handle_open_window(&mut self, packet: ClientOpenWindow) -> Result<()> {
+		if packet.window_type != WindowType::PocketCrafting {
+			trace!("Refused to open a {:?} window for {}", packet.window_type, self.username);
+			return Ok(());
+		}
+		self.open_window(Window::pocket_crafting()).await?;
+		Ok(())
+	}
+
+	fn handle_close_window(&mut self, packet: CloseWindow) {
+		self.close_window(packet.id);
+	}
+
+	async fn handle_window_action(&mut self, packet: SendWindowAction) -> Result<()> {
+		let result = match packet.action {
+			WindowAction::CraftRecipeAction(action) => {
+				let recipe = action.recipe_id.unwrap_or_default();
+				let result = self.inventory.craft(packet.id, &recipe, action.quantity, &self.ctx.recipes);
+				if result.is_ok() {
+					trace!("{} crafted {} x{}", self.username, recipe, action.quantity);
+					self.stats.items_crafted += action.quantity as u64;
+				}
+				result
+			}
+			WindowAction::SortItemsAction(action) => self.inventory.sort(packet.id, action.sort_type),
+			action => {
+				trace!("Unhandled window action {:?} from {}", action, self.username);
+				return Ok(());
+			}
+		};
+		self.inventory_changed(result).await
+	}
+
+	/// Opens the crafting window of a bench the player uses.
+	async fn use_block(&mut self, block: BlockState) -> Result<()> {
+		let bench = self.ctx.blocks.get_by_id(block.id).and_then(|block| block.definition.bench.as_ref());
+		let Some(bench) = bench.and_then(|bench| {
+			Some(CraftingBench {
+				bench_type: bench.bench_type(),
+				id: bench.id.clone()?,
+				tier: BASE_BENCH_TIER,
+			})
+		}) else {
+			return Ok(());
+		};
+		self.open_window(Window::at_bench(bench)).await?;
+		Ok(())
+	}
+
+	/// Shows the held item in the hand of the player's entity.
+	fn update_equipment(&self) {
+		let equipment = Equipment {
+			armor_ids: None,
+			right_hand_item_id: self.inventory.held_item().map(|item| item.item_id.clone()),
+			left_hand_item_id: None,
+		};
+		self.ctx.world.entities().lock().set_equipment(self.entity_id, Some(equipment));
+	}
+
+	/// Pauses the world when its only player asks for it, like the pause menu of a singleplayer game.
+	fn handle_set_paused(&mut self, packet: ServerSetPaused) {
+		if self.ctx.world.player_count() > 1 {
+			trace!("Ignoring pause request from {}, other players are online", self.username);
+			return;
+		}
+		self.ctx.world.set_paused(packet.paused);
+	}
+
+	/// Sends a chat message to everyone in the player's world, or tells the player why it wasn't sent. Messages
+	/// starting with `/` run a command instead.
+	async fn handle_chat(&mut self, packet: ChatMessage) -> Result<()> {
+		let message = packet.message.unwrap_or_default();
+		let result = chat::validate_message(&message).and_then(|message| if self.chat.allow() { Ok(message) } else { Err(ChatError::TooFast) });
+		match result {
+			Ok(message) if message.starts_with('/') => self.run_command(&message[1..]).await?,
+			Ok(message) => {
+				info!(target: "chat", "<{}> {}", self.username, message);
+				self.ctx.world.broadcast_all(chat::player_message(&self.username, message));
+			}
+			Err(e) => self.send_packet(chat::error_message(e.to_string())).await?,
+		}
+		Ok(())
+	}
+
+	async fn run_command(&mut self, command: &str) -> Result<()> {
+		let player = self.handle.clone().ok_or(anyhow!("Commands need a registered player"))?;
+		info!("{} issued command: /{}", self.username, command);
+		let operator = self.ctx.options.is_operator(self.uuid, &self.username);
+		let sender = Arc::new(PlayerSender::new(player, operator));
+		let result = self.ctx.commands.read().map_err(|_| anyhow!("Command registry lock poisoned"))?.execute(sender, command);
+		if let Err(e) = result {
+			self.send_packet(chat::error_message(e.to_string())).await?;
+		}
+		Ok(())
+	}
+
+	/// Recenters the streamed chunks on the player and unloads the ones that went out of view.
+	async fn update_view(&mut self, chunks: &mut ChunkStreamer) -> Result<()> {
+		if !self.ready_for_chunks {
+			return Ok(());
+		}
+		let position = self.movement.position();
+		let center = (chunk_coord(position.x.floor() as i32), chunk_coord(position.z.floor() as i32));
+		for packet in chunks.update(center, self.view_radius()) {
+			self.send_packet(packet).await?;
+		}
+		Ok(())
+	}
+
+	/// The view radius the client asked for, as far as the server allows it.
+	fn view_radius(&self) -> i32 {
+		self.view_radius.unwrap_or(DEFAULT_VIEW_RADIUS).min(self.ctx.options.max_view_radius)
+	}
+
+	/// Moves the receiving half of the stream into its own task, since reading a packet can't be cancelled halfway.
+	fn spawn_reader(&mut self) -> Result<mpsc::Receiver<Packet>> {
+		let mut recv = self.recv.take().ok_or(anyhow!("Packet reader already started"))?;
+		let username = self.username.clone();
+		let (tx, rx) = mpsc::channel(INCOMING_PACKET_BUFFER);
+		tokio::spawn(async move {
+			loop {
+				match read_packet(&mut recv).await {
+					Ok(packet) => {
+						if tx.send(packet).await.is_err() {
+							break;
+						}
+					}
+					Err(e) => {
+						trace!("Stopped reading packets from {}: {}", username, e);
+						break;
+					}
+				}
+			}
+		});
+		Ok(rx)
+	}
+
 	async fn perform_online_auth(&mut self, player_token: &str) -> Result<()> {
-		let server_session = self.auth.get_session_token().await.ok_or(anyhow!("Server not logged in (Offline)"))?;
+		let server_session = self.ctx.auth.get_session_token().await.ok_or(anyhow!("Server not logged in (Offline)"))?;
 
-		let server_id = self.auth.get_server_id().await;
-		let server_identity = self.auth.get_identity_token().await;
+		let server_id = self.ctx.auth.get_server_id().await;
+		let server_identity = self.ctx.auth.get_identity_token().await;
 
-		let auth_grant_str = self.auth.get_api().request_auth_grant(player_token, &server_session, &server_id).await?;
+		let auth_grant_str = self.ctx.auth.get_api().request_auth_grant(player_token, &server_session, &server_id).await?;
 
 		self.send_packet(AuthGrant {
 			auth_grant: Some(auth_grant_str.into()),
@@ -176,9 +1046,9 @@ impl PlayerConnection {
 
 		let server_grant = auth_response.server_grant.ok_or(anyhow!("Client missing server grant"))?;
 
-		let fingerprint = self.auth.get_cert_fingerprint();
+		let fingerprint = self.ctx.auth.get_cert_fingerprint();
 
-		let access_token = self.auth.get_api().exchange_grant(&server_grant, fingerprint, &server_session).await?;
+		let access_token = self.ctx.auth.get_api().exchange_grant(&server_grant, fingerprint, &server_session).await?;
 
 		self.send_packet(ServerAuthToken {
 			server_access_token: Some(access_token.into()),
@@ -189,30 +1059,38 @@ impl PlayerConnection {
 		Ok(())
 	}
 
-	async fn run_setup(&mut self) -> Result<()> {
-		let required_assets = self.common_assets.required_assets();
+	/// Sends the world settings and the assets the client asks for. Returns `false` if the client disconnected instead.
+	async fn run_setup(&mut self) -> Result<bool> {
+		let required_assets = self.ctx.common_assets.required_assets();
 
 		self.send_packet(WorldSettings {
-			world_height: 320,
+			world_height: CHUNK_HEIGHT as i32,
 			required_assets: Some(required_assets),
 		})
 		.await?;
 
-		self.wait_for_assets_request().await?;
+		if !self.wait_for_assets_request().await? {
+			return Ok(false);
+		}
+		for packet in join::asset_updates(&self.ctx) {
+			self.send_packet(packet).await?;
+		}
 		self.send_packet(WorldLoadFinished {}).await?;
 
-		Ok(())
+		Ok(true)
 	}
 
-	async fn wait_for_assets_request(&mut self) -> Result<()> {
+	/// Answers the asset request of the client. Returns `false` if it disconnected before asking.
+	async fn wait_for_assets_request(&mut self) -> Result<bool> {
 		loop {
 			match self.read_packet().await? {
 				Packet::RequestAssets(packet) => {
 					self.handle_request_assets(packet).await?;
-					return Ok(());
+					return Ok(true);
 				}
-				Packet::ViewRadius(_) | Packet::PlayerOptions(_) => {}
-				Packet::Disconnect(_) => return Ok(()),
+				Packet::ViewRadius(packet) => self.view_radius = Some(view_radius_chunks(packet.value)),
+				Packet::PlayerOptions(packet) => self.skin = packet.player_skin,
+				Packet::Disconnect(_) => return Ok(false),
 				packet => {
 					warn!("Unexpected setup packet {} from {}", packet.id(), self.username);
 				}
@@ -221,11 +1099,11 @@ impl PlayerConnection {
 	}
 
 	async fn handle_request_assets(&mut self, packet: RequestAssets) -> Result<()> {
-		let requested = packet.assets.unwrap_or_else(|| self.common_assets.required_assets());
+		let requested = packet.assets.unwrap_or_else(|| self.ctx.common_assets.required_assets());
 
 		for asset in requested {
 			let hash = asset.hash.to_string().to_lowercase();
-			let Some(common_asset) = self.common_assets.get_by_hash(&hash) else {
+			let Some(common_asset) = self.ctx.common_assets.get_by_hash(&hash) else {
 				warn!("Requested unknown common asset {} ({}).", asset.name, hash);
 				continue;
 			};
@@ -308,30 +1186,35 @@ impl PlayerConnection {
 	}
 
 	async fn read_packet(&mut self) -> Result<Packet> {
-		let len = self.recv.read_i32_le().await? as usize;
-		let id = self.recv.read_i32_le().await?;
-		trace!("Receiving packet id={} ({} bytes)", id, len);
+		let recv = self.recv.as_mut().ok_or(anyhow!("Packets are read by the reader task"))?;
+		read_packet(recv).await
+	}
+}
 
-		if len > 1677721600 {
-			bail!("Invalid Packet Length: {}", len);
-		}
+async fn read_packet(recv: &mut RecvStream) -> Result<Packet> {
+	let len = recv.read_i32_le().await? as usize;
+	let id = recv.read_i32_le().await?;
+	trace!("Receiving packet id={} ({} bytes)", id, len);
 
-		let mut buf = BytesMut::zeroed(len); // This can't be just `with_capacity` because its length would be 0, which is what's used in read_exact. That means 0 bytes will be read.
-		self.recv.read_exact(&mut buf).await?;
+	if len > 1677721600 {
+		bail!("Invalid Packet Length: {}", len);
+	}
 
-		let is_compressed = v2::is_id_compressed(id);
+	let mut buf = BytesMut::zeroed(len); // This can't be just `with_capacity` because its length would be 0, which is what's used in read_exact. That means 0 bytes will be read.
+	recv.read_exact(&mut buf).await?;
 
-		let mut final_data = if is_compressed && !buf.is_empty() {
-			let mut writer = BytesMut::with_capacity(buf.len() + 1024).writer();
-			zstd::stream::copy_decode(buf.reader(), &mut writer)?;
-			writer.into_inner().freeze()
-		} else {
-			buf.freeze()
-		};
+	let is_compressed = v2::is_id_compressed(id);
 
-		let packet = Packet::decode(id, &mut final_data)?;
-		trace!("Received packet {}", packet.id());
+	let mut final_data = if is_compressed && !buf.is_empty() {
+		let mut writer = BytesMut::with_capacity(buf.len() + 1024).writer();
+		zstd::stream::copy_decode(buf.reader(), &mut writer)?;
+		writer.into_inner().freeze()
+	} else {
+		buf.freeze()
+	};
 
-		Ok(packet)
-	}
+	let packet = Packet::decode(id, &mut final_data)?;
+	trace!("Received packet {}", packet.id());
+
+	Ok(packet)
 }
@@ -0,0 +1,69 @@
+use std::sync::{
+	Arc,
+	RwLock,
+};
+
+use command::CommandRegistry;
+use common_assets::CommonAssetStore;
+use protocol::v2::GameMode;
+use uuid::Uuid;
+use world::{
+	BlockRegistry,
+	EnvironmentRegistry,
+	FluidRegistry,
+	PlayerDataStore,
+	RecipeRegistry,
+	TickScheduler,
+	WeatherRegistry,
+	World,
+};
+
+use crate::{
+	auth::ServerAuthManager,
+	players::PlayerRegistry,
+};
+
+/// Server-side state shared by every connection.
+pub struct ServerContext {
+	pub auth: Arc<ServerAuthManager>,
+	pub common_assets: Arc<CommonAssetStore>,
+	pub blocks: Arc<BlockRegistry>,
+	pub fluids: Arc<FluidRegistry>,
+	pub weathers: Arc<WeatherRegistry>,
+	pub environments: Arc<EnvironmentRegistry>,
+	pub recipes: Arc<RecipeRegistry>,
+	pub world: Arc<World>,
+	pub ticks: Arc<TickScheduler>,
+	pub players: Arc<PlayerRegistry>,
+	pub player_data: Arc<PlayerDataStore>,
+	pub commands: Arc<RwLock<CommandRegistry>>,
+	pub options: GameplayOptions,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameplayOptions {
+	/// Upper bound for the view radius clients ask for, in chunks.
+	pub max_view_radius: i32,
+	/// Game mode of players joining the server.
+	pub default_game_mode: GameMode,
+	/// Names or UUIDs of the players allowed to use every command.
+	pub operators: Vec<String>,
+}
+
+impl GameplayOptions {
+	pub fn is_operator(&self, uuid: Uuid, username: &str) -> bool {
+		self.operators
+			.iter()
+			.any(|operator| operator.eq_ignore_ascii_case(username) || operator.parse::<Uuid>().is_ok_and(|operator| operator == uuid))
+	}
+}
+
+impl Default for GameplayOptions {
+	fn default() -> Self {
+		Self {
+			max_view_radius: 12,
+			default_game_mode: GameMode::Adventure,
+			operators: Vec::new(),
+		}
+	}
+}
@@ -0,0 +1,173 @@
+//! What a player gets between finishing the setup and spawning in the world.
+
+use protocol::v2::{
+	assets::{
+		UpdateAmbienceFX,
+		UpdateAudioCategories,
+		UpdateBlockBreakingDecals,
+		UpdateBlockGroups,
+		UpdateBlockHitboxes,
+		UpdateBlockParticleSets,
+		UpdateBlockSets,
+		UpdateBlockSoundSets,
+		UpdateCameraShake,
+		UpdateEntityEffects,
+		UpdateEntityStatTypes,
+		UpdateEntityUIComponents,
+		UpdateEqualizerEffects,
+		UpdateFieldcraftCategories,
+		UpdateFluidFX,
+		UpdateHitboxCollisionConfig,
+		UpdateInteractions,
+		UpdateItemCategories,
+		UpdateItemPlayerAnimations,
+		UpdateItemQualities,
+		UpdateItemReticles,
+		UpdateItemSoundSets,
+		UpdateItems,
+		UpdateModelVFXs,
+		UpdateParticleSpawners,
+		UpdateParticleSystems,
+		UpdateProjectileConfigs,
+		UpdateRepulsionConfig,
+		UpdateResourceTypes,
+		UpdateReverbEffects,
+		UpdateRootInteractions,
+		UpdateSoundEvents,
+		UpdateSoundSets,
+		UpdateTagPatterns,
+		UpdateTrails,
+		UpdateTranslations,
+		UpdateUnarmedInteractions,
+		UpdateViewBobbing,
+	},
+	MovementSettings,
+	Packet,
+	RangeF,
+	UpdateType,
+	Vector2f,
+};
+
+use crate::context::ServerContext;
+
+/// An `Init` update of a registry the server doesn't load yet, so the client starts out with an empty one.
+macro_rules! empty {
+	($packet:ident { $($field:ident: $value:expr),* $(,)? }) => {
+		Packet::from($packet {
+			update_type: UpdateType::Init,
+			$($field: $value),*
+		})
+	};
+}
+
+/// Every asset registry `Update*` packet, sent once the common assets are through. The client expects all of them
+/// before it enters a world, registries the server has nothing for are sent empty.
+pub fn asset_updates(ctx: &ServerContext) -> Vec<Packet> {
+	vec![
+		ctx.blocks.update_block_types().into(),
+		ctx.fluids.update_fluids().into(),
+		ctx.weathers.update_weathers().into(),
+		ctx.environments.update_environments().into(),
+		ctx.recipes.update_recipes().into(),
+		empty!(UpdateAmbienceFX { max_id: 0, ambience_fx: None }),
+		empty!(UpdateAudioCategories { max_id: 0, categories: None }),
+		empty!(UpdateBlockBreakingDecals { block_breaking_decals: None }),
+		empty!(UpdateBlockGroups { groups: None }),
+		empty!(UpdateBlockHitboxes { max_id: 0, hitboxes: None }),
+		empty!(UpdateBlockParticleSets { particle_sets: None }),
+		empty!(UpdateBlockSets { block_sets: None }),
+		empty!(UpdateBlockSoundSets { max_id: 0, sound_sets: None }),
+		empty!(UpdateCameraShake { profiles: None }),
+		empty!(UpdateEntityEffects { max_id: 0, effects: None }),
+		empty!(UpdateEntityStatTypes { max_id: 0, stat_types: None }),
+		empty!(UpdateEntityUIComponents { max_id: 0, components: None }),
+		empty!(UpdateEqualizerEffects { max_id: 0, effects: None }),
+		empty!(UpdateFieldcraftCategories { categories: None }),
+		empty!(UpdateFluidFX { max_id: 0, fluid_fx: None }),
+		empty!(UpdateHitboxCollisionConfig { max_id: 0, hitbox_collision_configs: None }),
+		empty!(UpdateInteractions { max_id: 0, interactions: None }),
+		empty!(UpdateItemCategories { categories: None }),
+		empty!(UpdateItemPlayerAnimations { animations: None }),
+		empty!(UpdateItemQualities { max_id: 0, qualities: None }),
+		empty!(UpdateItemReticles { max_id: 0, reticles: None }),
+		empty!(UpdateItems { update_models: false, update_icons: false, items: None, removed_items: None }),
+		empty!(UpdateItemSoundSets { max_id: 0, item_sound_sets: None }),
+		empty!(UpdateModelVFXs { max_id: 0, model_vfxs: None }),
+		empty!(UpdateParticleSpawners { particle_spawners: None, removed_particle_spawners: None }),
+		empty!(UpdateParticleSystems { particle_systems: None, removed_particle_systems: None }),
+		empty!(UpdateProjectileConfigs { projectile_configs: None, removed_projectile_configs: None }),
+		empty!(UpdateRepulsionConfig { max_id: 0, repulsion_configs: None }),
+		empty!(UpdateResourceTypes { resource_types: None }),
+		empty!(UpdateReverbEffects { max_id: 0, reverb_effects: None }),
+		empty!(UpdateRootInteractions { max_id: 0, root_interactions: None }),
+		empty!(UpdateSoundEvents { max_id: 0, sound_events: None }),
+		empty!(UpdateSoundSets { max_id: 0, sound_sets: None }),
+		empty!(UpdateTagPatterns { max_id: 0, tag_patterns: None }),
+		empty!(UpdateTrails { trails: None }),
+		empty!(UpdateTranslations { translations: None }),
+		empty!(UpdateUnarmedInteractions { unarmed_interactions: None }),
+		empty!(UpdateViewBobbing { profiles: None }),
+	]
+}
+
+/// Movement of a player without any effects, matching the client's own defaults.
+pub fn default_movement_settings() -> MovementSettings {
+	MovementSettings {
+		mass: 1.0,
+		drag_coefficient: 0.5,
+		inverted_gravity: false,
+		velocity_resistance: 0.242,
+		jump_force: 11.8,
+		swim_jump_force: 10.0,
+		jump_buffer_duration: 0.3,
+		jump_buffer_max_y_velocity: 3.0,
+		acceleration: 0.1,
+		air_drag_range: RangeF { min: 0.96, max: 0.995 },
+		air_drag_speed_range: RangeF { min: 8.0, max: 10.0 },
+		air_friction_range: RangeF { min: 0.02, max: 0.045 },
+		air_friction_speed_range: RangeF { min: 8.0, max: 10.0 },
+		air_speed_multiplier: 1.0,
+		air_control_speed_range: RangeF { min: 3.0, max: 10.0 },
+		air_control_multiplier_range: RangeF { min: 0.0, max: 3.13 },
+		combo_air_speed_multiplier: 1.05,
+		base_speed: 5.5,
+		climb_speed: 0.035,
+		climb_speed_lateral: 0.035,
+		climb_up_sprint_speed: 0.045,
+		climb_down_sprint_speed: 0.055,
+		horizontal_fly_speed: 10.32,
+		vertical_fly_speed: 10.32,
+		speed_multiplier_range: RangeF { min: 0.2, max: 2.0 },
+		wish_direction_gravity: Vector2f { x: 0.0, y: 0.0 },
+		wish_direction_weight: Vector2f { x: 0.0, y: 0.0 },
+		can_fly: false,
+		collision_expulsion_force: 0.04,
+		forward_walk_speed_multiplier: 0.3,
+		backward_walk_speed_multiplier: 0.3,
+		strafe_walk_speed_multiplier: 0.3,
+		forward_run_speed_multiplier: 1.0,
+		backward_run_speed_multiplier: 0.65,
+		strafe_run_speed_multiplier: 0.8,
+		forward_crouch_speed_multiplier: 0.55,
+		backward_crouch_speed_multiplier: 0.4,
+		strafe_crouch_speed_multiplier: 0.45,
+		forward_sprint_speed_multiplier: 1.65,
+		variable_jump_fall_force: 35.0,
+		fall_effect_duration: 0.6,
+		fall_jump_force: 7.0,
+		fall_momentum_loss: 0.1,
+		auto_jump_obstacle_speed_loss: 0.95,
+		auto_jump_obstacle_sprint_speed_loss: 0.75,
+		auto_jump_obstacle_effect_duration: 0.2,
+		auto_jump_obstacle_sprint_effect_duration: 0.1,
+		auto_jump_obstacle_max_angle: 45.0,
+		auto_jump_disable_jumping: true,
+		min_slide_entry_speed: 8.5,
+		slide_exit_speed: 2.5,
+		min_fall_speed_to_engage_roll: 21.0,
+		max_fall_speed_to_engage_roll: 31.0,
+		roll_start_speed_modifier: 2.5,
+		roll_exit_speed_modifier: 1.5,
+		roll_time_to_complete: 0.9,
+	}
+}
@@ -0,0 +1,129 @@
+use std::{
+	collections::VecDeque,
+	time::{
+		Duration,
+		SystemTime,
+		UNIX_EPOCH,
+	},
+};
+
+use protocol::v2::{
+	connection::{
+		Ping,
+		Pong,
+		PongType,
+	},
+	InstantData,
+};
+use tokio::time::Instant;
+
+/// How often a player is pinged.
+pub const PING_INTERVAL: Duration = Duration::from_secs(1);
+/// Pings a client may leave unanswered before the oldest ones are forgotten.
+const MAX_PENDING_PINGS: usize = 16;
+/// Weight of a new round trip in the smoothed average.
+const SMOOTHING: f64 = 0.125;
+
+/// How long a ping takes to come back, by how far into the client it got before being answered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Latency {
+	/// Answered as soon as the packet arrived.
+	pub raw: Option<Duration>,
+	/// Answered once the client got around to handling the packet.
+	pub direct: Option<Duration>,
+	/// Answered after the client's next game tick.
+	pub tick: Option<Duration>,
+	/// Smoothed raw round trip time.
+	pub average: Option<Duration>,
+}
+
+impl Latency {
+	/// The smoothed round trip time in whole milliseconds, for the player list.
+	pub fn average_millis(&self) -> Option<i32> {
+		self.average.map(|average| average.as_millis().try_into().unwrap_or(i32::MAX))
+	}
+}
+
+struct PendingPing {
+	id: i32,
+	sent: Instant,
+	/// Pong types answered so far, by their index.
+	answered: [bool; 3],
+}
+
+/// Pings a player and matches their pongs to work out the latency.
+///
+/// Clients answer every ping three times, see [`Latency`]. Each ping carries the previous measurements along, in
+/// microseconds, so the client knows its own latency too.
+#[derive(Default)]
+pub struct LatencyTracker {
+	next_id: i32,
+	pending: VecDeque<PendingPing>,
+	latency: Latency,
+}
+
+impl LatencyTracker {
+	pub fn latency(&self) -> Latency {
+		self.latency
+	}
+
+	/// Builds the next ping and starts waiting for its pongs.
+	pub fn ping(&mut self) -> Ping {
+		let id = self.next_id;
+		self.next_id = self.next_id.wrapping_add(1);
+		if self.pending.len() == MAX_PENDING_PINGS {
+			self.pending.pop_front();
+		}
+		self.pending.push_back(PendingPing {
+			id,
+			sent: Instant::now(),
+			answered: [false; 3],
+		});
+
+		let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+		Ping {
+			id,
+			time: Some(InstantData {
+				seconds: since_epoch.as_secs() as i64,
+				nanos: since_epoch.subsec_nanos() as i32,
+			}),
+			last_ping_raw: micros(self.latency.raw),
+			last_ping_direct: micros(self.latency.direct),
+			last_ping_tick: micros(self.latency.tick),
+		}
+	}
+
+	/// Records a pong. Returns `false` if it doesn't answer a ping that's still pending.
+	pub fn pong(&mut self, pong: &Pong) -> bool {
+		let Some(index) = self.pending.iter().position(|ping| ping.id == pong.id) else {
+			return false;
+		};
+		let ping = &mut self.pending[index];
+		let answered = &mut ping.answered[pong.pong_type as usize];
+		if *answered {
+			return false;
+		}
+		*answered = true;
+
+		let round_trip = ping.sent.elapsed();
+		match pong.pong_type {
+			PongType::Raw => {
+				self.latency.raw = Some(round_trip);
+				self.latency.average = Some(match self.latency.average {
+					Some(average) => average.mul_f64(1.0 - SMOOTHING) + round_trip.mul_f64(SMOOTHING),
+					None => round_trip,
+				});
+			}
+			PongType::Direct => self.latency.direct = Some(round_trip),
+			PongType::Tick => self.latency.tick = Some(round_trip),
+		}
+		if ping.answered.iter().all(|answered| *answered) {
+			self.pending.remove(index);
+		}
+		true
+	}
+}
+
+fn micros(latency: Option<Duration>) -> i32 {
+	latency.map_or(0, |latency| latency.as_micros().try_into().unwrap_or(i32::MAX))
+}
@@ -1,7 +1,16 @@
 pub mod api;
 pub mod auth;
 pub mod auth_store;
+pub mod building;
+pub mod chat;
+pub mod chunk_stream;
 pub mod connection;
+pub mod context;
+pub mod join;
+pub mod latency;
+pub mod movement;
 pub mod oauth;
+pub mod players;
+pub mod sender;
 pub mod server;
 pub mod tls;
@@ -0,0 +1,171 @@
+use protocol::v2::{
+	player::{
+		ClientMovement,
+		ClientTeleport,
+	},
+	DirectionF,
+	HalfFloatPosition,
+	ModelTransform,
+	MovementStates,
+	PositionF,
+	Vector3d,
+};
+
+/// Farthest a player may move with a single movement packet, in blocks. Anything beyond is sent back.
+const MAX_MOVE_DISTANCE: f64 = 64.0;
+
+/// Where a player is and which way they face, as far as the server is concerned.
+#[derive(Debug, Clone)]
+pub struct PlayerTransform {
+	pub position: PositionF,
+	pub body_orientation: DirectionF,
+	pub look_orientation: DirectionF,
+	pub velocity: Vector3d,
+	pub movement_states: Option<MovementStates>,
+}
+
+impl PlayerTransform {
+	/// Standing still at a position, facing north.
+	pub fn at(position: PositionF) -> Self {
+		Self {
+			position,
+			body_orientation: facing_north(),
+			look_orientation: facing_north(),
+			velocity: no_velocity(),
+			movement_states: None,
+		}
+	}
+
+	pub fn to_model_transform(&self) -> ModelTransform {
+		ModelTransform {
+			position: Some(self.position.clone()),
+			body_orientation: Some(self.body_orientation.clone()),
+			look_orientation: Some(self.look_orientation.clone()),
+		}
+	}
+}
+
+/// What came of a movement packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovementOutcome {
+	Moved,
+	/// Only the orientation or movement states changed.
+	Turned,
+	/// Sent before the client saw the latest teleport, so it was dropped.
+	Stale,
+	/// Not a move the player could have made, they should be put back.
+	Rejected,
+}
+
+/// Applies a player's movement to their server-side transform.
+///
+/// Teleports get an id the client acknowledges with its next movement. Until it does, whatever it sends was meant for
+/// the old position and is dropped.
+pub struct MovementTracker {
+	transform: PlayerTransform,
+	pending_teleport: Option<u8>,
+	next_teleport_id: u8,
+}
+
+impl MovementTracker {
+	pub fn new(position: PositionF) -> Self {
+		Self {
+			transform: PlayerTransform::at(position),
+			pending_teleport: None,
+			next_teleport_id: 0,
+		}
+	}
+
+	pub fn transform(&self) -> &PlayerTransform {
+		&self.transform
+	}
+
+	pub fn position(&self) -> &PositionF {
+		&self.transform.position
+	}
+
+	/// Moves the player and returns the teleport to send them, keeping their orientation.
+	pub fn teleport(&mut self, position: PositionF) -> ClientTeleport {
+		let teleport_id = self.next_teleport_id;
+		self.next_teleport_id = self.next_teleport_id.wrapping_add(1);
+		self.pending_teleport = Some(teleport_id);
+		self.transform.position = position;
+		self.transform.velocity = no_velocity();
+		ClientTeleport {
+			teleport_id,
+			model_transform: Some(self.transform.to_model_transform()),
+			reset_velocity: true,
+		}
+	}
+
+	pub fn apply(&mut self, packet: &ClientMovement) -> MovementOutcome {
+		if let Some(pending) = self.pending_teleport {
+			if packet.teleport_ack.as_ref().is_none_or(|ack| ack.teleport_id != pending) {
+				return MovementOutcome::Stale;
+			}
+			self.pending_teleport = None;
+		}
+
+		let position = match (&packet.absolute_position, &packet.relative_position) {
+			(Some(position), _) => Some(position.clone()),
+			(None, Some(relative)) => Some(offset(&self.transform.position, relative)),
+			(None, None) => None,
+		};
+		if let Some(position) = &position {
+			let (dx, dy, dz) = (position.x - self.transform.position.x, position.y - self.transform.position.y, position.z - self.transform.position.z);
+			if ![position.x, position.y, position.z].iter().all(|value| value.is_finite()) || dx * dx + dy * dy + dz * dz > MAX_MOVE_DISTANCE * MAX_MOVE_DISTANCE {
+				return MovementOutcome::Rejected;
+			}
+		}
+
+		if let Some(body_orientation) = &packet.body_orientation {
+			self.transform.body_orientation = body_orientation.clone();
+		}
+		if let Some(look_orientation) = &packet.look_orientation {
+			self.transform.look_orientation = look_orientation.clone();
+		}
+		if let Some(velocity) = &packet.velocity {
+			self.transform.velocity = velocity.clone();
+		}
+		if let Some(movement_states) = &packet.movement_states {
+			self.transform.movement_states = Some(movement_states.clone());
+		}
+		match position {
+			Some(position) => {
+				self.transform.position = position;
+				MovementOutcome::Moved
+			}
+			None => MovementOutcome::Turned,
+		}
+	}
+}
+
+fn facing_north() -> DirectionF {
+	DirectionF { yaw: 0.0, pitch: 0.0, roll: 0.0 }
+}
+
+fn no_velocity() -> Vector3d {
+	Vector3d { x: 0.0, y: 0.0, z: 0.0 }
+}
+
+fn offset(position: &PositionF, relative: &HalfFloatPosition) -> PositionF {
+	PositionF {
+		x: position.x + half_to_f64(relative.x),
+		y: position.y + half_to_f64(relative.y),
+		z: position.z + half_to_f64(relative.z),
+	}
+}
+
+/// Decodes the bits of an IEEE 754 half precision float.
+fn half_to_f64(bits: i16) -> f64 {
+	let bits = bits as u16;
+	let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+	let exponent = ((bits >> 10) & 0x1F) as i32;
+	let mantissa = (bits & 0x3FF) as f64;
+	sign * match exponent {
+		0 => mantissa * 2f64.powi(-24),
+		0x1F if mantissa == 0.0 => f64::INFINITY,
+		0x1F => f64::NAN,
+		_ => (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+	}
+}
@@ -0,0 +1,317 @@
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{
+			AtomicU64,
+			Ordering,
+		},
+		Arc,
+	},
+	time::Duration,
+};
+
+use parking_lot::{
+	Mutex,
+	RwLock,
+};
+use protocol::v2::{
+	interface::{
+		AddToServerPlayerList,
+		RemoveFromServerPlayerList,
+		ServerPlayerListPlayer,
+		ServerPlayerListUpdate,
+		UpdateServerPlayerList,
+		UpdateServerPlayerListPing,
+	},
+	GameMode,
+	Packet,
+	PositionF,
+};
+use quinn::Connection;
+use thiserror::Error;
+use tokio::sync::{
+	mpsc,
+	oneshot,
+};
+use uuid::Uuid;
+use world::Window;
+
+use crate::{
+	latency::Latency,
+	movement::PlayerTransform,
+};
+
+/// How long a kicked session gets to say goodbye before its connection is closed.
+const KICK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something another task asks a player's connection to do.
+#[derive(Debug)]
+pub enum PlayerMessage {
+	Packet(Box<Packet>),
+	Kick(String),
+	Teleport(PositionF),
+	SetGameMode(GameMode),
+	/// Saves the player's data, as the server does every now and then. The sender is dropped once it's saved.
+	Save(oneshot::Sender<()>),
+	/// Opens a window, which is handed back through the sender once it's closed.
+	OpenWindow(Box<Window>, oneshot::Sender<Window>),
+}
+
+#[derive(Debug, Error)]
+pub enum PlayerRegistryError {
+	#[error("A different player named {0} is already online")]
+	NameTaken(String),
+}
+
+/// The way into the connection of an online player.
+#[derive(Debug, Clone)]
+pub struct PlayerHandle {
+	uuid: Uuid,
+	username: String,
+	/// Tells sessions of the same player apart.
+	session: u64,
+	conn: Connection,
+	messages: mpsc::UnboundedSender<PlayerMessage>,
+	/// World the player is in, they're on the player list once they have one.
+	world: Arc<Mutex<Option<Uuid>>>,
+	/// Latest measurements of the connection's latency tracker.
+	latency: Arc<Mutex<Latency>>,
+	/// Where the player is, updated as their movement is applied.
+	transform: Arc<Mutex<PlayerTransform>>,
+	/// The player's game mode, updated once their connection switched to it.
+	game_mode: Arc<Mutex<GameMode>>,
+}
+
+impl PlayerHandle {
+	pub fn uuid(&self) -> Uuid {
+		self.uuid
+	}
+
+	pub fn username(&self) -> &str {
+		&self.username
+	}
+
+	pub fn world(&self) -> Option<Uuid> {
+		*self.world.lock()
+	}
+
+	pub fn latency(&self) -> Latency {
+		*self.latency.lock()
+	}
+
+	pub(crate) fn set_latency(&self, latency: Latency) {
+		*self.latency.lock() = latency;
+	}
+
+	pub fn transform(&self) -> PlayerTransform {
+		self.transform.lock().clone()
+	}
+
+	pub(crate) fn set_transform(&self, transform: &PlayerTransform) {
+		self.transform.lock().clone_from(transform);
+	}
+
+	pub fn game_mode(&self) -> GameMode {
+		*self.game_mode.lock()
+	}
+
+	pub(crate) fn set_game_mode(&self, game_mode: GameMode) {
+		*self.game_mode.lock() = game_mode;
+	}
+
+	/// Round trip time to the player in milliseconds, as measured by QUIC until the first pong came back.
+	pub fn ping(&self) -> i32 {
+		self.latency().average_millis().unwrap_or_else(|| self.conn.rtt().as_millis().try_into().unwrap_or(i32::MAX))
+	}
+
+	fn list_entry(&self) -> ServerPlayerListPlayer {
+		ServerPlayerListPlayer {
+			uuid: self.uuid,
+			world_uuid: self.world(),
+			pin: self.ping(),
+			username: Some(self.username.clone()),
+		}
+	}
+
+	/// Queues a packet for the player. Returns `false` if they're already gone.
+	pub fn send(&self, packet: impl Into<Packet>) -> bool {
+		self.messages.send(PlayerMessage::Packet(Box::new(packet.into()))).is_ok()
+	}
+
+	/// Moves the player to a position in their world.
+	pub fn teleport(&self, position: PositionF) -> bool {
+		self.messages.send(PlayerMessage::Teleport(position)).is_ok()
+	}
+
+	/// Switches the player to another game mode.
+	pub fn change_game_mode(&self, game_mode: GameMode) -> bool {
+		self.messages.send(PlayerMessage::SetGameMode(game_mode)).is_ok()
+	}
+
+	/// Has the player's data saved without waiting for them to leave. The receiver completes once it's saved, either
+	/// by this or by the player leaving.
+	pub fn save(&self) -> oneshot::Receiver<()> {
+		let (saved, receiver) = oneshot::channel();
+		// Unsent messages are dropped right away, and queued ones only after the data was saved on leave
+		let _ = self.messages.send(PlayerMessage::Save(saved));
+		receiver
+	}
+
+	/// Opens a window for the player. It comes back through the receiver once they close it or leave, along with the
+	/// items it holds by then. If they're already gone, it's returned right away.
+	pub fn open_window(&self, window: Window) -> Result<oneshot::Receiver<Window>, Window> {
+		let (owner, closed) = oneshot::channel();
+		match self.messages.send(PlayerMessage::OpenWindow(Box::new(window), owner)) {
+			Ok(()) => Ok(closed),
+			Err(mpsc::error::SendError(PlayerMessage::OpenWindow(window, _))) => Err(*window),
+			Err(_) => unreachable!("only a window was sent"),
+		}
+	}
+
+	/// Disconnects the player and waits for their session to end, which is only once their data is saved. The connection
+	/// is closed outright if the session doesn't end in time.
+	pub async fn kick(&self, reason: impl Into<String>) {
+		if self.messages.send(PlayerMessage::Kick(reason.into())).is_ok() && tokio::time::timeout(KICK_TIMEOUT, self.messages.closed()).await.is_ok() {
+			return;
+		}
+		self.conn.close(0u32.into(), b"Kicked");
+		// The session still saves once it notices the connection is gone
+		let _ = tokio::time::timeout(KICK_TIMEOUT, self.messages.closed()).await;
+	}
+}
+
+/// Every player online on the server, by UUID and by name.
+///
+/// Names are matched case-insensitively. A player logging in again replaces their older session, while a different
+/// player can't log in under a name that's already taken. The registry also keeps everyone's player list up to date as
+/// players enter worlds and leave.
+#[derive(Default)]
+pub struct PlayerRegistry {
+	players: RwLock<HashMap<Uuid, PlayerHandle>>,
+	/// UUIDs by lowercase name.
+	names: RwLock<HashMap<String, Uuid>>,
+	next_session: AtomicU64,
+}
+
+impl PlayerRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a player that just logged in and returns their handle, the messages for their connection and the older
+	/// session of the same player, which the caller should kick.
+	pub fn register(
+		&self,
+		uuid: Uuid,
+		username: &str,
+		conn: Connection,
+	) -> Result<(PlayerHandle, mpsc::UnboundedReceiver<PlayerMessage>, Option<PlayerHandle>), PlayerRegistryError> {
+		let mut players = self.players.write();
+		let mut names = self.names.write();
+		let name = username.to_lowercase();
+		if names.get(&name).is_some_and(|online| *online != uuid) {
+			return Err(PlayerRegistryError::NameTaken(username.to_string()));
+		}
+
+		let (messages, receiver) = mpsc::unbounded_channel();
+		let handle = PlayerHandle {
+			uuid,
+			username: username.to_string(),
+			session: self.next_session.fetch_add(1, Ordering::Relaxed),
+			conn,
+			messages,
+			world: Arc::default(),
+			latency: Arc::default(),
+			transform: Arc::new(Mutex::new(PlayerTransform::at(PositionF { x: 0.0, y: 0.0, z: 0.0 }))),
+			game_mode: Arc::new(Mutex::new(GameMode::Adventure)),
+		};
+		let replaced = players.insert(uuid, handle.clone());
+		if let Some(replaced) = &replaced {
+			names.remove(&replaced.username.to_lowercase());
+		}
+		names.insert(name, uuid);
+		drop((players, names));
+
+		if replaced.as_ref().is_some_and(|replaced| replaced.world().is_some()) {
+			self.broadcast(RemoveFromServerPlayerList { players: Some(vec![uuid]) });
+		}
+		Ok((handle, receiver, replaced))
+	}
+
+	/// Removes a player whose connection ended, unless a newer session of theirs already took over.
+	pub fn unregister(&self, handle: &PlayerHandle) {
+		let mut players = self.players.write();
+		if players.get(&handle.uuid).is_none_or(|online| online.session != handle.session) {
+			return;
+		}
+		players.remove(&handle.uuid);
+		self.names.write().remove(&handle.username.to_lowercase());
+		drop(players);
+
+		if handle.world().is_some() {
+			self.broadcast(RemoveFromServerPlayerList {
+				players: Some(vec![handle.uuid]),
+			});
+		}
+	}
+
+	/// Moves a player into a world. Entering the first one puts them on everyone's player list and sends them the
+	/// whole list.
+	pub fn set_world(&self, handle: &PlayerHandle, world: Uuid) {
+		let previous = handle.world.lock().replace(world);
+		if previous.is_some() {
+			self.broadcast(UpdateServerPlayerList {
+				players: Some(vec![ServerPlayerListUpdate { uuid: handle.uuid, world_uuid: world }]),
+			});
+			return;
+		}
+
+		let listed: Vec<PlayerHandle> = self.all().into_iter().filter(|player| player.world().is_some()).collect();
+		for player in &listed {
+			if player.uuid != handle.uuid {
+				player.send(AddToServerPlayerList {
+					players: Some(vec![handle.list_entry()]),
+				});
+			}
+		}
+		handle.send(AddToServerPlayerList {
+			players: Some(listed.iter().map(PlayerHandle::list_entry).collect()),
+		});
+	}
+
+	/// The current ping of everyone on the player list.
+	pub fn ping_update(&self) -> UpdateServerPlayerListPing {
+		let players = self.players.read().values().filter(|player| player.world().is_some()).map(|player| (player.uuid, player.ping())).collect();
+		UpdateServerPlayerListPing { players: Some(players) }
+	}
+
+	pub fn get(&self, uuid: Uuid) -> Option<PlayerHandle> {
+		self.players.read().get(&uuid).cloned()
+	}
+
+	pub fn get_by_name(&self, username: &str) -> Option<PlayerHandle> {
+		let uuid = *self.names.read().get(&username.to_lowercase())?;
+		self.get(uuid)
+	}
+
+	pub fn len(&self) -> usize {
+		self.players.read().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.players.read().is_empty()
+	}
+
+	/// A snapshot of everyone online, so the registry isn't locked while going through them.
+	pub fn all(&self) -> Vec<PlayerHandle> {
+		self.players.read().values().cloned().collect()
+	}
+
+	/// Sends a packet to everyone online.
+	pub fn broadcast(&self, packet: impl Into<Packet>) {
+		let packet = packet.into();
+		for player in self.players.read().values() {
+			player.send(packet.clone());
+		}
+	}
+}
@@ -0,0 +1,49 @@
+use std::any::Any;
+
+use command::CommandSender;
+
+use crate::{
+	chat,
+	players::PlayerHandle,
+};
+
+/// Permissions every player has, operators have all of them.
+const DEFAULT_PERMISSIONS: &[&str] = &["command.help", "command.list", "command.ping"];
+
+/// Runs commands on behalf of a player, answering in their chat.
+pub struct PlayerSender {
+	player: PlayerHandle,
+	operator: bool,
+}
+
+impl PlayerSender {
+	pub fn new(player: PlayerHandle, operator: bool) -> Self {
+		Self { player, operator }
+	}
+
+	pub fn player(&self) -> &PlayerHandle {
+		&self.player
+	}
+}
+
+impl CommandSender for PlayerSender {
+	fn send_message(&self, msg: &str) {
+		self.player.send(chat::system_message(msg));
+	}
+
+	fn send_error(&self, msg: &str) {
+		self.player.send(chat::error_message(msg));
+	}
+
+	fn has_permission(&self, node: &str) -> bool {
+		self.operator || DEFAULT_PERMISSIONS.contains(&node)
+	}
+
+	fn name(&self) -> &str {
+		self.player.username()
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
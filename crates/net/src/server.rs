@@ -9,7 +9,6 @@ use anyhow::{
 	Context,
 	Result,
 };
-use common_assets::CommonAssetStore;
 use quinn::{
 	crypto::rustls::QuicServerConfig,
 	Endpoint,
@@ -22,8 +21,8 @@ use tracing::{
 };
 
 use crate::{
-	auth::ServerAuthManager,
 	connection::PlayerConnection,
+	context::ServerContext,
 	tls::{
 		AllowAnyClientCertVerifier,
 		ServerCert,
@@ -32,8 +31,7 @@ use crate::{
 
 pub struct QuicServer {
 	endpoint: Endpoint,
-	auth_manager: Arc<ServerAuthManager>,
-	common_assets: Arc<CommonAssetStore>,
+	ctx: Arc<ServerContext>,
 }
 
 #[derive(Clone, Debug)]
@@ -52,7 +50,7 @@ impl Default for QuicServerOptions {
 }
 
 impl QuicServer {
-	pub async fn bind(addr: SocketAddr, cert: ServerCert, auth_manager: Arc<ServerAuthManager>, common_assets: Arc<CommonAssetStore>, options: QuicServerOptions) -> Result<Self> {
+	pub async fn bind(addr: SocketAddr, cert: ServerCert, ctx: Arc<ServerContext>, options: QuicServerOptions) -> Result<Self> {
 		info!("Setting up QUIC transport...");
 
 		let ServerCert { chain, key, fingerprint: _ } = cert;
@@ -78,11 +76,7 @@ impl QuicServer {
 
 		info!("QUIC Listener bound to {}", endpoint.local_addr()?);
 
-		Ok(Self {
-			endpoint,
-			auth_manager,
-			common_assets,
-		})
+		Ok(Self { endpoint, ctx })
 	}
 
 	/// The main accept loop. Blocks until the server shuts down.
@@ -90,11 +84,10 @@ impl QuicServer {
 		info!("Ready to accept connections.");
 
 		while let Some(connecting) = self.endpoint.accept().await {
-			let auth = self.auth_manager.clone();
-			let common_assets = self.common_assets.clone();
+			let ctx = self.ctx.clone();
 
 			tokio::spawn(async move {
-				if let Err(e) = handle_connection(connecting, auth, common_assets).await {
+				if let Err(e) = handle_connection(connecting, ctx).await {
 					error!("Connection terminated with error: {}", e);
 				}
 			});
@@ -110,7 +103,7 @@ impl QuicServer {
 }
 
 /// Handles the lifecycle of a single player connection
-async fn handle_connection(connecting: quinn::Incoming, auth: Arc<ServerAuthManager>, common_assets: Arc<CommonAssetStore>) -> Result<()> {
+async fn handle_connection(connecting: quinn::Incoming, ctx: Arc<ServerContext>) -> Result<()> {
 	let connection = connecting.await?;
 	let remote_addr = connection.remote_address();
 
@@ -118,7 +111,7 @@ async fn handle_connection(connecting: quinn::Incoming, auth: Arc<ServerAuthMana
 
 	let (send_stream, recv_stream) = connection.accept_bi().await.context("Failed to open bidirectional stream")?;
 
-	let player_conn = PlayerConnection::new(connection, send_stream, recv_stream, auth, common_assets);
+	let player_conn = PlayerConnection::new(connection, send_stream, recv_stream, ctx);
 
 	player_conn.run().await?;
 
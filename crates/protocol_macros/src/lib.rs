@@ -88,11 +88,11 @@ pub fn derive_fixed_size(input: TokenStream) -> TokenStream {
 /// Defines a packet.
 /// There are 3 different forms;
 /// 1. Empty packet:
-/// ```
+/// ```ignore
 /// define_packet! { PacketName }
 /// ```
 /// 2. Packet with only fixed-size and always required fields:
-/// ```
+/// ```ignore
 /// define_packet! {
 ///   // All fields are required, and must implement FixedSize. This can also be done with a fixed block wrapping the fields and `required` keyword in front of each field, but this is just more concise.
 ///   PacketName {
@@ -102,7 +102,7 @@ pub fn derive_fixed_size(input: TokenStream) -> TokenStream {
 /// }
 /// ```
 /// 3. Packet with fixed and variable fields:
-/// ```
+/// ```ignore
 /// define_packet! {
 ///   PacketName {
 ///     // All fields in the fixed block must implement FixedSize.
@@ -127,7 +127,7 @@ pub fn derive_fixed_size(input: TokenStream) -> TokenStream {
 ///  - `B` is the index of the byte in the byte vec if there's more than 1 byte. This is 0-indexed as arrays are in Rust.
 ///
 /// When there are multiple bytes, the number of bytes needed for the struct is inferred from the highest null byte used in all `opt`s, but can also be manually specified like so:
-/// ```
+/// ```ignore
 /// define_packet! {
 ///   PacketName {
 ///     mask_bytes = 2
@@ -138,7 +138,7 @@ pub fn derive_fixed_size(input: TokenStream) -> TokenStream {
 /// ```
 ///
 /// Similarly, the padding for each optional field in the required block can be manually specified like so:
-/// ```
+/// ```ignore
 /// define_packet! {
 ///   PacketName {
 ///     fixed {
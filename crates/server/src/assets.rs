@@ -1,10 +1,48 @@
 //! Asset pack loaders used by the server.t
 
-use std::path::Path;
+use std::{
+	collections::{
+		BTreeMap,
+		HashMap,
+	},
+	fs::File,
+	io::Read,
+	path::Path,
+};
 
 use anyhow::{Context, Result};
+use assets::{
+	InputRef,
+	WithInput,
+};
 use common_assets::CommonAssetStore;
-use tracing::info;
+use tracing::{
+	info,
+	warn,
+};
+use walkdir::WalkDir;
+use world::{
+	BlockRegistry,
+	BlockTypeAsset,
+	CraftingItem,
+	EnvironmentAsset,
+	EnvironmentRegistry,
+	FluidRegistry,
+	FluidTypeAsset,
+	RecipeRegistry,
+	WeatherAsset,
+	WeatherRegistry,
+};
+use zip::ZipArchive;
+
+/// Where item definitions, which include the block types, live inside an asset pack.
+const ITEMS_DIR: &str = "Server/Item/Items";
+/// Where fluid definitions live inside an asset pack.
+const FLUIDS_DIR: &str = "Server/Item/Block/Fluids";
+/// Where weather definitions live inside an asset pack.
+const WEATHERS_DIR: &str = "Server/Weathers";
+/// Where environment definitions live inside an asset pack.
+const ENVIRONMENTS_DIR: &str = "Server/Environments";
 
 pub fn load_common_assets(pack_root: &Path) -> Result<CommonAssetStore> {
 	let mut store = CommonAssetStore::new();
@@ -15,3 +53,136 @@ pub fn load_common_assets(pack_root: &Path) -> Result<CommonAssetStore> {
 
 	Ok(store)
 }
+
+/// Loads the block types of the pack, keeping the ids of a previously saved name→id mapping.
+pub fn load_block_registry(pack_root: &Path, saved_ids: &BTreeMap<String, i32>) -> Result<BlockRegistry> {
+	let mut block_types = Vec::new();
+	for (input, name, bytes) in read_pack_json(pack_root, ITEMS_DIR)? {
+		match BlockTypeAsset::from_item_json(name, &bytes) {
+			Ok(Some(block_type)) => block_types.push(WithInput::new(input, block_type)),
+			Ok(None) => {}
+			Err(e) => warn!("Skipping item {:?}: {}", input, e),
+		}
+	}
+
+	let mut registry = BlockRegistry::new(saved_ids);
+	registry.load(block_types).with_context(|| "Failed to load block types")?;
+	info!("Loaded {} block types from {}", registry.len(), pack_root.display());
+
+	Ok(registry)
+}
+
+/// Loads the fluids of the pack, keeping the ids of a previously saved name→id mapping.
+pub fn load_fluid_registry(pack_root: &Path, saved_ids: &BTreeMap<String, i32>, blocks: &BlockRegistry) -> Result<FluidRegistry> {
+	let mut fluids = Vec::new();
+	for (input, name, bytes) in read_pack_json(pack_root, FLUIDS_DIR)? {
+		match FluidTypeAsset::from_json(name, &bytes) {
+			Ok(fluid) => fluids.push(WithInput::new(input, fluid)),
+			Err(e) => warn!("Skipping fluid {:?}: {}", input, e),
+		}
+	}
+
+	let mut registry = FluidRegistry::new(saved_ids);
+	registry.load(fluids, blocks).with_context(|| "Failed to load fluids")?;
+	info!("Loaded {} fluids from {}", registry.len(), pack_root.display());
+
+	Ok(registry)
+}
+
+/// Loads the recipes the items of the pack define, along with the resource types the items count as.
+pub fn load_recipe_registry(pack_root: &Path) -> Result<RecipeRegistry> {
+	let mut recipes = Vec::new();
+	let mut resource_types = HashMap::new();
+	for (input, name, bytes) in read_pack_json(pack_root, ITEMS_DIR)? {
+		match CraftingItem::from_item_json(name.clone(), &bytes) {
+			Ok(item) => {
+				if let Some(recipe) = item.recipe {
+					recipes.push(WithInput::new(input, recipe));
+				}
+				if !item.resource_types.is_empty() {
+					resource_types.insert(name, item.resource_types);
+				}
+			}
+			Err(e) => warn!("Skipping recipe of item {:?}: {}", input, e),
+		}
+	}
+
+	let mut registry = RecipeRegistry::new();
+	registry.load(recipes, resource_types).with_context(|| "Failed to load recipes")?;
+	info!("Loaded {} recipes from {}", registry.len(), pack_root.display());
+
+	Ok(registry)
+}
+
+pub fn load_weather_registry(pack_root: &Path) -> Result<WeatherRegistry> {
+	let mut weathers = Vec::new();
+	for (input, name, bytes) in read_pack_json(pack_root, WEATHERS_DIR)? {
+		match WeatherAsset::from_json(name, &bytes) {
+			Ok(weather) => weathers.push(WithInput::new(input, weather)),
+			Err(e) => warn!("Skipping weather {:?}: {}", input, e),
+		}
+	}
+
+	let mut registry = WeatherRegistry::new();
+	registry.load(weathers).with_context(|| "Failed to load weathers")?;
+	info!("Loaded {} weathers from {}", registry.len(), pack_root.display());
+
+	Ok(registry)
+}
+
+/// Loads the environments of the pack, keeping the ids of a previously saved name→id mapping and giving id 0 to
+/// `default`.
+pub fn load_environment_registry(pack_root: &Path, saved_ids: &BTreeMap<String, i32>, default: &str, weathers: &WeatherRegistry) -> Result<EnvironmentRegistry> {
+	let mut environments = Vec::new();
+	for (input, name, bytes) in read_pack_json(pack_root, ENVIRONMENTS_DIR)? {
+		match EnvironmentAsset::from_json(name, &bytes) {
+			Ok(environment) => environments.push(WithInput::new(input, environment)),
+			Err(e) => warn!("Skipping environment {:?}: {}", input, e),
+		}
+	}
+
+	let mut registry = EnvironmentRegistry::new(saved_ids, default);
+	registry.load(environments, weathers).with_context(|| "Failed to load environments")?;
+	if registry.get_by_id(0).is_none() {
+		warn!("Default environment {} doesn't exist, columns without an environment won't have weather", default);
+	}
+	info!("Loaded {} environments from {}", registry.len(), pack_root.display());
+
+	Ok(registry)
+}
+
+/// Reads every JSON file below `dir` in a pack directory or zip, with the file name as the asset name.
+fn read_pack_json(pack_root: &Path, dir: &str) -> Result<Vec<(InputRef, String, Vec<u8>)>> {
+	let mut files = Vec::new();
+	if pack_root.is_dir() {
+		for entry in WalkDir::new(pack_root.join(dir)).into_iter().filter_map(Result::ok) {
+			let path = entry.path();
+			if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "json") {
+				continue;
+			}
+			let Some(name) = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()) else {
+				continue;
+			};
+			let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+			files.push((InputRef::path(path.to_path_buf()), name, bytes));
+		}
+	} else {
+		let file = File::open(pack_root).with_context(|| format!("Failed to open asset pack {}", pack_root.display()))?;
+		let mut archive = ZipArchive::new(file).with_context(|| "Failed to read asset pack")?;
+		let prefix = format!("{}/", dir);
+		for i in 0..archive.len() {
+			let mut entry = archive.by_index(i)?;
+			let path = entry.name().replace('\\', "/");
+			if entry.is_dir() || !path.starts_with(&prefix) {
+				continue;
+			}
+			let Some(name) = path.rsplit('/').next().and_then(|file| file.strip_suffix(".json")).map(str::to_string) else {
+				continue;
+			};
+			let mut bytes = Vec::new();
+			entry.read_to_end(&mut bytes)?;
+			files.push((InputRef::label(path), name, bytes));
+		}
+	}
+	Ok(files)
+}
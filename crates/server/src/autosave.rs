@@ -0,0 +1,61 @@
+use std::{
+	sync::{
+		atomic::{
+			AtomicBool,
+			Ordering,
+		},
+		Arc,
+	},
+	time::Duration,
+};
+
+use parking_lot::Mutex;
+use tokio::time::Instant;
+use tracing::{
+	debug,
+	warn,
+};
+use world::{
+	Tickable,
+	World,
+};
+
+/// Saves the chunks of a world that changed since they were last saved, once `interval` of real time has passed since
+/// the last save. It's checked every tick, while the save itself runs in the background and never overlaps another.
+pub struct ChunkAutosave {
+	world: Arc<World>,
+	interval: Duration,
+	last: Mutex<Instant>,
+	saving: Arc<AtomicBool>,
+}
+
+impl ChunkAutosave {
+	pub fn new(world: Arc<World>, interval: Duration) -> Self {
+		Self {
+			world,
+			interval,
+			last: Mutex::new(Instant::now()),
+			saving: Arc::new(AtomicBool::new(false)),
+		}
+	}
+}
+
+impl Tickable for ChunkAutosave {
+	fn tick(&self, _elapsed: Duration) {
+		let mut last = self.last.lock();
+		if last.elapsed() < self.interval || self.saving.swap(true, Ordering::AcqRel) {
+			return;
+		}
+		*last = Instant::now();
+
+		let (world, saving) = (self.world.clone(), self.saving.clone());
+		tokio::spawn(async move {
+			match world.save_unsaved().await {
+				Ok(0) => {}
+				Ok(count) => debug!("Autosaved {} chunks", count),
+				Err(e) => warn!("Failed to autosave chunks: {}", e),
+			}
+			saving.store(false, Ordering::Release);
+		});
+	}
+}
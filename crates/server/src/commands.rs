@@ -1,3 +1,10 @@
 pub mod auth;
+pub mod gamemode;
 pub mod help;
+pub mod list;
+pub mod ping;
 pub mod stop;
+pub mod tick;
+pub mod time;
+pub mod tp;
+pub mod weather;
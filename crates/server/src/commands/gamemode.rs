@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use anyhow::{
+	anyhow,
+	Result,
+};
+use command::{
+	command,
+	CommandContext,
+	CommandRegistry,
+};
+use net::{
+	players::{
+		PlayerHandle,
+		PlayerRegistry,
+	},
+	sender::PlayerSender,
+};
+
+use crate::options::parse_game_mode;
+
+pub fn register(registry: &mut CommandRegistry, players: Arc<PlayerRegistry>) {
+	command!(registry, "gamemode", {
+		argument "mode" (String) {
+			argument "player" (String) executes move |ctx| {
+				let name = ctx.arg::<String>("player")?;
+				let player = players.get_by_name(name).ok_or_else(|| anyhow!("{} isn't online", name))?;
+				switch(ctx, &player)
+			},
+
+			executes move |ctx| {
+				let sender = ctx.sender.as_any().downcast_ref::<PlayerSender>().ok_or_else(|| anyhow!("Name the player to switch"))?;
+				switch(ctx, sender.player())
+			}
+		}
+
+		executes move |ctx| {
+			let sender = ctx.sender.as_any().downcast_ref::<PlayerSender>().ok_or_else(|| anyhow!("Name a game mode"))?;
+			ctx.sender.send_message(&format!("You're in {:?} mode", sender.player().game_mode()));
+			Ok(())
+		}
+	});
+}
+
+fn switch(ctx: &CommandContext, player: &PlayerHandle) -> Result<()> {
+	let game_mode = parse_game_mode(ctx.arg::<String>("mode")?)?;
+	if !player.change_game_mode(game_mode) {
+		return Err(anyhow!("{} just left", player.username()));
+	}
+	ctx.sender.send_message(&format!("Switched {} to {:?} mode", player.username(), game_mode));
+	Ok(())
+}
@@ -7,7 +7,12 @@ pub fn register(registry: &mut CommandRegistry) {
 	command!(registry, "help", {
 		executes move |ctx| {
 			let sender = ctx.sender.clone();
-			let commands = ctx.registry.root_commands();
+			let commands: Vec<String> = ctx
+				.registry
+				.root_commands()
+				.into_iter()
+				.filter(|cmd| sender.has_permission(&CommandRegistry::permission_node(cmd)))
+				.collect();
 			if commands.is_empty() {
 				sender.send_message("No commands registered.");
 			} else {
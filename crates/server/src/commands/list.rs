@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use command::{
+	command,
+	CommandRegistry,
+};
+use net::players::PlayerRegistry;
+
+pub fn register(registry: &mut CommandRegistry, players: Arc<PlayerRegistry>) {
+	command!(registry, "list", {
+		executes move |ctx| {
+			let mut names: Vec<String> = players.all().iter().map(|player| player.username().to_string()).collect();
+			names.sort_by_key(|name| name.to_lowercase());
+			if names.is_empty() {
+				ctx.sender.send_message("Nobody is online");
+			} else {
+				ctx.sender.send_message(&format!("{} online: {}", names.len(), names.join(", ")));
+			}
+			Ok(())
+		}
+	});
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use command::{
+	command,
+	CommandRegistry,
+	CommandSender,
+};
+use net::{
+	latency::Latency,
+	players::{
+		PlayerHandle,
+		PlayerRegistry,
+	},
+	sender::PlayerSender,
+};
+
+pub fn register(registry: &mut CommandRegistry, players: Arc<PlayerRegistry>) {
+	command!(registry, "ping", {
+		argument "player" (String) executes move |ctx| {
+			let name = ctx.arg::<String>("player")?;
+			let player = players.get_by_name(name).ok_or_else(|| anyhow!("{} isn't online", name))?;
+			report(ctx.sender.as_ref(), &player);
+			Ok(())
+		},
+
+		executes move |ctx| {
+			let sender = ctx.sender.as_any().downcast_ref::<PlayerSender>().ok_or_else(|| anyhow!("Name the player to ping"))?;
+			report(ctx.sender.as_ref(), sender.player());
+			Ok(())
+		}
+	});
+}
+
+fn report(sender: &dyn CommandSender, player: &PlayerHandle) {
+	let Latency { raw, direct, tick, average } = player.latency();
+	let millis = |latency: Option<std::time::Duration>| latency.map_or("?".to_string(), |latency| format!("{:.1}ms", latency.as_secs_f64() * 1000.0));
+	sender.send_message(&format!(
+		"{}: {} average ({} raw, {} direct, {} tick)",
+		player.username(),
+		millis(average),
+		millis(raw),
+		millis(direct),
+		millis(tick)
+	));
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use command::{
+	command,
+	CommandRegistry,
+};
+use world::{
+	TickScheduler,
+	MAX_TICKS_PER_SECOND,
+	TIME_DILATION_RANGE,
+};
+
+pub fn register(registry: &mut CommandRegistry, ticks: Arc<TickScheduler>) {
+	let ticks_query = ticks.clone();
+	let ticks_rate = ticks.clone();
+	let ticks_dilation = ticks;
+	command!(registry, "tick", {
+		literal "rate" {
+			argument "tps" (i32) executes move |ctx| {
+				let tps = *ctx.arg::<i32>("tps")?;
+				if !(1..=MAX_TICKS_PER_SECOND as i32).contains(&tps) {
+					bail!("The tick rate must be between 1 and {}", MAX_TICKS_PER_SECOND);
+				}
+				ticks_rate.set_ticks_per_second(tps as u32);
+				ctx.sender.send_message(&format!("Ticking at {} ticks per second", tps));
+				Ok(())
+			}
+		}
+
+		literal "dilation" {
+			argument "factor" (f32) executes move |ctx| {
+				let factor = *ctx.arg::<f32>("factor")?;
+				let (min, max) = TIME_DILATION_RANGE;
+				if !(min..=max).contains(&factor) {
+					bail!("The time dilation must be between {} and {}", min, max);
+				}
+				ticks_dilation.set_time_dilation(factor);
+				ctx.sender.send_message(&format!("The game runs at {}x speed", factor));
+				Ok(())
+			}
+		}
+
+		executes move |ctx| {
+			let stats = ticks_query.stats();
+			let millis = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+			ctx.sender.send_message(&format!(
+				"{} ticks per second at {}x speed, {:.2}ms per tick allowed",
+				ticks_query.ticks_per_second(),
+				ticks_query.time_dilation(),
+				millis(ticks_query.tick_interval())
+			));
+			ctx.sender.send_message(&format!(
+				"Tick time: {:.2}ms last, {:.2}ms average, {:.2}ms max",
+				millis(stats.last),
+				millis(stats.average),
+				millis(stats.max)
+			));
+			ctx.sender.send_message(&format!("{} ticks, {} ran late, {} skipped", stats.ticks, stats.overruns, stats.skipped));
+			Ok(())
+		}
+	});
+}
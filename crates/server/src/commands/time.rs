@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use command::{
+	command,
+	CommandRegistry,
+};
+use world::{
+	format_time_of_day,
+	parse_time_of_day,
+	World,
+};
+
+pub fn register(registry: &mut CommandRegistry, world: Arc<World>) {
+	let world_query = world.clone();
+	let world_set = world.clone();
+	let world_add = world.clone();
+	let world_pause = world.clone();
+	let world_resume = world;
+	command!(registry, "time", {
+		literal "set" {
+			argument "time" (String) executes move |ctx| {
+				let input = ctx.arg::<String>("time")?;
+				let time_of_day = parse_time_of_day(input).ok_or_else(|| anyhow!("Invalid time '{}', expected HH:MM, hours or day/noon/night/midnight", input))?;
+				world_set.update_clock(|clock| clock.set_time_of_day(time_of_day));
+				ctx.sender.send_message(&format!("Set the time to {}", format_time_of_day(time_of_day)));
+				Ok(())
+			}
+		}
+
+		literal "add" {
+			argument "hours" (f64) executes move |ctx| {
+				let hours = *ctx.arg::<f64>("hours")?;
+				let seconds = hours * 3600.0;
+				if !seconds.is_finite() {
+					return Err(anyhow!("Invalid number of hours '{}'", hours));
+				}
+				let time_of_day = world_add.update_clock(|clock| {
+					clock.set_game_time(clock.game_time() + seconds);
+					clock.time_of_day()
+				});
+				ctx.sender.send_message(&format!("Added {} hours, it's now {}", hours, format_time_of_day(time_of_day)));
+				Ok(())
+			}
+		}
+
+		literal "pause" executes move |ctx| {
+			world_pause.update_clock(|clock| clock.set_paused(true));
+			ctx.sender.send_message("Time is paused");
+			Ok(())
+		},
+
+		literal "resume" executes move |ctx| {
+			world_resume.update_clock(|clock| clock.set_paused(false));
+			ctx.sender.send_message("Time is running again");
+			Ok(())
+		},
+
+		executes move |ctx| {
+			let clock = world_query.clock().lock();
+			let settings = clock.settings();
+			ctx.sender.send_message(&format!(
+				"Day {}, {}{} ({}s per day, {}s of it night)",
+				clock.day() + 1,
+				format_time_of_day(clock.time_of_day()),
+				if clock.is_paused() || world_query.is_paused() { ", paused" } else { "" },
+				settings.seconds_per_day(),
+				settings.nighttime_seconds
+			));
+			Ok(())
+		}
+	});
+}
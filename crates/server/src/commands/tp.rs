@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use anyhow::{
+	anyhow,
+	Result,
+};
+use command::{
+	command,
+	CommandContext,
+	CommandRegistry,
+};
+use net::{
+	players::{
+		PlayerHandle,
+		PlayerRegistry,
+	},
+	sender::PlayerSender,
+};
+use protocol::v2::PositionF;
+
+pub fn register(registry: &mut CommandRegistry, players: Arc<PlayerRegistry>) {
+	let (players_to_player, players_position) = (players.clone(), players);
+	command!(registry, "tp", {
+		argument "x" (f64) {
+			argument "y" (f64) {
+				argument "z" (f64) executes move |ctx| {
+					let sender = ctx.sender.as_any().downcast_ref::<PlayerSender>().ok_or_else(|| anyhow!("Name the player to teleport"))?;
+					teleport(ctx, sender.player(), position(ctx)?)
+				}
+			}
+		}
+
+		argument "player" (String) {
+			argument "x" (f64) {
+				argument "y" (f64) {
+					argument "z" (f64) executes move |ctx| {
+						let player = online(&players_position, ctx.arg::<String>("player")?)?;
+						teleport(ctx, &player, position(ctx)?)
+					}
+				}
+			}
+
+			argument "target" (String) executes move |ctx| {
+				let player = online(&players_to_player, ctx.arg::<String>("player")?)?;
+				let target = online(&players_to_player, ctx.arg::<String>("target")?)?;
+				if target.world() != player.world() {
+					return Err(anyhow!("{} is in another world", target.username()));
+				}
+				teleport(ctx, &player, target.transform().position)
+			}
+		}
+	});
+}
+
+fn online(players: &PlayerRegistry, name: &str) -> Result<PlayerHandle> {
+	players.get_by_name(name).ok_or_else(|| anyhow!("{} isn't online", name))
+}
+
+fn position(ctx: &CommandContext) -> Result<PositionF> {
+	Ok(PositionF {
+		x: *ctx.arg::<f64>("x")?,
+		y: *ctx.arg::<f64>("y")?,
+		z: *ctx.arg::<f64>("z")?,
+	})
+}
+
+fn teleport(ctx: &CommandContext, player: &PlayerHandle, position: PositionF) -> Result<()> {
+	let message = format!("Teleported {} to {:.1}, {:.1}, {:.1}", player.username(), position.x, position.y, position.z);
+	if !player.teleport(position) {
+		return Err(anyhow!("{} just left", player.username()));
+	}
+	ctx.sender.send_message(&message);
+	Ok(())
+}
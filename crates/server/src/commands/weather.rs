@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use anyhow::{
+	anyhow,
+	Result,
+};
+use command::{
+	command,
+	CommandContext,
+	CommandRegistry,
+};
+use world::{
+	WeatherRegistry,
+	World,
+};
+
+pub fn register(registry: &mut CommandRegistry, world: Arc<World>, weathers: Arc<WeatherRegistry>) {
+	let (world_query, weathers_query) = (world.clone(), weathers.clone());
+	let (world_force_all, weathers_force_all) = (world.clone(), weathers.clone());
+	let (world_force, weathers_force) = (world.clone(), weathers.clone());
+	let (world_clear_all, world_clear) = (world.clone(), world);
+	command!(registry, "weather", {
+		literal "force" {
+			argument "weather" (String) {
+				argument "environment" (String) executes move |ctx| {
+					let weather = weather_id(ctx, &weathers_force)?;
+					let environment = environment_id(ctx, &world_force)?;
+					world_force.force_weather(Some(environment), Some(weather));
+					ctx.sender.send_message(&format!("Forced {} in {}", ctx.arg::<String>("weather")?, ctx.arg::<String>("environment")?));
+					Ok(())
+				},
+
+				executes move |ctx| {
+					let weather = weather_id(ctx, &weathers_force_all)?;
+					world_force_all.force_weather(None, Some(weather));
+					ctx.sender.send_message(&format!("Forced {} everywhere", ctx.arg::<String>("weather")?));
+					Ok(())
+				}
+			}
+		}
+
+		literal "clear" {
+			argument "environment" (String) executes move |ctx| {
+				let environment = environment_id(ctx, &world_clear)?;
+				world_clear.force_weather(Some(environment), None);
+				ctx.sender.send_message(&format!("{} follows its forecast again", ctx.arg::<String>("environment")?));
+				Ok(())
+			},
+
+			executes move |ctx| {
+				world_clear_all.force_weather(None, None);
+				ctx.sender.send_message("Every environment follows its forecast again");
+				Ok(())
+			}
+		}
+
+		executes move |ctx| {
+			let scheduler = world_query.weather().lock();
+			let environments = scheduler.environments();
+			let weather_name = |id: i32| weathers_query.name(id).unwrap_or("?").to_string();
+			if let Some(weather) = scheduler.forced(None) {
+				ctx.sender.send_message(&format!("Forced everywhere: {}", weather_name(weather)));
+			}
+			let mut any = false;
+			for environment in 0..environments.max_id() {
+				let Some(weather) = scheduler.weather(environment) else {
+					continue;
+				};
+				let forced = if scheduler.forced(Some(environment)).is_some() { " (forced)" } else { "" };
+				ctx.sender.send_message(&format!("{}: {}{}", environments.name(environment).unwrap_or("?"), weather_name(weather), forced));
+				any = true;
+			}
+			if !any {
+				ctx.sender.send_message("No environment has any weather");
+			}
+			Ok(())
+		}
+	});
+}
+
+fn weather_id(ctx: &CommandContext, weathers: &WeatherRegistry) -> Result<i32> {
+	let name = ctx.arg::<String>("weather")?;
+	weathers.id(name).ok_or_else(|| anyhow!("Unknown weather '{}'", name))
+}
+
+fn environment_id(ctx: &CommandContext, world: &World) -> Result<i32> {
+	let name = ctx.arg::<String>("environment")?;
+	world.weather().lock().environments().id(name).ok_or_else(|| anyhow!("Unknown environment '{}'", name))
+}
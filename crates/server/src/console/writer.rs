@@ -24,7 +24,7 @@ impl io::Write for RustylineLogWriter {
 		// Lossy to prevent crashing on weird log bytes
 		let msg = String::from_utf8_lossy(buf);
 
-		self.printer.lock().print(msg.to_string()).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+		self.printer.lock().print(msg.to_string()).map_err(io::Error::other)?;
 
 		Ok(buf.len())
 	}
@@ -1,4 +1,5 @@
 pub mod assets;
+pub mod autosave;
 pub mod commands;
 pub mod console;
 pub mod options;
@@ -15,14 +16,32 @@ use command::CommandRegistry;
 use is_terminal::IsTerminal;
 use net::{
 	auth::ServerAuthManager,
+	context::{
+		GameplayOptions,
+		ServerContext,
+	},
+	players::PlayerRegistry,
 	server::QuicServer,
 	tls,
 };
 use tokio::sync::mpsc;
+use uuid::Uuid;
+use world::{
+	ClockSettings,
+	GeneratorPool,
+	PlayerDataStore,
+	RegionStorage,
+	TickScheduler,
+	World,
+	WeatherScheduler,
+	WorldClock,
+	WorldMeta,
+	DEFAULT_MOON_PHASES,
+};
 use tracing::{
 	error,
 	info,
-	Level,
+	warn,
 };
 use tracing_subscriber::{
 	filter::Directive,
@@ -32,6 +51,13 @@ use tracing_subscriber::{
 	Layer,
 };
 
+/// How often the server checks whether the game loop keeps up.
+const TICK_REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// How often everyone's ping on the player list is refreshed.
+const PLAYER_LIST_PING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// How long shutting down waits for the data of players still online to be saved.
+const PLAYER_SAVE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 macro_rules! register_commands {
     ($registry_lock:expr, $( $register:path => ( $( $arg:expr ),* $(,)? ) ),+ $(,)? ) => {{
         let mut registry = $registry_lock.write().unwrap();
@@ -87,19 +113,157 @@ pub async fn main() -> anyhow::Result<()> {
 
 	let common_assets = Arc::new(assets::load_common_assets(&options.assets_dir)?);
 
+	let mut world_meta = match WorldMeta::load(&options.world_dir)? {
+		Some(meta) => {
+			if options.world_seed.is_some_and(|seed| seed != meta.seed) {
+				warn!("Ignoring the configured world seed, {} already exists with seed {}", options.world_dir.display(), meta.seed);
+			}
+			meta
+		}
+		None => WorldMeta::new(Uuid::new_v4(), options.world_seed.unwrap_or_else(rand::random)),
+	};
+	let blocks = Arc::new(assets::load_block_registry(&options.assets_dir, &world_meta.block_ids)?);
+	let fluids = Arc::new(assets::load_fluid_registry(&options.assets_dir, &world_meta.fluid_ids, &blocks)?);
+	world_meta.block_ids = blocks.ids();
+	let weathers = Arc::new(assets::load_weather_registry(&options.assets_dir)?);
+	let environments = Arc::new(assets::load_environment_registry(&options.assets_dir, &world_meta.environment_ids, &options.default_environment, &weathers)?);
+	let recipes = Arc::new(assets::load_recipe_registry(&options.assets_dir)?);
+	world_meta.fluid_ids = fluids.ids();
+	world_meta.environment_ids = environments.ids();
+	world_meta.save(&options.world_dir)?;
+
+	let generator = options.world_generator.create(blocks.terrain_blocks(&fluids));
+	let generator_pool = Arc::new(GeneratorPool::new(generator, world_meta.seed, options.worldgen_threads)?);
+	info!("Using the {} world generator with seed {}", generator_pool.generator().name(), world_meta.seed);
+	let storage = Arc::new(RegionStorage::new(options.world_dir.join("regions")));
+	let clock = WorldClock::new(
+		ClockSettings {
+			daytime_seconds: options.daytime_secs,
+			nighttime_seconds: options.nighttime_secs,
+			moon_phases: DEFAULT_MOON_PHASES,
+			sleep_percentage: options.sleep_percentage,
+		},
+		world_meta.game_time,
+	);
+	let weather = WeatherScheduler::new(environments.clone());
+	let world = Arc::new(World::new("default", world_meta.uuid, generator_pool, storage, blocks.clone(), fluids.clone(), clock, weather));
+	let ticks = Arc::new(TickScheduler::new(options.ticks_per_second));
+	let players = Arc::new(PlayerRegistry::new());
+	ticks.add_system(world.clone());
+	let autosave_interval = std::time::Duration::from_secs(options.autosave_interval_secs);
+	ticks.add_system(Arc::new(autosave::ChunkAutosave::new(world.clone(), autosave_interval)));
+	register_commands!(cmd_reg_wrap,
+		commands::list::register => (players.clone()),
+		commands::gamemode::register => (players.clone()),
+		commands::ping::register => (players.clone()),
+		commands::tick::register => (ticks.clone()),
+		commands::time::register => (world.clone()),
+		commands::tp::register => (players.clone()),
+		commands::weather::register => (world.clone(), weathers.clone()),
+	);
+
+	let unload_world = world.clone();
+	let unload_interval = std::time::Duration::from_secs(options.chunk_unload_interval_secs);
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(unload_interval);
+		loop {
+			interval.tick().await;
+			match unload_world.unload_idle().await {
+				Ok(0) => {}
+				Ok(count) => info!("Unloaded {} idle chunks", count),
+				Err(e) => warn!("Failed to unload idle chunks: {}", e),
+			}
+		}
+	});
+
+	info!("Ticking at {} ticks per second", ticks.ticks_per_second());
+
+	let stats_ticks = ticks.clone();
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(TICK_REPORT_INTERVAL);
+		let mut last = stats_ticks.stats();
+		loop {
+			interval.tick().await;
+			let stats = stats_ticks.stats();
+			let (overruns, skipped) = (stats.overruns - last.overruns, stats.skipped - last.skipped);
+			if overruns > 0 {
+				warn!(
+					"Can't keep up! {} of the last {} ticks ran late and {} were skipped, averaging {:.2}ms",
+					overruns,
+					stats.ticks - last.ticks,
+					skipped,
+					stats.average.as_secs_f64() * 1000.0
+				);
+			}
+			last = stats;
+		}
+	});
+
+	let save_players = players.clone();
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + autosave_interval, autosave_interval);
+		loop {
+			interval.tick().await;
+			let online = save_players.all();
+			for player in &online {
+				player.save();
+			}
+			if !online.is_empty() {
+				info!("Saving the data of {} players", online.len());
+			}
+		}
+	});
+
+	let ping_players = players.clone();
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(PLAYER_LIST_PING_INTERVAL);
+		loop {
+			interval.tick().await;
+			if !ping_players.is_empty() {
+				ping_players.broadcast(ping_players.ping_update());
+			}
+		}
+	});
+
 	let quic_options = net::server::QuicServerOptions {
 		max_idle_timeout: std::time::Duration::from_secs(options.quic_idle_timeout_secs),
 		keep_alive_interval: std::time::Duration::from_secs(options.quic_keep_alive_secs),
 	};
-	let server = QuicServer::bind(options.bind_addr, cert_data, auth_manager, common_assets, quic_options).await?;
+	let ctx = Arc::new(ServerContext {
+		auth: auth_manager,
+		common_assets,
+		blocks,
+		fluids,
+		weathers,
+		environments,
+		recipes,
+		world: world.clone(),
+		ticks: ticks.clone(),
+		players: players.clone(),
+		player_data: Arc::new(PlayerDataStore::new(options.world_dir.join("players"))),
+		commands: cmd_reg_wrap.clone(),
+		options: GameplayOptions {
+			max_view_radius: options.max_view_radius,
+			default_game_mode: options.default_game_mode,
+			operators: options.operators.clone(),
+		},
+	});
+	let server = QuicServer::bind(options.bind_addr, cert_data, ctx, quic_options).await?;
 
 	info!("Server is Ready.");
 
+	let game_loop_ticks = ticks.clone();
+	let mut game_loop = tokio::spawn(async move { game_loop_ticks.run().await });
+
 	tokio::select! {
 		// The QUIC accept loop
 		_ = server.run_accept_loop() => {
 			error!("Server network loop exited unexpectedly");
 		}
+		// The game loop
+		_ = &mut game_loop => {
+			error!("Game loop exited unexpectedly");
+		}
 		// Graceful shutdown
 		_ = shutdown_rx.recv() => {
 			info!("Shutdown signal received.");
@@ -107,5 +271,30 @@ pub async fn main() -> anyhow::Result<()> {
 		}
 	}
 
+	let online = players.all();
+	if !online.is_empty() {
+		info!("Saving the data of {} players...", online.len());
+		let saves: Vec<_> = online.iter().map(|player| player.save()).collect();
+		let saved = tokio::time::timeout(PLAYER_SAVE_TIMEOUT, async {
+			for save in saves {
+				let _ = save.await;
+			}
+		});
+		if saved.await.is_err() {
+			error!("Timed out saving the data of players");
+		}
+	}
+
+	game_loop.abort();
+
+	info!("Saving world...");
+	if let Err(e) = world.save_all().await {
+		error!("Failed to save world: {}", e);
+	}
+	world_meta.game_time = world.clock().lock().game_time();
+	if let Err(e) = world_meta.save(&options.world_dir) {
+		error!("Failed to save world metadata: {}", e);
+	}
+
 	Ok(())
 }
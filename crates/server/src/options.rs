@@ -11,13 +11,29 @@ use anyhow::{
 	Result,
 };
 use clap::Parser;
+use protocol::v2::GameMode;
 use serde::Deserialize;
+use world::{
+	GeneratorKind,
+	DEFAULT_DAYTIME_SECONDS,
+	DEFAULT_ENVIRONMENT_NAME,
+	DEFAULT_NIGHTTIME_SECONDS,
+	DEFAULT_TICKS_PER_SECOND,
+	MAX_TICKS_PER_SECOND,
+};
 
 const DEFAULT_BIND_ADDR: &str = "0.0.0.0:5532";
 const DEFAULT_ASSETS_DIR: &str = "Assets.zip";
 const DEFAULT_AUTH_STORE: &str = "auth.enc";
 const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 30;
 const DEFAULT_KEEP_ALIVE_SECS: u64 = 5;
+const DEFAULT_WORLD_GENERATOR: GeneratorKind = GeneratorKind::Noise;
+const DEFAULT_WORLDGEN_THREADS: usize = 0;
+const DEFAULT_WORLD_DIR: &str = "world";
+const DEFAULT_MAX_VIEW_RADIUS: i32 = 12;
+const DEFAULT_CHUNK_UNLOAD_INTERVAL_SECS: u64 = 30;
+const DEFAULT_AUTOSAVE_INTERVAL_SECS: u64 = 300;
+const DEFAULT_SLEEP_PERCENTAGE: u8 = 100;
 
 #[derive(Debug, Parser)]
 #[command(name = "hightale-server", about = "Hightale server")]
@@ -45,6 +61,48 @@ struct CliOptions {
 
 	#[arg(long)]
 	auth_store_path: Option<PathBuf>,
+
+	#[arg(long)]
+	world_generator: Option<String>,
+
+	#[arg(long)]
+	world_seed: Option<u64>,
+
+	#[arg(long)]
+	worldgen_threads: Option<usize>,
+
+	#[arg(long)]
+	world_dir: Option<PathBuf>,
+
+	#[arg(long)]
+	max_view_radius: Option<i32>,
+
+	#[arg(long)]
+	chunk_unload_interval_secs: Option<u64>,
+
+	#[arg(long)]
+	autosave_interval_secs: Option<u64>,
+
+	#[arg(long)]
+	ticks_per_second: Option<u32>,
+
+	#[arg(long)]
+	daytime_secs: Option<u32>,
+
+	#[arg(long)]
+	nighttime_secs: Option<u32>,
+
+	#[arg(long)]
+	sleep_percentage: Option<u8>,
+
+	#[arg(long)]
+	default_environment: Option<String>,
+
+	#[arg(long)]
+	default_game_mode: Option<String>,
+
+	#[arg(long, value_delimiter = ',')]
+	operators: Option<Vec<String>>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -56,6 +114,20 @@ struct FileOptions {
 	auth_session_token: Option<String>,
 	auth_identity_token: Option<String>,
 	auth_store_path: Option<PathBuf>,
+	world_generator: Option<String>,
+	world_seed: Option<u64>,
+	worldgen_threads: Option<usize>,
+	world_dir: Option<PathBuf>,
+	max_view_radius: Option<i32>,
+	chunk_unload_interval_secs: Option<u64>,
+	autosave_interval_secs: Option<u64>,
+	ticks_per_second: Option<u32>,
+	daytime_secs: Option<u32>,
+	nighttime_secs: Option<u32>,
+	sleep_percentage: Option<u8>,
+	default_environment: Option<String>,
+	default_game_mode: Option<String>,
+	operators: Option<Vec<String>>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -74,6 +146,34 @@ struct EnvOptions {
 	auth_identity_token: Option<String>,
 	#[serde(rename = "AUTH_STORE_PATH")]
 	auth_store_path: Option<PathBuf>,
+	#[serde(rename = "WORLD_GENERATOR")]
+	world_generator: Option<String>,
+	#[serde(rename = "WORLD_SEED")]
+	world_seed: Option<u64>,
+	#[serde(rename = "WORLDGEN_THREADS")]
+	worldgen_threads: Option<usize>,
+	#[serde(rename = "WORLD_DIR")]
+	world_dir: Option<PathBuf>,
+	#[serde(rename = "MAX_VIEW_RADIUS")]
+	max_view_radius: Option<i32>,
+	#[serde(rename = "CHUNK_UNLOAD_INTERVAL_SECS")]
+	chunk_unload_interval_secs: Option<u64>,
+	#[serde(rename = "AUTOSAVE_INTERVAL_SECS")]
+	autosave_interval_secs: Option<u64>,
+	#[serde(rename = "TICKS_PER_SECOND")]
+	ticks_per_second: Option<u32>,
+	#[serde(rename = "DAYTIME_SECS")]
+	daytime_secs: Option<u32>,
+	#[serde(rename = "NIGHTTIME_SECS")]
+	nighttime_secs: Option<u32>,
+	#[serde(rename = "SLEEP_PERCENTAGE")]
+	sleep_percentage: Option<u8>,
+	#[serde(rename = "DEFAULT_ENVIRONMENT")]
+	default_environment: Option<String>,
+	#[serde(rename = "DEFAULT_GAME_MODE")]
+	default_game_mode: Option<String>,
+	#[serde(rename = "OPERATORS")]
+	operators: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
@@ -85,6 +185,32 @@ pub struct ServerOptions {
 	pub auth_session_token: Option<String>,
 	pub auth_identity_token: Option<String>,
 	pub auth_store_path: PathBuf,
+	pub world_generator: GeneratorKind,
+	/// `None` picks a random seed on startup.
+	pub world_seed: Option<u64>,
+	/// Worker threads for world generation, 0 for one per core.
+	pub worldgen_threads: usize,
+	pub world_dir: PathBuf,
+	/// Upper bound for the view radius players ask for, in chunks.
+	pub max_view_radius: i32,
+	/// How often chunks no player has in view are saved and unloaded.
+	pub chunk_unload_interval_secs: u64,
+	/// How often the data of online players and the chunks that changed are saved.
+	pub autosave_interval_secs: u64,
+	/// How often the game loop runs.
+	pub ticks_per_second: u32,
+	/// Real seconds from sunrise to sunset, a whole day lasts this plus `nighttime_secs`.
+	pub daytime_secs: u32,
+	/// Real seconds from sunset to sunrise.
+	pub nighttime_secs: u32,
+	/// Share of the players in a world that have to sleep to skip the night, in percent.
+	pub sleep_percentage: u8,
+	/// Environment of every column the world generator doesn't assign one to.
+	pub default_environment: String,
+	/// Game mode of players joining the server.
+	pub default_game_mode: GameMode,
+	/// Names or UUIDs of the players allowed to use every command.
+	pub operators: Vec<String>,
 	pub config_path: Option<PathBuf>,
 }
 
@@ -119,6 +245,49 @@ impl ServerOptions {
 			.or(file.auth_store_path)
 			.or(env.auth_store_path)
 			.unwrap_or_else(|| PathBuf::from(DEFAULT_AUTH_STORE));
+		let world_generator = match normalize_string_opt(cli.world_generator.or(file.world_generator).or(env.world_generator)) {
+			Some(name) => name.parse::<GeneratorKind>().map_err(anyhow::Error::msg)?,
+			None => DEFAULT_WORLD_GENERATOR,
+		};
+		let world_seed = cli.world_seed.or(file.world_seed).or(env.world_seed);
+		let worldgen_threads = cli.worldgen_threads.or(file.worldgen_threads).or(env.worldgen_threads).unwrap_or(DEFAULT_WORLDGEN_THREADS);
+		let world_dir = cli.world_dir.or(file.world_dir).or(env.world_dir).unwrap_or_else(|| PathBuf::from(DEFAULT_WORLD_DIR));
+		let max_view_radius = cli.max_view_radius.or(file.max_view_radius).or(env.max_view_radius).unwrap_or(DEFAULT_MAX_VIEW_RADIUS).max(1);
+		let chunk_unload_interval_secs = cli
+			.chunk_unload_interval_secs
+			.or(file.chunk_unload_interval_secs)
+			.or(env.chunk_unload_interval_secs)
+			.unwrap_or(DEFAULT_CHUNK_UNLOAD_INTERVAL_SECS)
+			.max(1);
+		let autosave_interval_secs = cli
+			.autosave_interval_secs
+			.or(file.autosave_interval_secs)
+			.or(env.autosave_interval_secs)
+			.unwrap_or(DEFAULT_AUTOSAVE_INTERVAL_SECS)
+			.max(1);
+		let ticks_per_second = cli
+			.ticks_per_second
+			.or(file.ticks_per_second)
+			.or(env.ticks_per_second)
+			.unwrap_or(DEFAULT_TICKS_PER_SECOND)
+			.clamp(1, MAX_TICKS_PER_SECOND);
+		let daytime_secs = cli.daytime_secs.or(file.daytime_secs).or(env.daytime_secs).unwrap_or(DEFAULT_DAYTIME_SECONDS).max(1);
+		let nighttime_secs = cli.nighttime_secs.or(file.nighttime_secs).or(env.nighttime_secs).unwrap_or(DEFAULT_NIGHTTIME_SECONDS).max(1);
+		let sleep_percentage = cli.sleep_percentage.or(file.sleep_percentage).or(env.sleep_percentage).unwrap_or(DEFAULT_SLEEP_PERCENTAGE).min(100);
+		let default_environment = normalize_string_opt(cli.default_environment.or(file.default_environment).or(env.default_environment))
+			.unwrap_or_else(|| DEFAULT_ENVIRONMENT_NAME.to_string());
+		let default_game_mode = match normalize_string_opt(cli.default_game_mode.or(file.default_game_mode).or(env.default_game_mode)) {
+			Some(name) => parse_game_mode(&name)?,
+			None => GameMode::Adventure,
+		};
+		let operators = cli
+			.operators
+			.or(file.operators)
+			.or(env.operators)
+			.unwrap_or_default()
+			.into_iter()
+			.filter_map(|operator| normalize_string_opt(Some(operator)))
+			.collect();
 
 		Ok(Self {
 			bind_addr,
@@ -128,6 +297,20 @@ impl ServerOptions {
 			auth_session_token,
 			auth_identity_token,
 			auth_store_path,
+			world_generator,
+			world_seed,
+			worldgen_threads,
+			world_dir,
+			max_view_radius,
+			chunk_unload_interval_secs,
+			autosave_interval_secs,
+			ticks_per_second,
+			daytime_secs,
+			nighttime_secs,
+			sleep_percentage,
+			default_environment,
+			default_game_mode,
+			operators,
 			config_path,
 		})
 	}
@@ -149,6 +332,14 @@ fn read_env() -> Result<EnvOptions> {
 	Ok(env)
 }
 
+pub(crate) fn parse_game_mode(name: &str) -> Result<GameMode> {
+	match name.to_ascii_lowercase().as_str() {
+		"adventure" => Ok(GameMode::Adventure),
+		"creative" => Ok(GameMode::Creative),
+		_ => Err(anyhow::anyhow!("Unknown game mode '{}', expected adventure or creative", name)),
+	}
+}
+
 fn normalize_string_opt(value: Option<String>) -> Option<String> {
 	value.and_then(|v| {
 		let trimmed = v.trim();
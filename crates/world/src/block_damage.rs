@@ -0,0 +1,79 @@
+use std::{
+	collections::HashMap,
+	time::Duration,
+};
+
+use protocol::v2::{
+	world::UpdateBlockDamage,
+	Vector3i,
+};
+
+/// How long a damaged block has to be left alone before it heals.
+pub const BLOCK_DAMAGE_RESET: Duration = Duration::from_secs(5);
+
+struct DamagedBlock {
+	damage: f32,
+	/// Time since the block was last hit.
+	idle: Duration,
+}
+
+/// Damage dealt to blocks that aren't broken yet, as a share of their health from 0 to 1.
+#[derive(Default)]
+pub struct BlockDamageTracker {
+	blocks: HashMap<(i32, i32, i32), DamagedBlock>,
+}
+
+impl BlockDamageTracker {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn damage(&self, x: i32, y: i32, z: i32) -> f32 {
+		self.blocks.get(&(x, y, z)).map_or(0.0, |block| block.damage)
+	}
+
+	/// Deals damage to a block. Returns the `UpdateBlockDamage` to send and whether the block broke, which forgets it.
+	pub fn hit(&mut self, x: i32, y: i32, z: i32, amount: f32) -> (UpdateBlockDamage, bool) {
+		let block = self.blocks.entry((x, y, z)).or_insert(DamagedBlock {
+			damage: 0.0,
+			idle: Duration::ZERO,
+		});
+		let before = block.damage;
+		block.damage = (block.damage + amount).min(1.0);
+		block.idle = Duration::ZERO;
+		let packet = update(x, y, z, block.damage, block.damage - before);
+		let broken = block.damage >= 1.0;
+		if broken {
+			self.blocks.remove(&(x, y, z));
+		}
+		(packet, broken)
+	}
+
+	/// Forgets the damage of a block that changed. Returns the update clearing it, if it was damaged.
+	pub fn clear(&mut self, x: i32, y: i32, z: i32) -> Option<UpdateBlockDamage> {
+		let block = self.blocks.remove(&(x, y, z))?;
+		Some(update(x, y, z, 0.0, -block.damage))
+	}
+
+	/// Heals the blocks nobody hit for [`BLOCK_DAMAGE_RESET`] and returns the updates clearing their damage.
+	pub fn tick(&mut self, elapsed: Duration) -> Vec<UpdateBlockDamage> {
+		let mut healed = Vec::new();
+		self.blocks.retain(|(x, y, z), block| {
+			block.idle += elapsed;
+			if block.idle < BLOCK_DAMAGE_RESET {
+				return true;
+			}
+			healed.push(update(*x, *y, *z, 0.0, -block.damage));
+			false
+		});
+		healed
+	}
+}
+
+fn update(x: i32, y: i32, z: i32, damage: f32, delta: f32) -> UpdateBlockDamage {
+	UpdateBlockDamage {
+		block_position: Some(Vector3i { x, y, z }),
+		damage,
+		delta,
+	}
+}
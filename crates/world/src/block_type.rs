@@ -0,0 +1,380 @@
+use assets::{
+	Asset,
+	StoreError,
+	StoreResult,
+};
+use protocol::v2::{
+	BenchType,
+	BlockFlags,
+	BlockMaterial,
+	BlockSupportsRequiredForType,
+	BlockTextures,
+	BlockType,
+	Color,
+	ColorLight,
+	DrawType,
+	ModelTexture,
+	Opacity,
+	RandomRotation,
+	Rotation,
+	ShadingMode,
+	VariantRotation,
+};
+use serde::Deserialize;
+
+use crate::{
+	light::MAX_LIGHT,
+	recipe::parse_bench_type,
+};
+
+/// A block type as defined by the `BlockType` section of an item asset, keyed by the item's file name.
+#[derive(Debug, Clone)]
+pub struct BlockTypeAsset {
+	name: String,
+	pub definition: BlockTypeDefinition,
+}
+
+impl Asset for BlockTypeAsset {
+	type Key = String;
+
+	fn key(&self) -> &Self::Key {
+		&self.name
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ItemDefinition {
+	block_type: Option<BlockTypeDefinition>,
+}
+
+impl BlockTypeAsset {
+	pub fn new(name: impl Into<String>, definition: BlockTypeDefinition) -> Self {
+		Self { name: name.into(), definition }
+	}
+
+	/// Parses an item asset, returning `None` for items that can't be placed as a block.
+	pub fn from_item_json(name: impl Into<String>, bytes: &[u8]) -> StoreResult<Option<Self>> {
+		let item: ItemDefinition = serde_json::from_slice(bytes).map_err(|e| StoreError::Decode(e.to_string()))?;
+		Ok(item.block_type.map(|definition| Self::new(name, definition)))
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Red, green and blue light levels the block emits, each in `0..=15`.
+	pub fn emission(&self) -> [u8; 3] {
+		self.definition.light.as_ref().and_then(|light| light.color.as_deref()).and_then(parse_light_color).unwrap_or([0; 3])
+	}
+
+	/// Light falloff through the block, see [`BlockLighting::opacity`](crate::BlockLighting::opacity).
+	pub fn light_opacity(&self) -> u8 {
+		if self.definition.material() == BlockMaterial::Empty {
+			return 0;
+		}
+		match self.definition.opacity() {
+			Opacity::Solid => MAX_LIGHT,
+			Opacity::Semitransparent => 2,
+			Opacity::Cutout => 1,
+			Opacity::Transparent => 0,
+		}
+	}
+
+	pub fn to_protocol(&self) -> BlockType {
+		let definition = &self.definition;
+		let mut block = BlockType {
+			unknown: false,
+			draw_type: definition.draw_type(),
+			material: definition.material(),
+			opacity: definition.opacity(),
+			model_scale: definition.custom_model_scale.unwrap_or(1.0),
+			requires_alpha_blending: definition.requires_alpha_blending,
+			cube_shading_mode: definition.cube_shading_mode(),
+			random_rotation: definition.random_rotation(),
+			variant_rotation: definition.variant_rotation(),
+			name: Some(self.name.clone()),
+			item: Some(self.name.clone()),
+			model: definition.custom_model.clone(),
+			..unknown_block_type()
+		};
+
+		if let Some(light) = &definition.light {
+			let [red, green, blue] = self.emission();
+			let radius = light.radius.unwrap_or_else(|| red.max(green).max(blue));
+			block.light = Some(ColorLight { radius, red, green, blue });
+		}
+		block.particle_color = definition.particle_color.as_deref().and_then(parse_rgb).map(|[red, green, blue]| Color { red, green, blue });
+		if !definition.textures.is_empty() {
+			block.cube_textures = Some(definition.textures.iter().map(TextureDefinition::to_protocol).collect());
+		}
+		if !definition.custom_model_texture.is_empty() {
+			block.model_texture = Some(
+				definition
+					.custom_model_texture
+					.iter()
+					.map(|texture| ModelTexture {
+						weight: texture.weight.unwrap_or(1.0),
+						texture: texture.texture.clone(),
+					})
+					.collect(),
+			);
+		}
+		if let Some(flags) = &definition.flags {
+			block.flags = Some(BlockFlags {
+				is_usable: flags.is_usable,
+				is_stackable: flags.is_stackable,
+			});
+		}
+		block
+	}
+}
+
+/// The block type sent for ids that have no asset, and the base every other block type is built on.
+pub fn unknown_block_type() -> BlockType {
+	BlockType {
+		unknown: true,
+		draw_type: DrawType::Cube,
+		material: BlockMaterial::Solid,
+		opacity: Opacity::Solid,
+		hitbox: 0,
+		interaction_hitbox: 0,
+		model_scale: 1.0,
+		looping: false,
+		max_support_distance: 0,
+		block_supports_required_for: BlockSupportsRequiredForType::Any,
+		requires_alpha_blending: false,
+		cube_shading_mode: ShadingMode::Standard,
+		random_rotation: RandomRotation::None,
+		variant_rotation: VariantRotation::None,
+		rotation_yaw_placement_offset: Rotation::None,
+		block_sound_set_index: 0,
+		ambient_sound_event_index: 0,
+		particle_color: None,
+		light: None,
+		tint: None,
+		biome_tint: None,
+		group: 0,
+		movement_settings: None,
+		flags: None,
+		placement_settings: None,
+		ignore_support_when_placed: false,
+		transition_to_tag: 0,
+		item: None,
+		name: None,
+		shader_effect: None,
+		model: None,
+		model_texture: None,
+		model_animation: None,
+		support: None,
+		supporting: None,
+		cube_textures: None,
+		cube_side_mask_texture: None,
+		particles: None,
+		block_particle_set_id: None,
+		block_breaking_decal_id: None,
+		transition_texture: None,
+		transition_to_groups: None,
+		interaction_hint: None,
+		gathering: None,
+		display: None,
+		rail: None,
+		interactions: None,
+		states: None,
+		tag_indexes: None,
+		bench: None,
+		connected_block_rule_set: None,
+	}
+}
+
+/// The block type of id 0, which the client treats as air.
+pub fn empty_block_type() -> BlockType {
+	BlockType {
+		unknown: false,
+		draw_type: DrawType::Empty,
+		material: BlockMaterial::Empty,
+		opacity: Opacity::Transparent,
+		name: Some(crate::registry::EMPTY_BLOCK.to_string()),
+		..unknown_block_type()
+	}
+}
+
+/// The subset of an item's `BlockType` section the server understands. Enum values are kept as strings, unknown ones
+/// fall back to the defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BlockTypeDefinition {
+	pub draw_type: Option<String>,
+	pub material: Option<String>,
+	pub opacity: Option<String>,
+	pub textures: Vec<TextureDefinition>,
+	pub custom_model: Option<String>,
+	pub custom_model_texture: Vec<ModelTextureDefinition>,
+	pub custom_model_scale: Option<f32>,
+	pub cube_shading_mode: Option<String>,
+	pub random_rotation: Option<String>,
+	pub variant_rotation: Option<String>,
+	pub requires_alpha_blending: bool,
+	pub light: Option<LightDefinition>,
+	pub particle_color: Option<String>,
+	pub flags: Option<FlagsDefinition>,
+	/// Set for blocks players craft at.
+	pub bench: Option<BenchDefinition>,
+}
+
+impl BlockTypeDefinition {
+	pub fn draw_type(&self) -> DrawType {
+		match self.draw_type.as_deref() {
+			Some("Empty") => DrawType::Empty,
+			Some("GizmoCube") => DrawType::GizmoCube,
+			Some("Model") => DrawType::Model,
+			Some("CubeWithModel") => DrawType::CubeWithModel,
+			_ => DrawType::Cube,
+		}
+	}
+
+	pub fn material(&self) -> BlockMaterial {
+		match self.material.as_deref() {
+			Some("Empty") => BlockMaterial::Empty,
+			_ => BlockMaterial::Solid,
+		}
+	}
+
+	pub fn opacity(&self) -> Opacity {
+		parse_opacity(self.opacity.as_deref())
+	}
+
+	pub fn cube_shading_mode(&self) -> ShadingMode {
+		match self.cube_shading_mode.as_deref() {
+			Some("Flat") => ShadingMode::Flat,
+			Some("Fullbright") => ShadingMode::Fullbright,
+			Some("Reflective") => ShadingMode::Reflective,
+			_ => ShadingMode::Standard,
+		}
+	}
+
+	pub fn random_rotation(&self) -> RandomRotation {
+		match self.random_rotation.as_deref() {
+			Some("YawPitchRollStep1") => RandomRotation::YawPitchRollStep1,
+			Some("YawStep1") => RandomRotation::YawStep1,
+			Some("YawStep1XZ") => RandomRotation::YawStep1XZ,
+			Some("YawStep90") => RandomRotation::YawStep90,
+			_ => RandomRotation::None,
+		}
+	}
+
+	pub fn variant_rotation(&self) -> VariantRotation {
+		match self.variant_rotation.as_deref() {
+			Some("Wall") => VariantRotation::Wall,
+			Some("UpDown") => VariantRotation::UpDown,
+			Some("Pipe") => VariantRotation::Pipe,
+			Some("DoublePipe") => VariantRotation::DoublePipe,
+			Some("NESW") => VariantRotation::NESW,
+			Some("UpDownNESW") => VariantRotation::UpDownNESW,
+			Some("All") => VariantRotation::All,
+			_ => VariantRotation::None,
+		}
+	}
+}
+
+/// One weighted set of cube face textures. More specific faces override `UpDown`/`Sides`, which override `All`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct TextureDefinition {
+	pub weight: Option<f32>,
+	pub all: Option<String>,
+	pub up_down: Option<String>,
+	pub sides: Option<String>,
+	pub top: Option<String>,
+	pub bottom: Option<String>,
+	pub north: Option<String>,
+	pub south: Option<String>,
+	pub east: Option<String>,
+	pub west: Option<String>,
+}
+
+impl TextureDefinition {
+	pub(crate) fn to_protocol(&self) -> BlockTextures {
+		let vertical = self.up_down.as_ref().or(self.all.as_ref());
+		let side = |face: &Option<String>| face.as_ref().or(self.sides.as_ref()).or(self.all.as_ref()).cloned();
+		BlockTextures {
+			weight: self.weight.unwrap_or(1.0),
+			top: self.top.as_ref().or(vertical).cloned(),
+			bottom: self.bottom.as_ref().or(vertical).cloned(),
+			front: side(&self.north),
+			back: side(&self.south),
+			left: side(&self.west),
+			right: side(&self.east),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ModelTextureDefinition {
+	pub weight: Option<f32>,
+	pub texture: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct LightDefinition {
+	/// `#RGB` with one hex digit per light level, or `#RRGGBB`.
+	pub color: Option<String>,
+	pub radius: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BenchDefinition {
+	#[serde(rename = "Type")]
+	pub bench_type: Option<String>,
+	/// What recipes name in their bench requirements.
+	pub id: Option<String>,
+}
+
+impl BenchDefinition {
+	pub fn bench_type(&self) -> BenchType {
+		parse_bench_type(self.bench_type.as_deref())
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct FlagsDefinition {
+	pub is_usable: bool,
+	pub is_stackable: bool,
+}
+
+pub(crate) fn parse_opacity(opacity: Option<&str>) -> Opacity {
+	match opacity {
+		Some("Semitransparent") => Opacity::Semitransparent,
+		Some("Cutout") => Opacity::Cutout,
+		Some("Transparent") => Opacity::Transparent,
+		_ => Opacity::Solid,
+	}
+}
+
+/// Parses a light color into levels in `0..=15`.
+pub(crate) fn parse_light_color(color: &str) -> Option<[u8; 3]> {
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	if hex.len() == 3 {
+		let mut levels = [0; 3];
+		for (level, digit) in levels.iter_mut().zip(hex.chars()) {
+			*level = digit.to_digit(16)? as u8;
+		}
+		return Some(levels);
+	}
+	parse_rgb(color).map(|rgb| rgb.map(|channel| channel >> 4))
+}
+
+/// Parses `#RRGGBB` or `#RGB` into 8-bit channels.
+pub(crate) fn parse_rgb(color: &str) -> Option<[u8; 3]> {
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	let value = u32::from_str_radix(hex, 16).ok()?;
+	match hex.len() {
+		6 => Some([(value >> 16) as u8, (value >> 8) as u8, value as u8]),
+		3 => Some([(value >> 8 & 0xF) as u8 * 17, (value >> 4 & 0xF) as u8 * 17, (value & 0xF) as u8 * 17]),
+		_ => None,
+	}
+}
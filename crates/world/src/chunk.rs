@@ -0,0 +1,306 @@
+use bytes::Bytes;
+use protocol::v2::{
+	world::{
+		ServerSetBlock,
+		ServerSetBlocks,
+		ServerSetFluid,
+		ServerSetFluids,
+		SetChunk,
+		SetChunkEnvironments,
+		SetChunkHeightmap,
+		SetChunkTintmap,
+		SetFluids,
+	},
+	Vector3i,
+};
+
+use crate::{
+	column::ColumnMaps,
+	error::{
+		ChunkError,
+		ChunkResult,
+	},
+	fluid::{
+		FluidSection,
+		FluidState,
+	},
+	light::ChunkLight,
+	section::{
+		block_coords,
+		block_index,
+		BlockState,
+		ChunkSection,
+		SECTION_SIZE,
+		SECTION_VOLUME,
+	},
+};
+
+/// Number of sections stacked in a chunk column.
+pub const CHUNK_SECTIONS: usize = 10;
+/// Height of the world in blocks.
+pub const CHUNK_HEIGHT: usize = CHUNK_SECTIONS * SECTION_SIZE;
+
+/// Chunk coordinate containing the given world coordinate.
+#[inline]
+pub fn chunk_coord(world: i32) -> i32 {
+	world >> 5
+}
+
+/// Chunk-local coordinate of the given world coordinate.
+#[inline]
+pub fn local_coord(world: i32) -> usize {
+	(world & 31) as usize
+}
+
+/// A full-height column of sections at chunk coordinates `(x, z)`.
+///
+/// Editing blocks through the chunk directly leaves its light untouched, use a [`LightEngine`](crate::LightEngine) to keep it up
+/// to date.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+	x: i32,
+	z: i32,
+	pub(crate) sections: Vec<ChunkSection>,
+	pub(crate) fluids: Vec<FluidSection>,
+	pub(crate) light: ChunkLight,
+	pub(crate) maps: ColumnMaps,
+}
+
+impl Chunk {
+	pub fn new(x: i32, z: i32) -> Self {
+		Self {
+			x,
+			z,
+			sections: vec![ChunkSection::new(); CHUNK_SECTIONS],
+			fluids: vec![FluidSection::new(); CHUNK_SECTIONS],
+			light: ChunkLight::new(),
+			maps: ColumnMaps::new(),
+		}
+	}
+
+	pub fn x(&self) -> i32 {
+		self.x
+	}
+
+	pub fn z(&self) -> i32 {
+		self.z
+	}
+
+	pub fn sections(&self) -> &[ChunkSection] {
+		&self.sections
+	}
+
+	pub fn section(&self, y: usize) -> Option<&ChunkSection> {
+		self.sections.get(y)
+	}
+
+	pub fn fluid_sections(&self) -> &[FluidSection] {
+		&self.fluids
+	}
+
+	pub fn light(&self) -> &ChunkLight {
+		&self.light
+	}
+
+	pub fn light_mut(&mut self) -> &mut ChunkLight {
+		&mut self.light
+	}
+
+	pub fn maps(&self) -> &ColumnMaps {
+		&self.maps
+	}
+
+	pub fn maps_mut(&mut self) -> &mut ColumnMaps {
+		&mut self.maps
+	}
+
+	/// Returns the block at the given chunk-local coordinates. Anything outside the world height is air.
+	pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockState {
+		match self.sections.get(y / SECTION_SIZE) {
+			Some(section) => section.get(x, y % SECTION_SIZE, z),
+			None => BlockState::AIR,
+		}
+	}
+
+	/// Sets the block at the given chunk-local coordinates and returns the previous state.
+	/// Writes outside the world height are ignored.
+	pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+		let Some(section) = self.sections.get_mut(y / SECTION_SIZE) else {
+			return BlockState::AIR;
+		};
+		let old = section.set(x, y % SECTION_SIZE, z, state);
+		if old.is_air() != state.is_air() {
+			self.maps.update_height(&self.sections, x, y, z, state.is_air());
+		}
+		old
+	}
+
+	/// Returns the fluid at the given chunk-local coordinates. Anything outside the world height is empty.
+	pub fn get_fluid(&self, x: usize, y: usize, z: usize) -> FluidState {
+		match self.fluids.get(y / SECTION_SIZE) {
+			Some(section) => section.get_index(block_index(x, y, z)),
+			None => FluidState::EMPTY,
+		}
+	}
+
+	/// Sets the fluid at the given chunk-local coordinates and returns the previous state.
+	/// Writes outside the world height are ignored.
+	pub fn set_fluid(&mut self, x: usize, y: usize, z: usize, state: FluidState) -> FluidState {
+		match self.fluids.get_mut(y / SECTION_SIZE) {
+			Some(section) => section.set_index(block_index(x, y, z), state),
+			None => FluidState::EMPTY,
+		}
+	}
+
+	/// Builds the `SetChunk` packet for the section at index `y`.
+	pub fn to_set_chunk(&self, y: usize) -> Option<SetChunk> {
+		let section = self.sections.get(y)?;
+		Some(SetChunk {
+			pos: Vector3i {
+				x: self.x,
+				y: y as i32,
+				z: self.z,
+			},
+			local_light: self.light.local_light_bytes(y),
+			global_light: self.light.global_light_bytes(y),
+			data: Some(section.to_bytes()),
+		})
+	}
+
+	pub fn to_set_heightmap(&self) -> SetChunkHeightmap {
+		SetChunkHeightmap {
+			x: self.x,
+			z: self.z,
+			heightmap: Some(self.maps.heightmap_bytes()),
+		}
+	}
+
+	pub fn to_set_tintmap(&self) -> SetChunkTintmap {
+		SetChunkTintmap {
+			x: self.x,
+			z: self.z,
+			tintmap: Some(self.maps.tintmap_bytes()),
+		}
+	}
+
+	pub fn to_set_environments(&self) -> SetChunkEnvironments {
+		SetChunkEnvironments {
+			x: self.x,
+			z: self.z,
+			environments: Some(self.maps.environments_bytes()),
+		}
+	}
+
+	/// Builds the `SetFluids` packet for the section at index `y`, or `None` if it has no fluids.
+	pub fn to_set_fluids(&self, y: usize) -> Option<SetFluids> {
+		let section = self.fluids.get(y).filter(|section| !section.is_empty())?;
+		Some(SetFluids {
+			pos: Vector3i {
+				x: self.x,
+				y: y as i32,
+				z: self.z,
+			},
+			data: Some(section.to_bytes()),
+		})
+	}
+
+	/// Builds the `SetFluids` packets for every section of the column that has fluids, bottom to top.
+	pub fn to_set_fluids_all(&self) -> Vec<SetFluids> {
+		(0..self.fluids.len()).filter_map(|y| self.to_set_fluids(y)).collect()
+	}
+
+	/// Builds the `SetChunk` packets for every section of the column, bottom to top.
+	pub fn to_set_chunks(&self) -> Vec<SetChunk> {
+		(0..self.sections.len()).filter_map(|y| self.to_set_chunk(y)).collect()
+	}
+
+	/// Replaces a section with the contents of a `SetChunk` packet addressed to this column.
+	/// The heightmap follows along, the light of the chunk has to be recomputed afterwards.
+	pub fn apply_set_chunk(&mut self, packet: &SetChunk) -> ChunkResult<()> {
+		let index = usize::try_from(packet.pos.y).ok().filter(|y| *y < self.sections.len()).ok_or(ChunkError::SectionOutOfRange(packet.pos.y))?;
+		let section = match &packet.data {
+			Some(data) => ChunkSection::decode(&mut Bytes::clone(data))?,
+			None => ChunkSection::new(),
+		};
+		self.sections[index] = section;
+		self.maps.recompute_heights(&self.sections);
+		Ok(())
+	}
+
+	/// Applies a `ServerSetBlock` whose position lies in this column.
+	/// Returns the chunk-local position if the block changed.
+	pub fn apply_set_block(&mut self, packet: &ServerSetBlock) -> Option<(usize, usize, usize)> {
+		let Vector3i { x, y, z } = packet.pos;
+		if chunk_coord(x) != self.x || chunk_coord(z) != self.z {
+			return None;
+		}
+		let y = usize::try_from(y).ok().filter(|y| *y < CHUNK_HEIGHT)?;
+		let (x, z) = (local_coord(x), local_coord(z));
+		let state = BlockState {
+			id: packet.block_id,
+			filler: packet.filler,
+			rotation: packet.rotation,
+		};
+		(self.set_block(x, y, z, state) != state).then_some((x, y, z))
+	}
+
+	/// Applies a `ServerSetBlocks` addressed to a section of this column.
+	/// Returns the chunk-local positions of the blocks that changed.
+	pub fn apply_set_blocks(&mut self, packet: &ServerSetBlocks) -> Vec<(usize, usize, usize)> {
+		let Vector3i { x, y, z } = packet.pos;
+		let Some(section_y) = usize::try_from(y).ok().filter(|y| x == self.x && z == self.z && *y < self.sections.len()) else {
+			return Vec::new();
+		};
+		let mut changed = Vec::new();
+		for cmd in &packet.cmds {
+			let Some(index) = usize::try_from(cmd.index).ok().filter(|index| *index < SECTION_VOLUME) else {
+				continue;
+			};
+			let state = BlockState {
+				id: cmd.block_id,
+				filler: cmd.filler,
+				rotation: cmd.rotation,
+			};
+			let (x, y, z) = block_coords(index);
+			let y = section_y * SECTION_SIZE + y;
+			if self.set_block(x, y, z, state) != state {
+				changed.push((x, y, z));
+			}
+		}
+		changed
+	}
+
+	/// Applies a `ServerSetFluid` whose position lies in this column.
+	/// Returns the chunk-local position if the fluid changed.
+	pub fn apply_set_fluid(&mut self, packet: &ServerSetFluid) -> Option<(usize, usize, usize)> {
+		let Vector3i { x, y, z } = packet.pos;
+		if chunk_coord(x) != self.x || chunk_coord(z) != self.z {
+			return None;
+		}
+		let y = usize::try_from(y).ok().filter(|y| *y < CHUNK_HEIGHT)?;
+		let (x, z) = (local_coord(x), local_coord(z));
+		let state = FluidState::new(packet.fluid_id, packet.fluid_level);
+		(self.set_fluid(x, y, z, state) != state).then_some((x, y, z))
+	}
+
+	/// Applies a `ServerSetFluids` addressed to a section of this column.
+	/// Returns the chunk-local positions of the fluids that changed.
+	pub fn apply_set_fluids(&mut self, packet: &ServerSetFluids) -> Vec<(usize, usize, usize)> {
+		let Vector3i { x, y, z } = packet.pos;
+		let Some(section_y) = usize::try_from(y).ok().filter(|y| x == self.x && z == self.z && *y < self.fluids.len()) else {
+			return Vec::new();
+		};
+		let mut changed = Vec::new();
+		for cmd in &packet.cmds {
+			let Some(index) = usize::try_from(cmd.index).ok().filter(|index| *index < SECTION_VOLUME) else {
+				continue;
+			};
+			let state = FluidState::new(cmd.fluid_id, cmd.fluid_level);
+			if self.fluids[section_y].set_index(index, state) != state {
+				let (x, y, z) = block_coords(index);
+				changed.push((x, section_y * SECTION_SIZE + y, z));
+			}
+		}
+		changed
+	}
+}
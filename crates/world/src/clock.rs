@@ -0,0 +1,310 @@
+use std::{
+	collections::HashSet,
+	time::Duration,
+};
+
+use protocol::v2::{
+	world::{
+		SleepClock,
+		SleepMultiplayer,
+		UpdateSleepState,
+		UpdateTime,
+		UpdateTimeSettings,
+	},
+	InstantData,
+	Packet,
+};
+use uuid::Uuid;
+
+/// Game seconds in a full day.
+pub const SECONDS_PER_DAY: f64 = 86_400.0;
+/// Time of day the sun rises, in game seconds.
+pub const SUNRISE: f64 = 6.0 * 3600.0;
+/// Time of day the sun sets, in game seconds.
+pub const SUNSET: f64 = 18.0 * 3600.0;
+/// Real seconds the daytime lasts unless configured otherwise.
+pub const DEFAULT_DAYTIME_SECONDS: u32 = 1200;
+/// Real seconds the night lasts unless configured otherwise.
+pub const DEFAULT_NIGHTTIME_SECONDS: u32 = 600;
+pub const DEFAULT_MOON_PHASES: u8 = 8;
+/// How often the game time is resent, so clients don't drift away from the server.
+const TIME_SYNC_INTERVAL: Duration = Duration::from_secs(5);
+/// Real seconds it takes to fast-forward through the night once enough players sleep.
+const NIGHT_SKIP_SECONDS: f32 = 3.0;
+/// Most awake players listed in the sleep state, the client only shows a few.
+const AWAKE_SAMPLE_SIZE: usize = 5;
+
+/// How fast time passes in a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSettings {
+	/// Real seconds from sunrise to sunset.
+	pub daytime_seconds: u32,
+	/// Real seconds from sunset to sunrise.
+	pub nighttime_seconds: u32,
+	pub moon_phases: u8,
+	/// Share of the players in the world that have to sleep to skip the night, in percent.
+	pub sleep_percentage: u8,
+}
+
+impl Default for ClockSettings {
+	fn default() -> Self {
+		Self {
+			daytime_seconds: DEFAULT_DAYTIME_SECONDS,
+			nighttime_seconds: DEFAULT_NIGHTTIME_SECONDS,
+			moon_phases: DEFAULT_MOON_PHASES,
+			sleep_percentage: 100,
+		}
+	}
+}
+
+impl ClockSettings {
+	/// Real seconds a whole day lasts.
+	pub fn seconds_per_day(&self) -> u32 {
+		self.daytime_seconds + self.nighttime_seconds
+	}
+}
+
+/// Fast-forward to the next sunrise that's in progress.
+#[derive(Debug, Clone, Copy)]
+struct NightSkip {
+	start: f64,
+	target: f64,
+	elapsed: f32,
+}
+
+/// The game time of a world, running through days and nights at the configured speed.
+///
+/// Game time counts game seconds since the world was created, with [`SECONDS_PER_DAY`] to a day. Days and nights last
+/// the same in game time but not necessarily in real time, so time runs at a different speed during the night. Once
+/// enough players sleep at night, the rest of the night is skipped over a few seconds.
+#[derive(Debug)]
+pub struct WorldClock {
+	settings: ClockSettings,
+	game_time: f64,
+	paused: bool,
+	sleeping: HashSet<Uuid>,
+	skip: Option<NightSkip>,
+	since_sync: Duration,
+}
+
+impl WorldClock {
+	pub fn new(settings: ClockSettings, game_time: f64) -> Self {
+		Self {
+			settings,
+			game_time: game_time.max(0.0),
+			paused: false,
+			sleeping: HashSet::new(),
+			skip: None,
+			since_sync: Duration::ZERO,
+		}
+	}
+
+	pub fn settings(&self) -> &ClockSettings {
+		&self.settings
+	}
+
+	pub fn set_settings(&mut self, settings: ClockSettings) {
+		self.settings = settings;
+	}
+
+	pub fn game_time(&self) -> f64 {
+		self.game_time
+	}
+
+	pub fn set_game_time(&mut self, game_time: f64) {
+		self.game_time = game_time.max(0.0);
+		self.skip = None;
+	}
+
+	/// Number of whole days since the world was created.
+	pub fn day(&self) -> u64 {
+		(self.game_time / SECONDS_PER_DAY) as u64
+	}
+
+	/// Game seconds since midnight.
+	pub fn time_of_day(&self) -> f64 {
+		self.game_time % SECONDS_PER_DAY
+	}
+
+	/// Moves to the given time of the current day, which may go back in time.
+	pub fn set_time_of_day(&mut self, time_of_day: f64) {
+		self.set_game_time(self.day() as f64 * SECONDS_PER_DAY + time_of_day.rem_euclid(SECONDS_PER_DAY));
+	}
+
+	pub fn is_night(&self) -> bool {
+		!(SUNRISE..SUNSET).contains(&self.time_of_day())
+	}
+
+	/// Whether time stands still. A paused clock still syncs, so the clients stop too.
+	pub fn is_paused(&self) -> bool {
+		self.paused
+	}
+
+	pub fn set_paused(&mut self, paused: bool) {
+		self.paused = paused;
+	}
+
+	/// Game seconds that pass per real second at the current time of day.
+	pub fn rate(&self) -> f64 {
+		let real = if self.is_night() { self.settings.nighttime_seconds } else { self.settings.daytime_seconds };
+		(SECONDS_PER_DAY / 2.0) / real.max(1) as f64
+	}
+
+	/// Advances the clock by `elapsed` real time and returns the packets everyone in the world should get.
+	pub fn tick(&mut self, elapsed: Duration) -> Vec<Packet> {
+		let mut packets = Vec::new();
+		if let Some(mut skip) = self.skip.take() {
+			skip.elapsed += elapsed.as_secs_f32();
+			let progress = (skip.elapsed / NIGHT_SKIP_SECONDS).min(1.0) as f64;
+			self.game_time = skip.start + (skip.target - skip.start) * progress;
+			if progress < 1.0 {
+				self.skip = Some(skip);
+			} else {
+				self.sleeping.clear();
+				packets.push(
+					UpdateSleepState {
+						gray_fade: false,
+						sleep_ui: false,
+						clock: None,
+						multiplayer: None,
+					}
+					.into(),
+				);
+				packets.push(self.update_time().into());
+				self.since_sync = Duration::ZERO;
+			}
+		} else if !self.paused {
+			self.game_time += elapsed.as_secs_f64() * self.rate();
+		}
+
+		self.since_sync += elapsed;
+		if self.since_sync >= TIME_SYNC_INTERVAL {
+			self.since_sync = Duration::ZERO;
+			packets.push(self.update_time().into());
+		}
+		packets
+	}
+
+	/// Marks a player as sleeping or awake and starts skipping the night once enough of the `players` in the world
+	/// sleep. Returns the sleep state to send to the world.
+	pub fn set_sleeping(&mut self, uuid: Uuid, sleeping: bool, players: &HashSet<Uuid>) -> Vec<Packet> {
+		let changed = if sleeping { self.sleeping.insert(uuid) } else { self.sleeping.remove(&uuid) };
+		if !changed {
+			return Vec::new();
+		}
+		self.update_sleep(players)
+	}
+
+	/// Forgets a player that left the world, which may be all it took for the others to skip the night.
+	pub fn remove_player(&mut self, uuid: Uuid, players: &HashSet<Uuid>) -> Vec<Packet> {
+		if !self.sleeping.remove(&uuid) {
+			return Vec::new();
+		}
+		self.update_sleep(players)
+	}
+
+	pub fn sleeping(&self) -> usize {
+		self.sleeping.len()
+	}
+
+	pub fn is_skipping_night(&self) -> bool {
+		self.skip.is_some()
+	}
+
+	fn update_sleep(&mut self, players: &HashSet<Uuid>) -> Vec<Packet> {
+		let sleepers = self.sleeping.intersection(players).count();
+		let awake: Vec<Uuid> = players.difference(&self.sleeping).copied().collect();
+		let multiplayer = SleepMultiplayer {
+			sleepers_count: sleepers as i32,
+			awake_count: awake.len() as i32,
+			awake_sample: (!awake.is_empty()).then(|| awake.into_iter().take(AWAKE_SAMPLE_SIZE).collect()),
+		};
+		let enough = sleepers > 0 && sleepers * 100 >= players.len() * self.settings.sleep_percentage as usize;
+		if self.skip.is_none() && enough && self.is_night() {
+			let start = self.game_time;
+			let sunrise = self.day() as f64 * SECONDS_PER_DAY + SUNRISE;
+			let target = if self.time_of_day() < SUNRISE { sunrise } else { sunrise + SECONDS_PER_DAY };
+			self.skip = Some(NightSkip { start, target, elapsed: 0.0 });
+			return vec![
+				UpdateSleepState {
+					gray_fade: true,
+					sleep_ui: true,
+					clock: Some(SleepClock {
+						start_game_time: Some(instant(start)),
+						target_game_time: Some(instant(target)),
+						progress: 0.0,
+						duration_seconds: NIGHT_SKIP_SECONDS,
+					}),
+					multiplayer: Some(multiplayer),
+				}
+				.into(),
+			];
+		}
+		vec![
+			UpdateSleepState {
+				gray_fade: false,
+				sleep_ui: false,
+				clock: None,
+				multiplayer: Some(multiplayer),
+			}
+			.into(),
+		]
+	}
+
+	pub fn update_time(&self) -> UpdateTime {
+		UpdateTime {
+			game_time: Some(instant(self.game_time)),
+		}
+	}
+
+	pub fn update_time_settings(&self) -> UpdateTimeSettings {
+		UpdateTimeSettings {
+			daytime_duration_seconds: self.settings.daytime_seconds as i32,
+			nighttime_duration_seconds: self.settings.nighttime_seconds as i32,
+			total_moon_phases: self.settings.moon_phases,
+			time_paused: self.paused,
+		}
+	}
+
+	/// Everything a client needs to show the same time as the server.
+	pub fn time_packets(&self) -> Vec<Packet> {
+		vec![self.update_time_settings().into(), self.update_time().into()]
+	}
+}
+
+/// Formats a time of day as `HH:MM`.
+pub fn format_time_of_day(time_of_day: f64) -> String {
+	let minutes = (time_of_day.rem_euclid(SECONDS_PER_DAY) / 60.0) as u32;
+	format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Parses a time of day as `HH:MM`, in hours or as one of `sunrise`, `day`, `noon`, `sunset`, `night` and `midnight`.
+/// Returns game seconds since midnight.
+pub fn parse_time_of_day(input: &str) -> Option<f64> {
+	let hours = match input.to_ascii_lowercase().as_str() {
+		"sunrise" | "day" => 6.0,
+		"noon" => 12.0,
+		"sunset" => 18.0,
+		"night" => 20.0,
+		"midnight" => 0.0,
+		input => match input.split_once(':') {
+			Some((hours, minutes)) => {
+				let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+				if hours >= 24 || minutes >= 60 {
+					return None;
+				}
+				hours as f64 + minutes as f64 / 60.0
+			}
+			None => input.parse::<f64>().ok().filter(|hours| (0.0..24.0).contains(hours))?,
+		},
+	};
+	Some(hours * 3600.0)
+}
+
+fn instant(game_time: f64) -> InstantData {
+	let seconds = game_time.floor();
+	InstantData {
+		seconds: seconds as i64,
+		nanos: ((game_time - seconds) * 1e9) as i32,
+	}
+}
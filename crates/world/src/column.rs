@@ -0,0 +1,193 @@
+use bytes::{
+	Buf,
+	BufMut,
+	Bytes,
+	BytesMut,
+};
+
+use crate::{
+	error::{
+		ChunkError,
+		ChunkResult,
+	},
+	section::{
+		ChunkSection,
+		SECTION_AREA,
+		SECTION_SIZE,
+	},
+};
+
+/// Tint of columns nobody assigned a tint to, as `0xAARRGGBB`.
+pub const DEFAULT_TINT: u32 = 0xFF_5B_9E_28;
+/// Environment of columns nobody assigned an environment to.
+pub const DEFAULT_ENVIRONMENT: i32 = 0;
+
+/// Wire modes for the column maps.
+const MODE_UNIFORM: u8 = 0;
+const MODE_ARRAY: u8 = 1;
+
+/// Index of a column inside a chunk, the same as the `x`/`z` part of a block index.
+#[inline]
+pub fn column_index(x: usize, z: usize) -> usize {
+	((z & 31) << 5) | (x & 31)
+}
+
+/// Per-column data of a chunk: the heightmap, grass tint and environment ids.
+///
+/// The heightmap is owned by the chunk and follows its block edits, tints and environments are set by whoever generates
+/// the chunk.
+#[derive(Debug, Clone)]
+pub struct ColumnMaps {
+	/// Y of the highest non-air block per column, `-1` for columns that are all air.
+	heights: Box<[i16]>,
+	tints: Box<[u32]>,
+	environments: Box<[i32]>,
+}
+
+impl Default for ColumnMaps {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ColumnMaps {
+	pub fn new() -> Self {
+		Self {
+			heights: vec![-1; SECTION_AREA].into_boxed_slice(),
+			tints: vec![DEFAULT_TINT; SECTION_AREA].into_boxed_slice(),
+			environments: vec![DEFAULT_ENVIRONMENT; SECTION_AREA].into_boxed_slice(),
+		}
+	}
+
+	/// Y of the highest non-air block of the column, or `None` if the column is empty.
+	pub fn height(&self, x: usize, z: usize) -> Option<usize> {
+		usize::try_from(self.heights[column_index(x, z)]).ok()
+	}
+
+	pub fn tint(&self, x: usize, z: usize) -> u32 {
+		self.tints[column_index(x, z)]
+	}
+
+	pub fn set_tint(&mut self, x: usize, z: usize, tint: u32) {
+		self.tints[column_index(x, z)] = tint;
+	}
+
+	pub fn fill_tint(&mut self, tint: u32) {
+		self.tints.fill(tint);
+	}
+
+	pub fn environment(&self, x: usize, z: usize) -> i32 {
+		self.environments[column_index(x, z)]
+	}
+
+	pub fn set_environment(&mut self, x: usize, z: usize, environment: i32) {
+		self.environments[column_index(x, z)] = environment;
+	}
+
+	pub fn fill_environment(&mut self, environment: i32) {
+		self.environments.fill(environment);
+	}
+
+	/// Updates the heightmap after the block at `(x, y, z)` changed. Returns whether the height of the column changed.
+	pub(crate) fn update_height(&mut self, sections: &[ChunkSection], x: usize, y: usize, z: usize, air: bool) -> bool {
+		let index = column_index(x, z);
+		let height = self.heights[index];
+		let new = if !air {
+			height.max(y as i16)
+		} else if height == y as i16 {
+			scan_height(sections, x, y, z)
+		} else {
+			height
+		};
+		self.heights[index] = new;
+		new != height
+	}
+
+	/// Recomputes the whole heightmap from the blocks.
+	pub(crate) fn recompute_heights(&mut self, sections: &[ChunkSection]) {
+		let top = sections.len() * SECTION_SIZE;
+		for z in 0..SECTION_SIZE {
+			for x in 0..SECTION_SIZE {
+				self.heights[column_index(x, z)] = scan_height(sections, x, top, z);
+			}
+		}
+	}
+
+	/// Heightmap in the `SetChunkHeightmap` layout: one `i16` (little endian) per column in column index order.
+	pub fn heightmap_bytes(&self) -> Bytes {
+		let mut buf = BytesMut::with_capacity(SECTION_AREA * 2);
+		for height in self.heights.iter() {
+			buf.put_i16_le(*height);
+		}
+		buf.freeze()
+	}
+
+	/// Tintmap in the `SetChunkTintmap` layout: a `u8` mode, then either a single `u32` for the whole chunk (mode 0) or one
+	/// `u32` per column in column index order (mode 1). Tints are `0xAARRGGBB`, little endian.
+	pub fn tintmap_bytes(&self) -> Bytes {
+		encode_map(&self.tints, |buf, tint| buf.put_u32_le(*tint))
+	}
+
+	/// Environment ids in the `SetChunkEnvironments` layout, the same as [`tintmap_bytes`](Self::tintmap_bytes) with `i32` ids.
+	pub fn environments_bytes(&self) -> Bytes {
+		encode_map(&self.environments, |buf, environment| buf.put_i32_le(*environment))
+	}
+
+	/// Replaces the tintmap with the contents of a `SetChunkTintmap` payload.
+	pub fn apply_tintmap(&mut self, buf: &mut impl Buf) -> ChunkResult<()> {
+		decode_map(buf, &mut self.tints, 4, |buf| buf.get_u32_le())
+	}
+
+	/// Replaces the environment ids with the contents of a `SetChunkEnvironments` payload.
+	pub fn apply_environments(&mut self, buf: &mut impl Buf) -> ChunkResult<()> {
+		decode_map(buf, &mut self.environments, 4, |buf| buf.get_i32_le())
+	}
+}
+
+/// Highest non-air block of the column below `below`, or `-1`.
+fn scan_height(sections: &[ChunkSection], x: usize, below: usize, z: usize) -> i16 {
+	for y in (0..below).rev() {
+		let Some(section) = sections.get(y / SECTION_SIZE) else {
+			continue;
+		};
+		if section.blocks().is_empty() {
+			continue;
+		}
+		if !section.get(x, y % SECTION_SIZE, z).is_air() {
+			return y as i16;
+		}
+	}
+	-1
+}
+
+fn encode_map<T: PartialEq>(values: &[T], write: impl Fn(&mut BytesMut, &T)) -> Bytes {
+	let mut buf = BytesMut::new();
+	if values.iter().all(|value| *value == values[0]) {
+		buf.put_u8(MODE_UNIFORM);
+		write(&mut buf, &values[0]);
+	} else {
+		buf.put_u8(MODE_ARRAY);
+		for value in values {
+			write(&mut buf, value);
+		}
+	}
+	buf.freeze()
+}
+
+fn decode_map<B: Buf, T: Copy>(buf: &mut B, values: &mut [T], size: usize, read: impl Fn(&mut B) -> T) -> ChunkResult<()> {
+	ChunkError::ensure_remaining(buf.remaining(), 1)?;
+	match buf.get_u8() {
+		MODE_UNIFORM => {
+			ChunkError::ensure_remaining(buf.remaining(), size)?;
+			values.fill(read(buf));
+		}
+		MODE_ARRAY => {
+			ChunkError::ensure_remaining(buf.remaining(), size * values.len())?;
+			for value in values.iter_mut() {
+				*value = read(buf);
+			}
+		}
+		mode => return Err(ChunkError::InvalidMapMode(mode)),
+	}
+	Ok(())
+}
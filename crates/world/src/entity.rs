@@ -0,0 +1,268 @@
+use std::collections::{
+	HashMap,
+	HashSet,
+};
+
+use protocol::v2::{
+	entities::EntityUpdates,
+	setup::PlayerSkin,
+	ComponentUpdate,
+	ComponentUpdateType,
+	EntityUpdate,
+	Equipment,
+	Model,
+	ModelTransform,
+	MovementStates,
+	Nameplate,
+};
+use uuid::Uuid;
+
+use crate::chunk::chunk_coord;
+
+/// What players get to know about an entity. Components that are `None` aren't sent.
+#[derive(Debug, Clone)]
+pub struct EntityState {
+	pub transform: ModelTransform,
+	pub movement_states: Option<MovementStates>,
+	pub model: Option<Model>,
+	pub skin: Option<PlayerSkin>,
+	pub nameplate: Option<String>,
+	pub equipment: Option<Equipment>,
+}
+
+impl EntityState {
+	pub fn new(transform: ModelTransform) -> Self {
+		Self {
+			transform,
+			movement_states: None,
+			model: None,
+			skin: None,
+			nameplate: None,
+			equipment: None,
+		}
+	}
+
+	/// A player's entity, named after them.
+	pub fn player(username: &str, skin: Option<PlayerSkin>, transform: ModelTransform) -> Self {
+		Self {
+			skin,
+			nameplate: Some(username.to_string()),
+			..Self::new(transform)
+		}
+	}
+
+	fn chunk(&self) -> Option<(i32, i32)> {
+		self.transform.position.as_ref().map(|position| (chunk_coord(position.x.floor() as i32), chunk_coord(position.z.floor() as i32)))
+	}
+
+	/// The components this entity has, to send to a player who hasn't seen it yet.
+	fn components(&self) -> Vec<ComponentUpdateType> {
+		let mut components = vec![ComponentUpdateType::Transform];
+		let optional = [
+			(self.movement_states.is_some(), ComponentUpdateType::MovementStates),
+			(self.model.is_some(), ComponentUpdateType::Model),
+			(self.skin.is_some(), ComponentUpdateType::PlayerSkin),
+			(self.nameplate.is_some(), ComponentUpdateType::Nameplate),
+			(self.equipment.is_some(), ComponentUpdateType::Equipment),
+		];
+		components.extend(optional.into_iter().filter(|(present, _)| *present).map(|(_, component)| component));
+		components
+	}
+
+	fn component_update(&self, update_type: ComponentUpdateType) -> ComponentUpdate {
+		let mut update = ComponentUpdate {
+			update_type,
+			block_id: 0,
+			entity_scale: 1.0,
+			transform: None,
+			movement_states: None,
+			dynamic_light: None,
+			hitbox_collision_config_index: -1,
+			repulsion_config_index: -1,
+			prediction_id: Uuid::nil(),
+			mounted: None,
+			nameplate: None,
+			entity_ui_components: None,
+			combat_text_update: None,
+			model: None,
+			skin: None,
+			item: None,
+			equipment: None,
+			entity_stat_updates: None,
+			entity_effect_updates: None,
+			interactions: None,
+			sound_event_ids: None,
+			interaction_hint: None,
+			active_animations: None,
+		};
+		match update_type {
+			ComponentUpdateType::Transform => update.transform = Some(self.transform.clone()),
+			ComponentUpdateType::MovementStates => update.movement_states = self.movement_states.clone(),
+			ComponentUpdateType::Model => update.model = self.model.clone(),
+			ComponentUpdateType::PlayerSkin => update.skin = self.skin.clone(),
+			ComponentUpdateType::Nameplate => update.nameplate = Some(Nameplate { text: self.nameplate.clone() }),
+			ComponentUpdateType::Equipment => update.equipment = self.equipment.clone(),
+			_ => {}
+		}
+		update
+	}
+}
+
+struct TrackedEntity {
+	state: EntityState,
+	/// Components changed since the last tick.
+	dirty: HashSet<ComponentUpdateType>,
+}
+
+/// A player who's shown the entities around them.
+struct Viewer {
+	/// Their own entity, which they aren't sent and which they see from.
+	entity_id: i32,
+	/// How far they see, in chunks.
+	view_radius: i32,
+	/// Entities they were sent.
+	visible: HashSet<i32>,
+}
+
+/// Keeps track of the entities in a world by their network id and of which players see which of them.
+///
+/// Changes are collected and sent once per tick as an `EntityUpdates` per player: entities that came into view with all
+/// their components, visible entities with the components that changed, and the ids of the ones that went away.
+#[derive(Default)]
+pub struct EntityTracker {
+	entities: HashMap<i32, TrackedEntity>,
+	viewers: HashMap<Uuid, Viewer>,
+}
+
+impl EntityTracker {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entities.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entities.is_empty()
+	}
+
+	pub fn get(&self, network_id: i32) -> Option<&EntityState> {
+		self.entities.get(&network_id).map(|entity| &entity.state)
+	}
+
+	/// Adds an entity under a network id handed out by [`crate::World::next_entity_id`], replacing any entity that had it.
+	pub fn spawn(&mut self, network_id: i32, state: EntityState) {
+		self.entities.insert(network_id, TrackedEntity { state, dirty: HashSet::new() });
+	}
+
+	/// Removes an entity, players that saw it are told with the next tick. Returns whether it existed.
+	pub fn remove(&mut self, network_id: i32) -> bool {
+		self.entities.remove(&network_id).is_some()
+	}
+
+	pub fn set_transform(&mut self, network_id: i32, transform: ModelTransform, movement_states: Option<MovementStates>) {
+		self.update(network_id, |state, dirty| {
+			state.transform = transform;
+			dirty.insert(ComponentUpdateType::Transform);
+			if movement_states.is_some() {
+				state.movement_states = movement_states;
+				dirty.insert(ComponentUpdateType::MovementStates);
+			}
+		});
+	}
+
+	pub fn set_model(&mut self, network_id: i32, model: Option<Model>) {
+		self.update(network_id, |state, dirty| {
+			state.model = model;
+			dirty.insert(ComponentUpdateType::Model);
+		});
+	}
+
+	pub fn set_nameplate(&mut self, network_id: i32, nameplate: Option<String>) {
+		self.update(network_id, |state, dirty| {
+			state.nameplate = nameplate;
+			dirty.insert(ComponentUpdateType::Nameplate);
+		});
+	}
+
+	pub fn set_equipment(&mut self, network_id: i32, equipment: Option<Equipment>) {
+		self.update(network_id, |state, dirty| {
+			state.equipment = equipment;
+			dirty.insert(ComponentUpdateType::Equipment);
+		});
+	}
+
+	fn update(&mut self, network_id: i32, f: impl FnOnce(&mut EntityState, &mut HashSet<ComponentUpdateType>)) {
+		if let Some(entity) = self.entities.get_mut(&network_id) {
+			f(&mut entity.state, &mut entity.dirty);
+		}
+	}
+
+	/// Starts showing a player the entities around their own entity.
+	pub fn add_viewer(&mut self, uuid: Uuid, entity_id: i32, view_radius: i32) {
+		self.viewers.insert(
+			uuid,
+			Viewer {
+				entity_id,
+				view_radius,
+				visible: HashSet::new(),
+			},
+		);
+	}
+
+	pub fn set_view_radius(&mut self, uuid: Uuid, view_radius: i32) {
+		if let Some(viewer) = self.viewers.get_mut(&uuid) {
+			viewer.view_radius = view_radius;
+		}
+	}
+
+	pub fn remove_viewer(&mut self, uuid: Uuid) {
+		self.viewers.remove(&uuid);
+	}
+
+	/// Works out what every player sees now and returns the updates to send them, leaving out players with nothing new.
+	pub fn tick(&mut self) -> Vec<(Uuid, EntityUpdates)> {
+		let mut packets = Vec::new();
+		for (uuid, viewer) in &mut self.viewers {
+			let center = self.entities.get(&viewer.entity_id).and_then(|entity| entity.state.chunk());
+			let in_view = |entity: &TrackedEntity| {
+				matches!((center, entity.state.chunk()), (Some((cx, cz)), Some((x, z))) if (x - cx).abs() <= viewer.view_radius && (z - cz).abs() <= viewer.view_radius)
+			};
+
+			let visible: HashSet<i32> = self.entities.iter().filter(|(id, entity)| **id != viewer.entity_id && in_view(entity)).map(|(id, _)| *id).collect();
+			let removed: Vec<i32> = viewer.visible.difference(&visible).copied().collect();
+			let updates: Vec<EntityUpdate> = visible
+				.iter()
+				.filter_map(|id| {
+					let entity = &self.entities[id];
+					let components = if viewer.visible.contains(id) {
+						entity.dirty.iter().copied().collect()
+					} else {
+						entity.state.components()
+					};
+					(!components.is_empty()).then(|| EntityUpdate {
+						network_id: *id,
+						removed: None,
+						updates: Some(components.into_iter().map(|component| entity.state.component_update(component)).collect()),
+					})
+				})
+				.collect();
+			viewer.visible = visible;
+
+			if !removed.is_empty() || !updates.is_empty() {
+				packets.push((
+					*uuid,
+					EntityUpdates {
+						removed: (!removed.is_empty()).then_some(removed),
+						updates: (!updates.is_empty()).then_some(updates),
+					},
+				));
+			}
+		}
+		for entity in self.entities.values_mut() {
+			entity.dirty.clear();
+		}
+		packets
+	}
+}
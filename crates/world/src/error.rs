@@ -0,0 +1,137 @@
+#[derive(thiserror::Error, Debug)]
+pub enum ChunkError {
+	#[error("Incomplete data: expected at least {expected} bytes, found {found} bytes")]
+	Incomplete { expected: usize, found: usize },
+
+	#[error("Invalid palette type {0}")]
+	InvalidPaletteType(u8),
+
+	#[error("Invalid palette: {0}")]
+	InvalidPalette(String),
+
+	#[error("Section {0} is out of range")]
+	SectionOutOfRange(i32),
+
+	#[error("Invalid column map mode {0}")]
+	InvalidMapMode(u8),
+}
+
+impl ChunkError {
+	pub(crate) fn ensure_remaining(found: usize, expected: usize) -> ChunkResult<()> {
+		if found < expected { Err(Self::Incomplete { expected, found }) } else { Ok(()) }
+	}
+}
+
+pub type ChunkResult<T> = Result<T, ChunkError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RegionError {
+	#[error("IO error: {0}")]
+	Io(#[from] std::io::Error),
+
+	#[error("Invalid chunk data: {0}")]
+	Chunk(#[from] ChunkError),
+
+	#[error("Not a region file")]
+	InvalidMagic,
+
+	#[error("Unsupported region format version {0}")]
+	UnsupportedVersion(u16),
+
+	#[error("Corrupt region file: {0}")]
+	Corrupt(String),
+
+	#[error("Region task failed: {0}")]
+	Task(#[from] tokio::task::JoinError),
+
+	#[error("Chunk generation failed: {0}")]
+	Generator(#[from] GeneratorError),
+}
+
+pub type RegionResult<T> = Result<T, RegionError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum GeneratorError {
+	#[error("Generator pool job panicked: {0}")]
+	Panicked(String),
+}
+
+pub type GeneratorResult<T> = Result<T, GeneratorError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum MetaError {
+	#[error("IO error: {0}")]
+	Io(#[from] std::io::Error),
+
+	#[error("Invalid world metadata: {0}")]
+	Json(#[from] serde_json::Error),
+
+	#[error("Unsupported world metadata version {0}")]
+	UnsupportedVersion(u32),
+}
+
+pub type MetaResult<T> = Result<T, MetaError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum InventoryError {
+	#[error("Unknown inventory section {0}")]
+	UnknownSection(i32),
+
+	#[error("Slot {slot} doesn't exist in section {section}")]
+	InvalidSlot { section: i32, slot: i32 },
+
+	#[error("Slot {slot} in section {section} is empty")]
+	EmptySlot { section: i32, slot: i32 },
+
+	#[error("Slot {slot} in section {section} has no room")]
+	SlotFull { section: i32, slot: i32 },
+
+	#[error("Invalid item quantity {0}")]
+	InvalidQuantity(i32),
+
+	#[error("No room for the items")]
+	NoRoom,
+
+	#[error("Section {0} has no active slot")]
+	NoActiveSlot(i32),
+
+	#[error("No window is open")]
+	NoOpenWindow,
+
+	#[error("Window {0} isn't a crafting window")]
+	NotCrafting(i32),
+
+	#[error("Unknown recipe {0}")]
+	UnknownRecipe(String),
+
+	#[error("Recipe {0} can't be crafted here")]
+	WrongBench(String),
+
+	#[error("Missing ingredients")]
+	MissingIngredients,
+
+	#[error("Only creative players can do that")]
+	NotCreative,
+
+	#[error("There's no saved hotbar row {0}")]
+	InvalidHotbarRow(u8),
+}
+
+pub type InventoryResult<T> = Result<T, InventoryError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PlayerDataError {
+	#[error("IO error: {0}")]
+	Io(#[from] std::io::Error),
+
+	#[error("Invalid player data: {0}")]
+	Json(#[from] serde_json::Error),
+
+	#[error("Unsupported player data version {0}")]
+	UnsupportedVersion(u32),
+
+	#[error("Player data task failed: {0}")]
+	Task(#[from] tokio::task::JoinError),
+}
+
+pub type PlayerDataResult<T> = Result<T, PlayerDataError>;
@@ -0,0 +1,83 @@
+use bytes::{
+	Buf,
+	Bytes,
+	BytesMut,
+};
+
+use crate::{
+	error::ChunkResult,
+	palette::Palette,
+};
+
+/// The fluid in a single block: which fluid, and how full the block is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FluidState {
+	pub id: i32,
+	/// `1..=max_fluid_level` of the fluid, where the maximum is a source.
+	pub level: u8,
+}
+
+impl FluidState {
+	pub const EMPTY: FluidState = FluidState { id: 0, level: 0 };
+
+	pub fn new(id: i32, level: u8) -> Self {
+		if id == 0 || level == 0 { Self::EMPTY } else { Self { id, level } }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.id == 0 || self.level == 0
+	}
+}
+
+/// The fluids of a 32x32x32 section. Fluid ids and levels are each stored in their own palette.
+#[derive(Debug, Clone, Default)]
+pub struct FluidSection {
+	ids: Palette<i32>,
+	levels: Palette<u8>,
+}
+
+impl FluidSection {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.is_empty() && self.levels.is_empty()
+	}
+
+	#[inline]
+	pub fn get_index(&self, index: usize) -> FluidState {
+		FluidState {
+			id: self.ids.get(index),
+			level: self.levels.get(index),
+		}
+	}
+
+	/// Sets the fluid at the given block index, returning the previous state.
+	pub fn set_index(&mut self, index: usize, state: FluidState) -> FluidState {
+		let state = FluidState::new(state.id, state.level);
+		FluidState {
+			id: self.ids.set(index, state.id),
+			level: self.levels.set(index, state.level),
+		}
+	}
+
+	/// Encodes the section the way `SetFluids.data` carries it: the id palette followed by the level palette.
+	pub fn encode(&self, buf: &mut BytesMut) {
+		self.ids.encode(buf);
+		self.levels.encode(buf);
+	}
+
+	pub fn decode(buf: &mut impl Buf) -> ChunkResult<Self> {
+		Ok(Self {
+			ids: Palette::decode(buf)?,
+			levels: Palette::decode(buf)?,
+		})
+	}
+
+	pub fn to_bytes(&self) -> Bytes {
+		let mut buf = BytesMut::new();
+		self.encode(&mut buf);
+		buf.freeze()
+	}
+}
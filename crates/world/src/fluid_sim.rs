@@ -0,0 +1,293 @@
+use std::{
+	collections::{
+		BTreeMap,
+		HashMap,
+		HashSet,
+	},
+	sync::Arc,
+};
+
+use protocol::v2::{
+	world::{
+		ServerSetBlock,
+		ServerSetFluid,
+		ServerSetFluids,
+		SetFluidCmd,
+	},
+	Packet,
+	Vector3i,
+};
+
+use crate::{
+	chunk::{
+		chunk_coord,
+		local_coord,
+	},
+	fluid::FluidState,
+	registry::{
+		FluidRegistry,
+		FluidRules,
+	},
+	section::{
+		block_index,
+		BlockState,
+		SECTION_SIZE,
+	},
+};
+
+const HORIZONTAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const NEIGHBORS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+/// Block and fluid access for the [`FluidSimulator`], in world coordinates.
+/// Positions in chunks that aren't loaded or outside the world height return `None`, and fluids treat them like walls.
+pub trait FluidAccess {
+	fn block(&mut self, x: i32, y: i32, z: i32) -> Option<BlockState>;
+	fn fluid(&mut self, x: i32, y: i32, z: i32) -> Option<FluidState>;
+	fn set_block(&mut self, x: i32, y: i32, z: i32, state: BlockState);
+	fn set_fluid(&mut self, x: i32, y: i32, z: i32, state: FluidState);
+}
+
+/// What a fluid finds in a block it wants to flow into.
+enum Target {
+	Blocked,
+	/// Air, either empty or holding the same fluid.
+	Open(FluidState),
+	Other(FluidState),
+}
+
+/// Blocks and fluids a tick of the simulation changed, by world position.
+#[derive(Debug, Default)]
+pub struct FluidChanges {
+	pub fluids: HashMap<(i32, i32, i32), FluidState>,
+	pub blocks: HashMap<(i32, i32, i32), BlockState>,
+}
+
+impl FluidChanges {
+	pub fn is_empty(&self) -> bool {
+		self.fluids.is_empty() && self.blocks.is_empty()
+	}
+
+	/// Batches the changes into one `ServerSetFluid(s)` per section and one `ServerSetBlock` per block, each with the
+	/// chunk column it belongs to.
+	pub fn into_packets(self) -> Vec<(i32, i32, Packet)> {
+		let mut sections: HashMap<(i32, i32, i32), Vec<SetFluidCmd>> = HashMap::new();
+		let mut positions: HashMap<(i32, i32, i32), (i32, i32, i32)> = HashMap::new();
+		for ((x, y, z), state) in self.fluids {
+			let section = (chunk_coord(x), y / SECTION_SIZE as i32, chunk_coord(z));
+			let index = block_index(local_coord(x), y as usize, local_coord(z));
+			sections.entry(section).or_default().push(SetFluidCmd {
+				index: index as i16,
+				fluid_id: state.id,
+				fluid_level: state.level,
+			});
+			positions.insert(section, (x, y, z));
+		}
+
+		let mut packets: Vec<(i32, i32, Packet)> = Vec::new();
+		for ((cx, sy, cz), cmds) in sections {
+			let packet = match cmds.as_slice() {
+				[cmd] => {
+					let (x, y, z) = positions[&(cx, sy, cz)];
+					ServerSetFluid {
+						pos: Vector3i { x, y, z },
+						fluid_id: cmd.fluid_id,
+						fluid_level: cmd.fluid_level,
+					}
+					.into()
+				}
+				_ => ServerSetFluids {
+					pos: Vector3i { x: cx, y: sy, z: cz },
+					cmds,
+				}
+				.into(),
+			};
+			packets.push((cx, cz, packet));
+		}
+		for ((x, y, z), state) in self.blocks {
+			let packet = ServerSetBlock {
+				pos: Vector3i { x, y, z },
+				block_id: state.id,
+				filler: state.filler,
+				rotation: state.rotation,
+			};
+			packets.push((chunk_coord(x), chunk_coord(z), packet.into()));
+		}
+		packets
+	}
+}
+
+/// Spreads and drains fluids a step at a time, driven by the tickers of the fluid assets.
+///
+/// Only blocks that were scheduled are looked at, which happens whenever a fluid or a block next to it changes. Sources
+/// hold the fluid's maximum level, every block a fluid flows sideways costs one level, and falling fluid is one below a
+/// source. Flowing fluid that lost its source drains again if the fluid can demote.
+pub struct FluidSimulator {
+	registry: Arc<FluidRegistry>,
+	ticks_per_second: u32,
+	tick: u64,
+	scheduled: BTreeMap<u64, Vec<(i32, i32, i32)>>,
+	pending: HashSet<(i32, i32, i32)>,
+}
+
+impl FluidSimulator {
+	pub fn new(registry: Arc<FluidRegistry>, ticks_per_second: u32) -> Self {
+		Self {
+			registry,
+			ticks_per_second: ticks_per_second.max(1),
+			tick: 0,
+			scheduled: BTreeMap::new(),
+			pending: HashSet::new(),
+		}
+	}
+
+	pub fn registry(&self) -> &Arc<FluidRegistry> {
+		&self.registry
+	}
+
+	pub fn set_ticks_per_second(&mut self, ticks_per_second: u32) {
+		self.ticks_per_second = ticks_per_second.max(1);
+	}
+
+	/// Number of blocks waiting for a step.
+	pub fn pending(&self) -> usize {
+		self.pending.len()
+	}
+
+	/// Schedules a step for the block in `delay` ticks, unless one is already pending.
+	pub fn schedule(&mut self, x: i32, y: i32, z: i32, delay: u64) {
+		if self.pending.insert((x, y, z)) {
+			self.scheduled.entry(self.tick + delay.max(1)).or_default().push((x, y, z));
+		}
+	}
+
+	/// Schedules the block and its six neighbors, for when something changed there.
+	pub fn schedule_around(&mut self, x: i32, y: i32, z: i32, delay: u64) {
+		self.schedule(x, y, z, delay);
+		for (dx, dy, dz) in NEIGHBORS {
+			self.schedule(x + dx, y + dy, z + dz, delay);
+		}
+	}
+
+	fn delay(&self, rules: &FluidRules) -> u64 {
+		((rules.flow_rate * self.ticks_per_second as f32).round() as u64).max(1)
+	}
+
+	/// Advances the simulation by one tick and steps every block that is due.
+	pub fn tick(&mut self, access: &mut impl FluidAccess) -> FluidChanges {
+		self.tick += 1;
+		let mut changes = FluidChanges::default();
+		while let Some(entry) = self.scheduled.first_entry() {
+			if *entry.key() > self.tick {
+				break;
+			}
+			for (x, y, z) in entry.remove() {
+				self.pending.remove(&(x, y, z));
+				self.step(access, &mut changes, x, y, z);
+			}
+		}
+		changes
+	}
+
+	fn step(&mut self, access: &mut impl FluidAccess, changes: &mut FluidChanges, x: i32, y: i32, z: i32) {
+		let Some(mut current) = access.fluid(x, y, z).filter(|fluid| !fluid.is_empty()) else {
+			return;
+		};
+		let Some(rules) = self.registry.rules(current.id).filter(|rules| rules.spreads) else {
+			return;
+		};
+		let delay = self.delay(&rules);
+		let falling = rules.max_level.saturating_sub(1).max(1);
+
+		if current.level < rules.max_level {
+			let expected = self.expected_level(access, current.id, &rules, x, y, z);
+			if expected != current.level && (rules.can_demote || expected > current.level) {
+				current = FluidState::new(current.id, expected);
+				self.set_fluid(access, changes, x, y, z, current, delay);
+				if current.is_empty() {
+					return;
+				}
+			}
+		}
+
+		match self.target(access, current.id, x, y - 1, z) {
+			Target::Open(below) if below.is_empty() || below.level < falling => {
+				self.set_fluid(access, changes, x, y - 1, z, FluidState::new(current.id, falling), delay);
+				return;
+			}
+			// Fluid already falling below keeps flowing down instead of spreading
+			Target::Open(below) if below.level < rules.max_level => return,
+			Target::Other(below) if self.collide(access, changes, current.id, below.id, x, y - 1, z, delay) => return,
+			_ => {}
+		}
+
+		let next = if current.level >= rules.max_level { rules.max_level - 1 } else { current.level - 1 };
+		if next == 0 {
+			return;
+		}
+		for (dx, dz) in HORIZONTAL {
+			let (nx, nz) = (x + dx, z + dz);
+			match self.target(access, current.id, nx, y, nz) {
+				Target::Open(side) if side.is_empty() || side.level < next => {
+					self.set_fluid(access, changes, nx, y, nz, FluidState::new(current.id, next), delay);
+				}
+				Target::Other(side) => {
+					self.collide(access, changes, current.id, side.id, nx, y, nz, delay);
+				}
+				_ => {}
+			}
+		}
+	}
+
+	/// The level a flowing block should have based on what feeds it: fluid above makes it fall, otherwise it's one less
+	/// than its highest neighbor that doesn't fall itself.
+	fn expected_level(&self, access: &mut impl FluidAccess, id: i32, rules: &FluidRules, x: i32, y: i32, z: i32) -> u8 {
+		if access.fluid(x, y + 1, z).is_some_and(|above| above.id == id && !above.is_empty()) {
+			return rules.max_level.saturating_sub(1).max(1);
+		}
+		let mut level = 0;
+		for (dx, dz) in HORIZONTAL {
+			let Some(side) = access.fluid(x + dx, y, z + dz).filter(|side| side.id == id && !side.is_empty()) else {
+				continue;
+			};
+			let supported = side.level >= rules.max_level || matches!(self.target(access, id, x + dx, y - 1, z + dz), Target::Blocked | Target::Other(_));
+			if supported {
+				level = level.max(side.level - 1);
+			}
+		}
+		level
+	}
+
+	fn target(&self, access: &mut impl FluidAccess, id: i32, x: i32, y: i32, z: i32) -> Target {
+		match (access.block(x, y, z), access.fluid(x, y, z)) {
+			(Some(block), Some(fluid)) if block.is_air() => {
+				if fluid.is_empty() || fluid.id == id {
+					Target::Open(fluid)
+				} else {
+					Target::Other(fluid)
+				}
+			}
+			_ => Target::Blocked,
+		}
+	}
+
+	/// Handles fluid `from` flowing into fluid `into` at the given position. Returns whether the fluids interact at all.
+	#[allow(clippy::too_many_arguments)]
+	fn collide(&mut self, access: &mut impl FluidAccess, changes: &mut FluidChanges, from: i32, into: i32, x: i32, y: i32, z: i32, delay: u64) -> bool {
+		let Some(block) = self.registry.collision(from, into) else {
+			return false;
+		};
+		if let Some(block) = block {
+			access.set_block(x, y, z, block);
+			changes.blocks.insert((x, y, z), block);
+			self.set_fluid(access, changes, x, y, z, FluidState::EMPTY, delay);
+		}
+		true
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn set_fluid(&mut self, access: &mut impl FluidAccess, changes: &mut FluidChanges, x: i32, y: i32, z: i32, state: FluidState, delay: u64) {
+		access.set_fluid(x, y, z, state);
+		changes.fluids.insert((x, y, z), state);
+		self.schedule_around(x, y, z, delay);
+	}
+}
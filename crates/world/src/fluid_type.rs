@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use assets::{
+	Asset,
+	StoreError,
+	StoreResult,
+};
+use protocol::v2::{
+	Color,
+	ColorLight,
+	Fluid,
+};
+use serde::Deserialize;
+
+use crate::block_type::{
+	parse_light_color,
+	parse_opacity,
+	parse_rgb,
+	LightDefinition,
+	TextureDefinition,
+};
+
+/// Level of a fluid source when the definition doesn't say.
+pub const DEFAULT_MAX_FLUID_LEVEL: u8 = 8;
+/// Highest level a fluid can have, levels are sent as nibbles.
+pub const MAX_FLUID_LEVEL: u8 = 15;
+
+/// A fluid asset, keyed by its file name.
+#[derive(Debug, Clone)]
+pub struct FluidTypeAsset {
+	name: String,
+	pub definition: FluidDefinition,
+}
+
+impl Asset for FluidTypeAsset {
+	type Key = String;
+
+	fn key(&self) -> &Self::Key {
+		&self.name
+	}
+}
+
+impl FluidTypeAsset {
+	pub fn new(name: impl Into<String>, definition: FluidDefinition) -> Self {
+		Self { name: name.into(), definition }
+	}
+
+	pub fn from_json(name: impl Into<String>, bytes: &[u8]) -> StoreResult<Self> {
+		let definition = serde_json::from_slice(bytes).map_err(|e| StoreError::Decode(e.to_string()))?;
+		Ok(Self::new(name, definition))
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// The level of a source block. Flowing fluid loses one level per block it spreads sideways.
+	pub fn max_level(&self) -> u8 {
+		self.definition.max_fluid_level.unwrap_or(DEFAULT_MAX_FLUID_LEVEL).clamp(1, MAX_FLUID_LEVEL)
+	}
+
+	/// Seconds between two steps of the fluid.
+	pub fn flow_rate(&self) -> f32 {
+		self.definition.ticker.flow_rate.unwrap_or(0.5).max(0.0)
+	}
+
+	pub fn to_protocol(&self) -> Fluid {
+		let definition = &self.definition;
+		let light = definition.light.as_ref().map(|light| {
+			let [red, green, blue] = light.color.as_deref().and_then(parse_light_color).unwrap_or([0; 3]);
+			ColorLight {
+				radius: light.radius.unwrap_or_else(|| red.max(green).max(blue)),
+				red,
+				green,
+				blue,
+			}
+		});
+		Fluid {
+			max_fluid_level: self.max_level() as i32,
+			requires_alpha_blending: definition.requires_alpha_blending,
+			opacity: parse_opacity(definition.opacity.as_deref()),
+			light,
+			fluid_fx_index: 0,
+			block_sound_set_index: 0,
+			particle_color: definition.particle_color.as_deref().and_then(parse_rgb).map(|[red, green, blue]| Color { red, green, blue }),
+			id: Some(self.name.clone()),
+			cube_textures: (!definition.textures.is_empty()).then(|| definition.textures.iter().map(TextureDefinition::to_protocol).collect()),
+			shader_effect: None,
+			block_particle_set_id: None,
+			tag_indexes: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct FluidDefinition {
+	pub max_fluid_level: Option<u8>,
+	pub opacity: Option<String>,
+	pub requires_alpha_blending: bool,
+	pub light: Option<LightDefinition>,
+	pub particle_color: Option<String>,
+	pub textures: Vec<TextureDefinition>,
+	pub ticker: FluidTicker,
+}
+
+/// How a fluid moves.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct FluidTicker {
+	/// Seconds between two steps of the fluid.
+	pub flow_rate: Option<f32>,
+	/// Whether flowing fluid drains away once nothing feeds it anymore.
+	pub can_demote: bool,
+	/// Whether the fluid flows at all, `false` keeps it where it was placed.
+	pub spreads: bool,
+	/// What happens when this fluid flows into another one, by the other fluid's name.
+	pub collisions: HashMap<String, FluidCollision>,
+}
+
+impl Default for FluidTicker {
+	fn default() -> Self {
+		Self {
+			flow_rate: None,
+			can_demote: true,
+			spreads: true,
+			collisions: HashMap::new(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct FluidCollision {
+	/// Block that replaces both fluids where they meet. Without one, the flowing fluid just stops.
+	pub block_to_place: Option<String>,
+}
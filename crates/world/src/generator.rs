@@ -0,0 +1,325 @@
+use std::{
+	any::Any,
+	fmt::{
+		Display,
+		Formatter,
+	},
+	panic::{
+		self,
+		AssertUnwindSafe,
+	},
+	str::FromStr,
+	sync::Arc,
+};
+
+use rayon::{
+	ThreadPool,
+	ThreadPoolBuilder,
+};
+use tokio::sync::oneshot;
+
+use crate::{
+	chunk::{
+		Chunk,
+		CHUNK_HEIGHT,
+	},
+	column::DEFAULT_TINT,
+	error::{
+		GeneratorError,
+		GeneratorResult,
+	},
+	fluid::FluidState,
+	section::{
+		block_index,
+		BlockState,
+		ChunkSection,
+		SECTION_SIZE,
+	},
+};
+
+/// Fills freshly created chunks with terrain.
+///
+/// Generators have to be deterministic: the same seed and chunk coordinates always produce the same chunk.
+pub trait WorldGenerator: Send + Sync {
+	fn name(&self) -> &'static str;
+
+	/// Fills `chunk`, which is empty, based on the seed and its coordinates.
+	fn generate(&self, seed: u64, chunk: &mut Chunk);
+}
+
+/// The built-in generators, as they are named in the server config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GeneratorKind {
+	Void,
+	Flat,
+	#[default]
+	Noise,
+}
+
+impl GeneratorKind {
+	pub fn create(self, blocks: TerrainBlocks) -> Arc<dyn WorldGenerator> {
+		match self {
+			GeneratorKind::Void => Arc::new(VoidGenerator),
+			GeneratorKind::Flat => Arc::new(FlatGenerator::from_blocks(&blocks)),
+			GeneratorKind::Noise => Arc::new(NoiseGenerator::new(blocks)),
+		}
+	}
+}
+
+impl FromStr for GeneratorKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim().to_ascii_lowercase().as_str() {
+			"void" => Ok(GeneratorKind::Void),
+			"flat" => Ok(GeneratorKind::Flat),
+			"noise" => Ok(GeneratorKind::Noise),
+			other => Err(format!("Unknown world generator '{}', expected one of: void, flat, noise", other)),
+		}
+	}
+}
+
+impl Display for GeneratorKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			GeneratorKind::Void => "void",
+			GeneratorKind::Flat => "flat",
+			GeneratorKind::Noise => "noise",
+		})
+	}
+}
+
+/// Block ids the built-in generators build terrain out of, and the fluid filling the oceans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainBlocks {
+	pub bedrock: BlockState,
+	pub stone: BlockState,
+	pub dirt: BlockState,
+	pub grass: BlockState,
+	pub sand: BlockState,
+	pub water: FluidState,
+}
+
+impl Default for TerrainBlocks {
+	fn default() -> Self {
+		Self {
+			bedrock: BlockState::new(1),
+			stone: BlockState::new(2),
+			dirt: BlockState::new(3),
+			grass: BlockState::new(4),
+			sand: BlockState::new(5),
+			water: FluidState::new(1, 8),
+		}
+	}
+}
+
+/// Leaves every chunk empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VoidGenerator;
+
+impl WorldGenerator for VoidGenerator {
+	fn name(&self) -> &'static str {
+		"void"
+	}
+
+	fn generate(&self, _seed: u64, _chunk: &mut Chunk) {}
+}
+
+/// Stacks the same layers everywhere, bottom to top.
+#[derive(Debug, Clone)]
+pub struct FlatGenerator {
+	layers: Vec<(BlockState, usize)>,
+}
+
+impl FlatGenerator {
+	/// Creates a generator from `(block, thickness)` pairs, bottom to top. Layers above the world height are cut off.
+	pub fn new(layers: Vec<(BlockState, usize)>) -> Self {
+		Self { layers }
+	}
+
+	/// A bedrock floor, stone, dirt and a layer of grass on top.
+	pub fn from_blocks(blocks: &TerrainBlocks) -> Self {
+		Self::new(vec![(blocks.bedrock, 1), (blocks.stone, 60), (blocks.dirt, 3), (blocks.grass, 1)])
+	}
+}
+
+impl WorldGenerator for FlatGenerator {
+	fn name(&self) -> &'static str {
+		"flat"
+	}
+
+	fn generate(&self, _seed: u64, chunk: &mut Chunk) {
+		let mut y = 0;
+		for (block, thickness) in &self.layers {
+			let top = (y + thickness).min(CHUNK_HEIGHT);
+			// Whole sections made of one block skip the per-block writes
+			while y < top {
+				let section_y = y / SECTION_SIZE;
+				if y % SECTION_SIZE == 0 && top - y >= SECTION_SIZE {
+					chunk.sections[section_y] = ChunkSection::filled(*block);
+					y += SECTION_SIZE;
+					continue;
+				}
+				for z in 0..SECTION_SIZE {
+					for x in 0..SECTION_SIZE {
+						chunk.sections[section_y].set(x, y % SECTION_SIZE, z, *block);
+					}
+				}
+				y += 1;
+			}
+		}
+		chunk.maps.recompute_heights(&chunk.sections);
+	}
+}
+
+/// Rolling hills from layered value noise, with water filling everything below sea level.
+#[derive(Debug, Clone)]
+pub struct NoiseGenerator {
+	blocks: TerrainBlocks,
+	pub sea_level: usize,
+	pub base_height: f64,
+	pub amplitude: f64,
+	/// Horizontal size of the largest features in blocks.
+	pub scale: f64,
+	pub octaves: u32,
+}
+
+impl NoiseGenerator {
+	pub fn new(blocks: TerrainBlocks) -> Self {
+		Self {
+			blocks,
+			sea_level: 96,
+			base_height: 100.0,
+			amplitude: 48.0,
+			scale: 256.0,
+			octaves: 5,
+		}
+	}
+
+	/// Terrain height at the given world column.
+	pub fn height_at(&self, seed: u64, x: i32, z: i32) -> usize {
+		let mut value = 0.0;
+		let mut frequency = 1.0 / self.scale;
+		let mut weight = 1.0;
+		let mut total = 0.0;
+		for octave in 0..self.octaves {
+			value += value_noise(seed.wrapping_add(octave as u64), x as f64 * frequency, z as f64 * frequency) * weight;
+			total += weight;
+			frequency *= 2.0;
+			weight *= 0.5;
+		}
+		let height = self.base_height + value / total * self.amplitude;
+		height.clamp(1.0, (CHUNK_HEIGHT - 1) as f64) as usize
+	}
+}
+
+impl WorldGenerator for NoiseGenerator {
+	fn name(&self) -> &'static str {
+		"noise"
+	}
+
+	fn generate(&self, seed: u64, chunk: &mut Chunk) {
+		let (base_x, base_z) = (chunk.x() * SECTION_SIZE as i32, chunk.z() * SECTION_SIZE as i32);
+		for z in 0..SECTION_SIZE {
+			for x in 0..SECTION_SIZE {
+				let height = self.height_at(seed, base_x + x as i32, base_z + z as i32);
+				let beach = height <= self.sea_level + 1;
+				for y in height + 1..=self.sea_level {
+					chunk.fluids[y / SECTION_SIZE].set_index(block_index(x, y, z), self.blocks.water);
+				}
+				for y in 0..=height {
+					let block = if y == 0 {
+						self.blocks.bedrock
+					} else if y + 4 <= height {
+						self.blocks.stone
+					} else if beach {
+						self.blocks.sand
+					} else if y == height {
+						self.blocks.grass
+					} else {
+						self.blocks.dirt
+					};
+					chunk.sections[y / SECTION_SIZE].set(x, y % SECTION_SIZE, z, block);
+				}
+
+				// Grass gets a little darker the higher up it is
+				let shade = (height.saturating_sub(self.sea_level) as u32 / 4).min(0x30);
+				let tint = DEFAULT_TINT - ((shade << 16) | (shade << 8) | (shade / 2));
+				chunk.maps.set_tint(x, z, tint);
+			}
+		}
+		chunk.maps.recompute_heights(&chunk.sections);
+	}
+}
+
+/// Smoothly interpolated noise in `-1.0..=1.0` with one random value per integer lattice point.
+fn value_noise(seed: u64, x: f64, z: f64) -> f64 {
+	let (x0, z0) = (x.floor(), z.floor());
+	let (fx, fz) = (smooth(x - x0), smooth(z - z0));
+	let (x0, z0) = (x0 as i64, z0 as i64);
+	let top = lerp(lattice(seed, x0, z0), lattice(seed, x0 + 1, z0), fx);
+	let bottom = lerp(lattice(seed, x0, z0 + 1), lattice(seed, x0 + 1, z0 + 1), fx);
+	lerp(top, bottom, fz)
+}
+
+fn lattice(seed: u64, x: i64, z: i64) -> f64 {
+	let mut hash = seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+	// splitmix64 finalizer
+	hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	hash ^= hash >> 31;
+	(hash >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+}
+
+#[inline]
+fn smooth(t: f64) -> f64 {
+	t * t * (3.0 - 2.0 * t)
+}
+
+#[inline]
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+	a + (b - a) * t
+}
+
+/// Runs a [`WorldGenerator`] and other CPU heavy chunk work on a dedicated rayon pool, so async tasks never block on it.
+pub struct GeneratorPool {
+	pool: ThreadPool,
+	generator: Arc<dyn WorldGenerator>,
+	seed: u64,
+}
+
+impl GeneratorPool {
+	/// Creates a pool with `threads` workers, or one per core if `threads` is 0.
+	pub fn new(generator: Arc<dyn WorldGenerator>, seed: u64, threads: usize) -> Result<Self, rayon::ThreadPoolBuildError> {
+		let pool = ThreadPoolBuilder::new().num_threads(threads).thread_name(|index| format!("worldgen-{}", index)).build()?;
+		Ok(Self { pool, generator, seed })
+	}
+
+	pub fn generator(&self) -> &Arc<dyn WorldGenerator> {
+		&self.generator
+	}
+
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// Runs `job` on the pool and waits for its result. A panicking job is caught, as rayon would abort the process.
+	pub async fn run<R: Send + 'static>(&self, job: impl FnOnce() -> R + Send + 'static) -> GeneratorResult<R> {
+		let (tx, rx) = oneshot::channel();
+		self.pool.spawn(move || {
+			let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(job)));
+		});
+		match rx.await {
+			Ok(Ok(result)) => Ok(result),
+			Ok(Err(payload)) => Err(GeneratorError::Panicked(panic_message(payload.as_ref()))),
+			Err(_) => Err(GeneratorError::Panicked("job was dropped".to_string())),
+		}
+	}
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+	match payload.downcast_ref::<&str>() {
+		Some(message) => message.to_string(),
+		None => payload.downcast_ref::<String>().cloned().unwrap_or_else(|| "unknown panic".to_string()),
+	}
+}
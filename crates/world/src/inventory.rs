@@ -0,0 +1,634 @@
+use std::collections::{
+	BTreeMap,
+	HashMap,
+};
+
+use protocol::v2::{
+	inventory::{
+		InventoryActionType,
+		InventorySection,
+		SmartMoveType,
+		UpdatePlayerInventory,
+	},
+	ItemQuantity,
+	ItemWithAllMetadata,
+	SortType,
+};
+use serde::{
+	Deserialize,
+	Serialize,
+};
+
+use crate::{
+	error::{
+		InventoryError,
+		InventoryResult,
+	},
+	registry::RecipeRegistry,
+	window::WindowManager,
+};
+
+/// How many items of a kind fit in one slot, unless they wear down, which keeps them from stacking at all.
+pub const DEFAULT_MAX_STACK: i32 = 100;
+
+pub const HOTBAR_SECTION_ID: i32 = -1;
+pub const STORAGE_SECTION_ID: i32 = -2;
+pub const ARMOR_SECTION_ID: i32 = -3;
+pub const UTILITY_SECTION_ID: i32 = -5;
+pub const BUILDER_MATERIAL_SECTION_ID: i32 = -6;
+pub const TOOLS_SECTION_ID: i32 = -8;
+pub const BACKPACK_SECTION_ID: i32 = -9;
+
+const HOTBAR_CAPACITY: i16 = 9;
+const STORAGE_CAPACITY: i16 = 36;
+const ARMOR_CAPACITY: i16 = 4;
+const UTILITY_CAPACITY: i16 = 4;
+/// How many hotbar rows a player can save.
+pub const SAVED_HOTBAR_ROWS: u8 = 10;
+
+/// Some amount of one item, along with the state that sets it apart from other items of its kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemStack {
+	pub item_id: String,
+	pub quantity: i32,
+	/// Durability left, only meaningful when `max_durability` is above zero.
+	#[serde(default)]
+	pub durability: f64,
+	#[serde(default)]
+	pub max_durability: f64,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub metadata: Option<String>,
+}
+
+impl ItemStack {
+	pub fn new(item_id: impl Into<String>, quantity: i32) -> Self {
+		Self {
+			item_id: item_id.into(),
+			quantity,
+			durability: 0.0,
+			max_durability: 0.0,
+			metadata: None,
+		}
+	}
+
+	/// An item that wears down, in mint condition.
+	pub fn with_durability(mut self, max_durability: f64) -> Self {
+		self.durability = max_durability;
+		self.max_durability = max_durability;
+		self
+	}
+
+	pub fn has_durability(&self) -> bool {
+		self.max_durability > 0.0
+	}
+
+	pub fn max_stack(&self) -> i32 {
+		if self.has_durability() { 1 } else { DEFAULT_MAX_STACK }
+	}
+
+	/// Whether both stacks are the same item and can be merged.
+	pub fn stacks_with(&self, other: &ItemStack) -> bool {
+		self.item_id == other.item_id && self.metadata == other.metadata && !self.has_durability() && !other.has_durability()
+	}
+
+	/// An item taken from the creative library, which has no state of its own. `None` for no item at all.
+	pub fn creative(item: &ItemQuantity) -> InventoryResult<Option<Self>> {
+		let Some(item_id) = item.item_id.as_deref().filter(|id| !id.is_empty()) else {
+			return Ok(None);
+		};
+		let stack = Self::new(item_id, item.quantity);
+		if !(1..=stack.max_stack()).contains(&item.quantity) {
+			return Err(InventoryError::InvalidQuantity(item.quantity));
+		}
+		Ok(Some(stack))
+	}
+
+	/// Wears the item down. Returns whether it broke.
+	pub fn damage(&mut self, amount: f64) -> bool {
+		if !self.has_durability() {
+			return false;
+		}
+		self.durability = (self.durability - amount).max(0.0);
+		self.durability == 0.0
+	}
+
+	/// Takes `quantity` items off this stack into a new one.
+	pub fn split(&mut self, quantity: i32) -> ItemStack {
+		self.quantity -= quantity;
+		ItemStack { quantity, ..self.clone() }
+	}
+
+	pub fn to_packet(&self) -> ItemWithAllMetadata {
+		ItemWithAllMetadata {
+			quantity: self.quantity,
+			durability: self.durability,
+			max_durability: self.max_durability,
+			override_dropped_item_animation: false,
+			item_id: self.item_id.clone(),
+			metadata: self.metadata.clone(),
+		}
+	}
+}
+
+/// A fixed number of item slots.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemContainer {
+	capacity: i16,
+	slots: BTreeMap<i16, ItemStack>,
+}
+
+impl ItemContainer {
+	pub fn new(capacity: i16) -> Self {
+		Self {
+			capacity,
+			slots: BTreeMap::new(),
+		}
+	}
+
+	pub fn capacity(&self) -> i16 {
+		self.capacity
+	}
+
+	pub fn contains_slot(&self, slot: i32) -> bool {
+		(0..self.capacity as i32).contains(&slot)
+	}
+
+	pub fn get(&self, slot: i32) -> Option<&ItemStack> {
+		self.slots.get(&i16::try_from(slot).ok()?)
+	}
+
+	pub fn get_mut(&mut self, slot: i32) -> Option<&mut ItemStack> {
+		self.slots.get_mut(&i16::try_from(slot).ok()?)
+	}
+
+	/// Puts a stack into a slot, or empties it for `None`. Returns what was there before.
+	pub fn set(&mut self, slot: i32, stack: Option<ItemStack>) -> Option<ItemStack> {
+		if !self.contains_slot(slot) {
+			return stack;
+		}
+		match stack.filter(|stack| stack.quantity > 0) {
+			Some(stack) => self.slots.insert(slot as i16, stack),
+			None => self.slots.remove(&(slot as i16)),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.slots.is_empty()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (i32, &ItemStack)> {
+		self.slots.iter().map(|(slot, stack)| (*slot as i32, stack))
+	}
+
+	/// Adds items, topping up stacks of the same item before filling empty slots. Returns what didn't fit.
+	pub fn add(&mut self, mut stack: ItemStack) -> Option<ItemStack> {
+		for existing in self.slots.values_mut() {
+			if existing.stacks_with(&stack) {
+				let moved = stack.quantity.min(existing.max_stack() - existing.quantity).max(0);
+				existing.quantity += moved;
+				stack.quantity -= moved;
+				if stack.quantity == 0 {
+					return None;
+				}
+			}
+		}
+		for slot in 0..self.capacity {
+			if self.slots.contains_key(&slot) {
+				continue;
+			}
+			let moved = stack.quantity.min(stack.max_stack());
+			self.slots.insert(slot, stack.split(moved));
+			if stack.quantity == 0 {
+				return None;
+			}
+		}
+		Some(stack)
+	}
+
+	/// Merges stacks of the same item and packs everything into the first slots, in order.
+	pub fn sort(&mut self, sort_type: SortType) {
+		let mut stacks: Vec<ItemStack> = std::mem::take(&mut self.slots).into_values().collect();
+		// Items don't know their type or rarity yet, so every order falls back to the name
+		match sort_type {
+			SortType::Name | SortType::Type | SortType::Rarity => stacks.sort_by(|a, b| a.item_id.cmp(&b.item_id).then(b.quantity.cmp(&a.quantity))),
+		}
+		for stack in stacks {
+			self.add(stack);
+		}
+	}
+
+	pub fn to_section(&self) -> InventorySection {
+		InventorySection {
+			capacity: self.capacity,
+			items: Some(self.slots.iter().map(|(slot, stack)| (*slot as i32, stack.to_packet())).collect::<HashMap<_, _>>()),
+		}
+	}
+}
+
+/// Everything a player carries, split into the sections the client shows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerInventory {
+	pub hotbar: ItemContainer,
+	pub storage: ItemContainer,
+	pub armor: ItemContainer,
+	pub utility: ItemContainer,
+	pub builder_material: ItemContainer,
+	pub tools: ItemContainer,
+	pub backpack: ItemContainer,
+	pub active_hotbar_slot: i32,
+	/// `-1` while no utility item is selected.
+	pub active_utility_slot: i32,
+	/// `-1` while no tool is selected.
+	pub active_tools_slot: i32,
+	/// Hotbar layouts the player saved to recall later, by row.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub saved_hotbars: BTreeMap<u8, ItemContainer>,
+	#[serde(skip, default = "default_sort_type")]
+	pub sort_type: SortType,
+	/// Windows the player has open. Container windows are sections too, with the window id as section id.
+	#[serde(skip)]
+	pub windows: WindowManager,
+}
+
+impl Default for PlayerInventory {
+	fn default() -> Self {
+		Self {
+			hotbar: ItemContainer::new(HOTBAR_CAPACITY),
+			storage: ItemContainer::new(STORAGE_CAPACITY),
+			armor: ItemContainer::new(ARMOR_CAPACITY),
+			utility: ItemContainer::new(UTILITY_CAPACITY),
+			builder_material: ItemContainer::new(0),
+			tools: ItemContainer::new(0),
+			backpack: ItemContainer::new(0),
+			active_hotbar_slot: 0,
+			active_utility_slot: -1,
+			active_tools_slot: -1,
+			saved_hotbars: BTreeMap::new(),
+			sort_type: default_sort_type(),
+			windows: WindowManager::new(),
+		}
+	}
+}
+
+impl PlayerInventory {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn section(&self, section_id: i32) -> InventoryResult<&ItemContainer> {
+		Ok(match section_id {
+			HOTBAR_SECTION_ID => &self.hotbar,
+			STORAGE_SECTION_ID => &self.storage,
+			ARMOR_SECTION_ID => &self.armor,
+			UTILITY_SECTION_ID => &self.utility,
+			BUILDER_MATERIAL_SECTION_ID => &self.builder_material,
+			TOOLS_SECTION_ID => &self.tools,
+			BACKPACK_SECTION_ID => &self.backpack,
+			window if window > 0 => self.windows.get(window).and_then(|window| window.items()).ok_or(InventoryError::UnknownSection(section_id))?,
+			_ => return Err(InventoryError::UnknownSection(section_id)),
+		})
+	}
+
+	pub fn section_mut(&mut self, section_id: i32) -> InventoryResult<&mut ItemContainer> {
+		Ok(match section_id {
+			HOTBAR_SECTION_ID => &mut self.hotbar,
+			STORAGE_SECTION_ID => &mut self.storage,
+			ARMOR_SECTION_ID => &mut self.armor,
+			UTILITY_SECTION_ID => &mut self.utility,
+			BUILDER_MATERIAL_SECTION_ID => &mut self.builder_material,
+			TOOLS_SECTION_ID => &mut self.tools,
+			BACKPACK_SECTION_ID => &mut self.backpack,
+			window if window > 0 => self.windows.get_mut(window).and_then(|window| window.items_mut()).ok_or(InventoryError::UnknownSection(section_id))?,
+			_ => return Err(InventoryError::UnknownSection(section_id)),
+		})
+	}
+
+	/// The stack in a slot, making sure the slot exists.
+	fn stack(&self, section_id: i32, slot: i32) -> InventoryResult<Option<&ItemStack>> {
+		let section = self.section(section_id)?;
+		if !section.contains_slot(slot) {
+			return Err(InventoryError::InvalidSlot { section: section_id, slot });
+		}
+		Ok(section.get(slot))
+	}
+
+	/// Takes `quantity` items out of a slot, checking that it holds that many.
+	fn take(&mut self, section_id: i32, slot: i32, quantity: i32) -> InventoryResult<ItemStack> {
+		let stack = self.stack(section_id, slot)?.ok_or(InventoryError::EmptySlot { section: section_id, slot })?;
+		if quantity <= 0 || quantity > stack.quantity {
+			return Err(InventoryError::InvalidQuantity(quantity));
+		}
+		let section = self.section_mut(section_id)?;
+		let stack = section.get_mut(slot).expect("slot was checked above");
+		let taken = stack.split(quantity);
+		if stack.quantity == 0 {
+			section.set(slot, None);
+		}
+		Ok(taken)
+	}
+
+	/// Moves items from one slot to another. They merge into the same item, swap with a different one if the whole
+	/// stack is moved, and are refused otherwise.
+	pub fn move_item(&mut self, from_section: i32, from_slot: i32, quantity: i32, to_section: i32, to_slot: i32) -> InventoryResult<()> {
+		let source = self.stack(from_section, from_slot)?.ok_or(InventoryError::EmptySlot { section: from_section, slot: from_slot })?.clone();
+		let target = self.stack(to_section, to_slot)?.cloned();
+		if quantity <= 0 || quantity > source.quantity {
+			return Err(InventoryError::InvalidQuantity(quantity));
+		}
+		if (from_section, from_slot) == (to_section, to_slot) {
+			return Ok(());
+		}
+
+		match target {
+			None => {
+				let moved = self.take(from_section, from_slot, quantity)?;
+				self.section_mut(to_section)?.set(to_slot, Some(moved));
+			}
+			Some(mut target) if target.stacks_with(&source) => {
+				let moved = quantity.min(target.max_stack() - target.quantity);
+				if moved <= 0 {
+					return Err(InventoryError::SlotFull { section: to_section, slot: to_slot });
+				}
+				self.take(from_section, from_slot, moved)?;
+				target.quantity += moved;
+				self.section_mut(to_section)?.set(to_slot, Some(target));
+			}
+			Some(target) if quantity == source.quantity => {
+				self.section_mut(from_section)?.set(from_slot, Some(target));
+				self.section_mut(to_section)?.set(to_slot, Some(source));
+			}
+			Some(_) => return Err(InventoryError::SlotFull { section: to_section, slot: to_slot }),
+		}
+		Ok(())
+	}
+
+	/// Moves items to wherever they fit best: into an open container window and back, between the hotbar and the rest
+	/// of the inventory, or onto stacks of the same item. Whatever doesn't fit stays where it was.
+	pub fn smart_move(&mut self, section_id: i32, slot: i32, quantity: i32, move_type: SmartMoveType) -> InventoryResult<()> {
+		let window = self.windows.container_id();
+		let targets = match move_type {
+			SmartMoveType::EquipOrMergeStack => vec![section_id],
+			_ if section_id > 0 => vec![HOTBAR_SECTION_ID, STORAGE_SECTION_ID],
+			SmartMoveType::PutInHotbarOrWindow if window.is_some() => window.into_iter().collect(),
+			SmartMoveType::PutInHotbarOrWindow if section_id == HOTBAR_SECTION_ID => vec![STORAGE_SECTION_ID],
+			SmartMoveType::PutInHotbarOrBackpack if section_id == HOTBAR_SECTION_ID => vec![BACKPACK_SECTION_ID, STORAGE_SECTION_ID],
+			SmartMoveType::PutInHotbarOrWindow | SmartMoveType::PutInHotbarOrBackpack => vec![HOTBAR_SECTION_ID],
+		};
+		let mut moving = self.take(section_id, slot, quantity)?;
+		for target in targets {
+			let section = self.section_mut(target)?;
+			moving = match move_type {
+				SmartMoveType::EquipOrMergeStack => merge(section, moving, slot),
+				_ => match section.add(moving) {
+					Some(rest) => rest,
+					None => return Ok(()),
+				},
+			};
+			if moving.quantity == 0 {
+				return Ok(());
+			}
+		}
+		let unmoved = moving.quantity == quantity;
+		self.give_back(section_id, slot, moving);
+		if unmoved { Err(InventoryError::NoRoom) } else { Ok(()) }
+	}
+
+	/// Returns items taken out of a slot.
+	fn give_back(&mut self, section_id: i32, slot: i32, mut stack: ItemStack) {
+		let Ok(section) = self.section_mut(section_id) else {
+			return;
+		};
+		if let Some(existing) = section.get(slot) {
+			stack.quantity += existing.quantity;
+		}
+		section.set(slot, Some(stack));
+	}
+
+	/// Puts a creative item into a slot. With `replace` it overwrites whatever is there, otherwise it only fills an
+	/// empty slot or tops up a stack of the same item. No item empties the slot.
+	pub fn set_creative_item(&mut self, section_id: i32, slot: i32, stack: Option<ItemStack>, replace: bool) -> InventoryResult<()> {
+		let existing = self.stack(section_id, slot)?.cloned();
+		let stack = match (existing, stack) {
+			(_, None) => None,
+			(None, Some(stack)) => Some(stack),
+			(Some(_), Some(stack)) if replace => Some(stack),
+			(Some(mut existing), Some(stack)) if existing.stacks_with(&stack) => {
+				existing.quantity = (existing.quantity + stack.quantity).min(existing.max_stack());
+				Some(existing)
+			}
+			(Some(_), Some(_)) => return Err(InventoryError::SlotFull { section: section_id, slot }),
+		};
+		self.section_mut(section_id)?.set(slot, stack);
+		Ok(())
+	}
+
+	/// Takes items out of a slot to drop them.
+	pub fn drop_item(&mut self, section_id: i32, slot: i32, quantity: i32) -> InventoryResult<ItemStack> {
+		self.take(section_id, slot, quantity)
+	}
+
+	/// Selects a slot of the hotbar, the utility or the tools section. The latter two can be left without a selection.
+	pub fn set_active_slot(&mut self, section_id: i32, slot: i32) -> InventoryResult<()> {
+		let none_allowed = section_id != HOTBAR_SECTION_ID;
+		let valid = self.section(section_id)?.contains_slot(slot) || (none_allowed && slot == -1);
+		let active = match section_id {
+			HOTBAR_SECTION_ID => &mut self.active_hotbar_slot,
+			UTILITY_SECTION_ID => &mut self.active_utility_slot,
+			TOOLS_SECTION_ID => &mut self.active_tools_slot,
+			_ => return Err(InventoryError::NoActiveSlot(section_id)),
+		};
+		if !valid {
+			return Err(InventoryError::InvalidSlot { section: section_id, slot });
+		}
+		*active = slot;
+		Ok(())
+	}
+
+	pub fn sort(&mut self, section_id: i32, sort_type: SortType) -> InventoryResult<()> {
+		self.section_mut(section_id)?.sort(sort_type);
+		self.sort_type = sort_type;
+		Ok(())
+	}
+
+	/// Runs an action on a whole section. Everything but sorting moves items between a container window and the
+	/// storage.
+	pub fn section_action(&mut self, section_id: i32, action: InventoryActionType, data: u8) -> InventoryResult<()> {
+		if action == InventoryActionType::Sort {
+			let sort_type = match data {
+				1 => SortType::Type,
+				2 => SortType::Rarity,
+				_ => SortType::Name,
+			};
+			return self.sort(section_id, sort_type);
+		}
+		if section_id <= 0 {
+			return Err(InventoryError::NoOpenWindow);
+		}
+		let window = std::mem::take(self.section_mut(section_id)?);
+		let result = self.window_transfer(window, action);
+		*self.section_mut(section_id)? = result;
+		Ok(())
+	}
+
+	/// Moves items between a window's container and the storage, returning what the container ends up with.
+	fn window_transfer(&mut self, mut window: ItemContainer, action: InventoryActionType) -> ItemContainer {
+		match action {
+			InventoryActionType::TakeAll => {
+				for slot in 0..window.capacity() as i32 {
+					if let Some(stack) = window.set(slot, None) {
+						window.set(slot, self.give(stack));
+					}
+				}
+			}
+			InventoryActionType::PutAll | InventoryActionType::QuickStack => {
+				for slot in 0..self.storage.capacity() as i32 {
+					let Some(stack) = self.storage.get(slot) else {
+						continue;
+					};
+					if action == InventoryActionType::QuickStack && !window.iter().any(|(_, existing)| existing.stacks_with(stack)) {
+						continue;
+					}
+					let stack = self.storage.set(slot, None).expect("slot was checked above");
+					self.storage.set(slot, window.add(stack));
+				}
+			}
+			InventoryActionType::Sort => {}
+		}
+		window
+	}
+
+	/// Crafts a recipe `quantity` times in a crafting window, using up ingredients from the hotbar and the storage.
+	/// Nothing changes unless every craft has its ingredients and the results fit.
+	pub fn craft(&mut self, window_id: i32, recipe_id: &str, quantity: i32, recipes: &RecipeRegistry) -> InventoryResult<()> {
+		let window = self.windows.get(window_id).filter(|window| window.is_crafting()).ok_or(InventoryError::NotCrafting(window_id))?;
+		let recipe = recipes.get(recipe_id).ok_or_else(|| InventoryError::UnknownRecipe(recipe_id.to_string()))?;
+		let bench = window.bench().map(|bench| (bench.bench_type, bench.id.as_str(), bench.tier));
+		if !recipe.craftable_at(bench) {
+			return Err(InventoryError::WrongBench(recipe_id.to_string()));
+		}
+		if quantity <= 0 {
+			return Err(InventoryError::InvalidQuantity(quantity));
+		}
+
+		let (mut hotbar, mut storage) = (self.hotbar.clone(), self.storage.clone());
+		for input in &recipe.definition.input {
+			let mut needed = input.quantity.max(0) as i64 * quantity as i64;
+			for section in [&mut hotbar, &mut storage] {
+				for slot in 0..section.capacity() as i32 {
+					let Some(stack) = section.get_mut(slot).filter(|stack| recipes.matches(input, &stack.item_id)) else {
+						continue;
+					};
+					let used = needed.min(stack.quantity as i64) as i32;
+					stack.quantity -= used;
+					needed -= used as i64;
+					if stack.quantity == 0 {
+						section.set(slot, None);
+					}
+				}
+			}
+			if needed > 0 {
+				return Err(InventoryError::MissingIngredients);
+			}
+		}
+		for output in recipe.outputs() {
+			let total = output.quantity as i64 * quantity as i64;
+			let mut remaining = total;
+			while remaining > 0 {
+				let stack = ItemStack {
+					quantity: remaining.min(output.max_stack() as i64) as i32,
+					..output.clone()
+				};
+				remaining -= stack.quantity as i64;
+				if hotbar.add(stack).and_then(|rest| storage.add(rest)).is_some() {
+					return Err(InventoryError::NoRoom);
+				}
+			}
+		}
+		self.hotbar = hotbar;
+		self.storage = storage;
+		Ok(())
+	}
+
+	/// Saves a copy of the hotbar into a row.
+	pub fn save_hotbar(&mut self, row: u8) -> InventoryResult<()> {
+		if row >= SAVED_HOTBAR_ROWS {
+			return Err(InventoryError::InvalidHotbarRow(row));
+		}
+		self.saved_hotbars.insert(row, self.hotbar.clone());
+		Ok(())
+	}
+
+	/// Replaces the hotbar with the one saved in a row, which is empty if nothing was saved there.
+	pub fn load_hotbar(&mut self, row: u8) -> InventoryResult<()> {
+		if row >= SAVED_HOTBAR_ROWS {
+			return Err(InventoryError::InvalidHotbarRow(row));
+		}
+		self.hotbar = self.saved_hotbars.get(&row).cloned().unwrap_or_else(|| ItemContainer::new(HOTBAR_CAPACITY));
+		Ok(())
+	}
+
+	/// Adds items to the hotbar, then to the storage. Returns what didn't fit.
+	pub fn give(&mut self, stack: ItemStack) -> Option<ItemStack> {
+		self.hotbar.add(stack).and_then(|rest| self.storage.add(rest))
+	}
+
+	/// The item in the selected hotbar slot.
+	pub fn held_item(&self) -> Option<&ItemStack> {
+		self.hotbar.get(self.active_hotbar_slot)
+	}
+
+	/// Takes items off the stack in the selected hotbar slot, like a placed block.
+	pub fn take_held_item(&mut self, quantity: i32) -> InventoryResult<ItemStack> {
+		self.take(HOTBAR_SECTION_ID, self.active_hotbar_slot, quantity)
+	}
+
+	/// Wears down the item in the selected hotbar slot, removing it once it breaks. Returns whether anything changed.
+	pub fn damage_held_item(&mut self, amount: f64) -> bool {
+		let slot = self.active_hotbar_slot;
+		let Some(item) = self.hotbar.get_mut(slot).filter(|item| item.has_durability()) else {
+			return false;
+		};
+		if item.damage(amount) {
+			self.hotbar.set(slot, None);
+		}
+		true
+	}
+
+	pub fn to_packet(&self) -> UpdatePlayerInventory {
+		UpdatePlayerInventory {
+			sort_type: self.sort_type,
+			storage: Some(Box::new(self.storage.to_section())),
+			armor: Some(Box::new(self.armor.to_section())),
+			hotbar: Some(Box::new(self.hotbar.to_section())),
+			utility: Some(Box::new(self.utility.to_section())),
+			builder_material: Some(Box::new(self.builder_material.to_section())),
+			tools: Some(Box::new(self.tools.to_section())),
+			backpack: Some(Box::new(self.backpack.to_section())),
+		}
+	}
+}
+
+/// Tops up the other stacks of the same item in a section. Returns what's left.
+fn merge(section: &mut ItemContainer, mut stack: ItemStack, skip_slot: i32) -> ItemStack {
+	for slot in 0..section.capacity() as i32 {
+		if slot == skip_slot {
+			continue;
+		}
+		let Some(existing) = section.get_mut(slot).filter(|existing| existing.stacks_with(&stack)) else {
+			continue;
+		};
+		let moved = stack.quantity.min(existing.max_stack() - existing.quantity).max(0);
+		existing.quantity += moved;
+		stack.quantity -= moved;
+		if stack.quantity == 0 {
+			break;
+		}
+	}
+	stack
+}
+
+fn default_sort_type() -> SortType {
+	SortType::Name
+}
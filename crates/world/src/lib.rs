@@ -0,0 +1,54 @@
+//! Chunk storage and world state
+mod block_damage;
+mod block_type;
+mod chunk;
+mod clock;
+mod column;
+mod entity;
+mod error;
+mod fluid;
+mod fluid_sim;
+mod fluid_type;
+mod generator;
+mod inventory;
+mod light;
+mod meta;
+mod palette;
+mod player_data;
+mod recipe;
+mod region;
+mod registry;
+mod section;
+mod tick;
+mod view;
+mod weather;
+mod weather_type;
+mod window;
+mod world;
+
+pub use block_damage::*;
+pub use block_type::*;
+pub use chunk::*;
+pub use clock::*;
+pub use column::*;
+pub use entity::*;
+pub use error::*;
+pub use fluid::*;
+pub use fluid_sim::*;
+pub use fluid_type::*;
+pub use generator::*;
+pub use inventory::*;
+pub use light::*;
+pub use meta::*;
+pub use palette::*;
+pub use player_data::*;
+pub use recipe::*;
+pub use region::*;
+pub use registry::*;
+pub use section::*;
+pub use tick::*;
+pub use view::*;
+pub use weather::*;
+pub use weather_type::*;
+pub use window::*;
+pub use world::*;
@@ -0,0 +1,679 @@
+use std::{
+	collections::VecDeque,
+	sync::Arc,
+};
+
+use bytes::{
+	BufMut,
+	Bytes,
+	BytesMut,
+};
+
+use protocol::v2::world::{
+	ServerSetBlock,
+	ServerSetBlocks,
+};
+
+use crate::{
+	chunk::{
+		Chunk,
+		CHUNK_HEIGHT,
+		CHUNK_SECTIONS,
+	},
+	column::column_index,
+	section::{
+		BlockState,
+		ChunkSection,
+		SECTION_AREA,
+		SECTION_SIZE,
+		SECTION_VOLUME,
+	},
+};
+
+pub const MAX_LIGHT: u8 = 15;
+
+/// Channels packed into a light value, 4 bits each.
+pub const RED: usize = 0;
+pub const GREEN: usize = 1;
+pub const BLUE: usize = 2;
+pub const SKY: usize = 3;
+
+const BLOCK_CHANNELS: [usize; 3] = [RED, GREEN, BLUE];
+const BLOCK_LIGHT_MASK: u16 = 0x0FFF;
+const FULL_SKY: u16 = (MAX_LIGHT as u16) << (SKY * 4);
+
+/// Wire modes for the light buffers of `SetChunk`.
+const MODE_UNIFORM: u8 = 0;
+const MODE_ARRAY: u8 = 1;
+
+#[inline]
+pub fn light_channel(light: u16, channel: usize) -> u8 {
+	((light >> (channel * 4)) & 0xF) as u8
+}
+
+#[inline]
+fn with_channel(light: u16, channel: usize, level: u8) -> u16 {
+	(light & !(0xF << (channel * 4))) | ((level as u16 & 0xF) << (channel * 4))
+}
+
+/// Tells the light engine how blocks interact with light.
+pub trait BlockLighting: Send + Sync {
+	/// Red, green and blue light levels emitted by the block, each in `0..=15`.
+	fn emission(&self, block: BlockState) -> [u8; 3];
+
+	/// How many levels light loses when passing through the block on top of the regular falloff.
+	/// `0` is fully transparent, `15` (or more) blocks light entirely.
+	fn opacity(&self, block: BlockState) -> u8;
+}
+
+impl<L: BlockLighting + ?Sized> BlockLighting for Arc<L> {
+	fn emission(&self, block: BlockState) -> [u8; 3] {
+		(**self).emission(block)
+	}
+
+	fn opacity(&self, block: BlockState) -> u8 {
+		(**self).opacity(block)
+	}
+}
+
+/// Lighting rules for when no block data is available: air is transparent, everything else is opaque and nothing glows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultLighting;
+
+impl BlockLighting for DefaultLighting {
+	fn emission(&self, _block: BlockState) -> [u8; 3] {
+		[0; 3]
+	}
+
+	fn opacity(&self, block: BlockState) -> u8 {
+		if block.is_air() { 0 } else { MAX_LIGHT }
+	}
+}
+
+/// Light values of one section. Sections that are lit uniformly don't allocate per-block storage.
+#[derive(Debug, Clone)]
+struct LightSection {
+	data: Option<Box<[u16]>>,
+	fill: u16,
+}
+
+impl LightSection {
+	fn uniform(fill: u16) -> Self {
+		Self { data: None, fill }
+	}
+
+	#[inline]
+	fn get(&self, index: usize) -> u16 {
+		match &self.data {
+			Some(data) => data[index],
+			None => self.fill,
+		}
+	}
+
+	#[inline]
+	fn set(&mut self, index: usize, value: u16) {
+		match &mut self.data {
+			Some(data) => data[index] = value,
+			None if value == self.fill => {}
+			None => {
+				let mut data = vec![self.fill; SECTION_VOLUME].into_boxed_slice();
+				data[index] = value;
+				self.data = Some(data);
+			}
+		}
+	}
+
+	/// Collapses the per-block storage back into a fill value if every block has the same light.
+	fn compact(&mut self) {
+		if let Some(data) = &self.data
+			&& data.iter().all(|v| *v == data[0])
+		{
+			self.fill = data[0];
+			self.data = None;
+		}
+	}
+}
+
+/// Block and sky light of a chunk column.
+///
+/// Every block holds a `u16` with 4 bits per channel: red, green and blue block light, then sky light.
+#[derive(Debug, Clone)]
+pub struct ChunkLight {
+	sections: Vec<LightSection>,
+	/// Per column, the lowest y that receives direct sky light.
+	sky_heights: Box<[u16]>,
+	dirty: u16,
+}
+
+impl Default for ChunkLight {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ChunkLight {
+	/// Light of an empty column: full sky light everywhere, no block light.
+	pub fn new() -> Self {
+		Self {
+			sections: vec![LightSection::uniform(FULL_SKY); CHUNK_SECTIONS],
+			sky_heights: vec![0; SECTION_AREA].into_boxed_slice(),
+			dirty: 0,
+		}
+	}
+
+	#[inline]
+	fn get_raw(&self, index: usize) -> u16 {
+		self.sections[index / SECTION_VOLUME].get(index % SECTION_VOLUME)
+	}
+
+	#[inline]
+	fn set_raw(&mut self, index: usize, value: u16) {
+		let section = index / SECTION_VOLUME;
+		self.sections[section].set(index % SECTION_VOLUME, value);
+		self.dirty |= 1 << section;
+	}
+
+	/// Packed light value at the given chunk-local coordinates.
+	pub fn get(&self, x: usize, y: usize, z: usize) -> u16 {
+		if y >= CHUNK_HEIGHT {
+			return FULL_SKY;
+		}
+		self.get_raw(light_index(x, y, z))
+	}
+
+	pub fn block_light(&self, x: usize, y: usize, z: usize) -> [u8; 3] {
+		let light = self.get(x, y, z);
+		BLOCK_CHANNELS.map(|channel| light_channel(light, channel))
+	}
+
+	pub fn sky_light(&self, x: usize, y: usize, z: usize) -> u8 {
+		light_channel(self.get(x, y, z), SKY)
+	}
+
+	/// The lowest y of the column at `(x, z)` that receives direct sky light.
+	pub fn sky_height(&self, x: usize, z: usize) -> usize {
+		self.sky_heights[column_index(x, z)] as usize
+	}
+
+	/// Returns the sections whose light changed since the last call.
+	pub fn take_dirty(&mut self) -> Vec<usize> {
+		let dirty = std::mem::take(&mut self.dirty);
+		(0..CHUNK_SECTIONS).filter(|section| dirty & (1 << section) != 0).collect()
+	}
+
+	/// Block light of a section in the `SetChunk.local_light` layout, or `None` if the section has no block light.
+	///
+	/// Layout (little endian): a `u8` mode, then either a single `u16` for the whole section (mode 0) or one `u16` per block
+	/// in block index order (mode 1). Red, green and blue take 4 bits each, starting from the least significant bits.
+	pub fn local_light_bytes(&self, section: usize) -> Option<Bytes> {
+		let light = self.sections.get(section)?;
+		let mut buf = BytesMut::new();
+		match &light.data {
+			None if light.fill & BLOCK_LIGHT_MASK == 0 => return None,
+			None => {
+				buf.put_u8(MODE_UNIFORM);
+				buf.put_u16_le(light.fill & BLOCK_LIGHT_MASK);
+			}
+			Some(data) => {
+				if data.iter().all(|v| v & BLOCK_LIGHT_MASK == 0) {
+					return None;
+				}
+				buf.reserve(1 + SECTION_VOLUME * 2);
+				buf.put_u8(MODE_ARRAY);
+				for value in data.iter() {
+					buf.put_u16_le(value & BLOCK_LIGHT_MASK);
+				}
+			}
+		}
+		Some(buf.freeze())
+	}
+
+	/// Sky light of a section in the `SetChunk.global_light` layout.
+	///
+	/// Layout: a `u8` mode, then either a single `u8` level for the whole section (mode 0) or one nibble per block in block
+	/// index order (mode 1), even indices in the low nibble.
+	pub fn global_light_bytes(&self, section: usize) -> Option<Bytes> {
+		let light = self.sections.get(section)?;
+		let mut buf = BytesMut::new();
+		match &light.data {
+			None => {
+				buf.put_u8(MODE_UNIFORM);
+				buf.put_u8(light_channel(light.fill, SKY));
+			}
+			Some(data) => {
+				buf.reserve(1 + SECTION_VOLUME / 2);
+				buf.put_u8(MODE_ARRAY);
+				for pair in data.chunks_exact(2) {
+					buf.put_u8(light_channel(pair[0], SKY) | (light_channel(pair[1], SKY) << 4));
+				}
+			}
+		}
+		Some(buf.freeze())
+	}
+}
+
+/// Index of a block inside a column. The section is `index / SECTION_VOLUME` and the remainder is the section block index.
+#[inline]
+fn light_index(x: usize, y: usize, z: usize) -> usize {
+	(y << 10) | (z << 5) | x
+}
+
+/// Blocks in a column.
+const COLUMN_VOLUME: usize = CHUNK_HEIGHT * SECTION_AREA;
+/// The columns of a [`LightArea`], three by three.
+const AREA_COLUMNS: usize = 9;
+/// Slot of the middle column of a [`LightArea`].
+const CENTER: usize = 4;
+
+/// Slot in a [`LightArea`] of the column at an offset from the middle one.
+#[inline]
+fn area_slot(dx: i32, dz: i32) -> usize {
+	((dz + 1) * 3 + dx + 1) as usize
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+	Down,
+	Up,
+	North,
+	South,
+	East,
+	West,
+}
+
+const DIRECTIONS: [Direction; 6] = [Direction::Down, Direction::Up, Direction::North, Direction::South, Direction::East, Direction::West];
+
+/// A column and whichever of the eight columns around it are loaded, which light spreads into and comes from.
+///
+/// A position in the area is the slot of its column times the column volume plus its index in the column. Light from a
+/// block of the middle column fades out long before it gets past the columns around it, so it never leaves the area.
+pub struct LightArea<'a> {
+	columns: [Option<&'a mut Chunk>; AREA_COLUMNS],
+}
+
+impl<'a> LightArea<'a> {
+	/// An area of a single column, whose light doesn't spread any further.
+	pub fn single(chunk: &'a mut Chunk) -> Self {
+		let mut columns: [Option<&'a mut Chunk>; AREA_COLUMNS] = Default::default();
+		columns[CENTER] = Some(chunk);
+		Self { columns }
+	}
+
+	/// An area around `center`. Neighbours that aren't next to it are ignored.
+	pub fn new(center: &'a mut Chunk, neighbours: impl IntoIterator<Item = &'a mut Chunk>) -> Self {
+		let (x, z) = (center.x(), center.z());
+		let mut area = Self::single(center);
+		for chunk in neighbours {
+			let (dx, dz) = (chunk.x() - x, chunk.z() - z);
+			if dx.abs() <= 1 && dz.abs() <= 1 && (dx, dz) != (0, 0) {
+				area.columns[area_slot(dx, dz)] = Some(chunk);
+			}
+		}
+		area
+	}
+
+	fn center(&mut self) -> &mut Chunk {
+		self.columns[CENTER].as_deref_mut().expect("the middle column is always there")
+	}
+
+	fn column(&self, position: usize) -> &Chunk {
+		self.columns[position / COLUMN_VOLUME].as_deref().expect("positions only point into loaded columns")
+	}
+
+	fn column_mut(&mut self, position: usize) -> &mut Chunk {
+		self.columns[position / COLUMN_VOLUME].as_deref_mut().expect("positions only point into loaded columns")
+	}
+
+	#[inline]
+	fn get_raw(&self, position: usize) -> u16 {
+		self.column(position).light.get_raw(position % COLUMN_VOLUME)
+	}
+
+	#[inline]
+	fn set_raw(&mut self, position: usize, value: u16) {
+		self.column_mut(position).light.set_raw(position % COLUMN_VOLUME, value);
+	}
+
+	#[inline]
+	fn block(&self, position: usize) -> BlockState {
+		let index = position % COLUMN_VOLUME;
+		self.column(position).sections[index / SECTION_VOLUME].get_index(index % SECTION_VOLUME)
+	}
+
+	/// The position next to another one, if it's in a loaded column of the area.
+	#[inline]
+	fn neighbour(&self, position: usize, direction: Direction) -> Option<usize> {
+		let (slot, index) = (position / COLUMN_VOLUME, position % COLUMN_VOLUME);
+		let (x, z, y) = (index & 31, (index >> 5) & 31, index >> 10);
+		let (dx, dz) = ((slot % 3) as i32 - 1, (slot / 3) as i32 - 1);
+		let across = |dx: i32, dz: i32, index: usize| {
+			let loaded = dx.abs() <= 1 && dz.abs() <= 1 && self.columns[area_slot(dx, dz)].is_some();
+			loaded.then(|| area_slot(dx, dz) * COLUMN_VOLUME + index)
+		};
+		match direction {
+			Direction::Down => (y > 0).then(|| position - SECTION_AREA),
+			Direction::Up => (y + 1 < CHUNK_HEIGHT).then(|| position + SECTION_AREA),
+			Direction::North if z > 0 => Some(position - SECTION_SIZE),
+			Direction::North => across(dx, dz - 1, index + SECTION_AREA - SECTION_SIZE),
+			Direction::South if z + 1 < SECTION_SIZE => Some(position + SECTION_SIZE),
+			Direction::South => across(dx, dz + 1, index - (SECTION_AREA - SECTION_SIZE)),
+			Direction::West if x > 0 => Some(position - 1),
+			Direction::West => across(dx - 1, dz, index + SECTION_SIZE - 1),
+			Direction::East if x + 1 < SECTION_SIZE => Some(position + 1),
+			Direction::East => across(dx + 1, dz, index - (SECTION_SIZE - 1)),
+		}
+	}
+
+	/// Clears the dirty sections of every column, handing back what they were.
+	fn take_dirty(&mut self) -> [u16; AREA_COLUMNS] {
+		let mut dirty = [0; AREA_COLUMNS];
+		for (slot, column) in self.columns.iter_mut().enumerate() {
+			if let Some(column) = column {
+				dirty[slot] = std::mem::take(&mut column.light.dirty);
+			}
+		}
+		dirty
+	}
+
+	/// Compacts the sections that turned dirty since [`take_dirty`](Self::take_dirty), then marks the earlier ones dirty
+	/// again. Only the sections whose light changed can have become uniform.
+	fn compact_dirty(&mut self, earlier: [u16; AREA_COLUMNS]) {
+		for (column, earlier) in self.columns.iter_mut().zip(earlier) {
+			let Some(column) = column else {
+				continue;
+			};
+			let light = &mut column.light;
+			for (index, section) in light.sections.iter_mut().enumerate() {
+				if light.dirty & (1 << index) != 0 {
+					section.compact();
+				}
+			}
+			light.dirty |= earlier;
+		}
+	}
+}
+
+/// Computes block light from emissive blocks and sky light from the column heightmap.
+///
+/// A column is first lit on its own with [`light_chunk`](Self::light_chunk). Once it's next to other loaded columns,
+/// [`stitch`](Self::stitch) spreads light across their borders, and block changes relight the columns around them too.
+pub struct LightEngine<L: BlockLighting> {
+	lighting: L,
+}
+
+impl<L: BlockLighting> LightEngine<L> {
+	pub fn new(lighting: L) -> Self {
+		Self { lighting }
+	}
+
+	pub fn lighting(&self) -> &L {
+		&self.lighting
+	}
+
+	/// Recomputes all light of the chunk from scratch, as if no other column was around. No section is marked dirty, as
+	/// the whole chunk has to be sent again anyway.
+	pub fn light_chunk(&self, chunk: &mut Chunk) {
+		chunk.light = ChunkLight {
+			sections: vec![LightSection::uniform(0); CHUNK_SECTIONS],
+			sky_heights: vec![0; SECTION_AREA].into_boxed_slice(),
+			dirty: 0,
+		};
+		for z in 0..SECTION_SIZE {
+			for x in 0..SECTION_SIZE {
+				chunk.light.sky_heights[column_index(x, z)] = self.compute_sky_height(&chunk.sections, x, z) as u16;
+			}
+		}
+		// Sections fully above every column are lit uniformly, the rest are filled block by block
+		let max_height = chunk.light.sky_heights.iter().copied().max().unwrap_or(0) as usize;
+		let lit_from = max_height.div_ceil(SECTION_SIZE) * SECTION_SIZE;
+		for section in &mut chunk.light.sections[lit_from / SECTION_SIZE..] {
+			*section = LightSection::uniform(FULL_SKY);
+		}
+		let mut queue = VecDeque::new();
+		for z in 0..SECTION_SIZE {
+			for x in 0..SECTION_SIZE {
+				let height = chunk.light.sky_heights[column_index(x, z)] as usize;
+				for y in height..lit_from {
+					chunk.light.set_raw(light_index(x, y, z), FULL_SKY);
+				}
+				// Only the top of each lit column borders unlit blocks, either below or next to it
+				let spread_top = self.neighbour_max_height(&chunk.light, x, z).max(height + 1).min(CHUNK_HEIGHT);
+				for y in height..spread_top {
+					queue.push_back(CENTER * COLUMN_VOLUME + light_index(x, y, z));
+				}
+			}
+		}
+
+		let mut queues: [VecDeque<usize>; 3] = Default::default();
+		for (section_index, section) in chunk.sections.iter().enumerate() {
+			if !section.blocks().values().any(|id| id != 0) {
+				continue;
+			}
+			for index in 0..SECTION_VOLUME {
+				let emission = self.lighting.emission(section.get_index(index));
+				if emission == [0; 3] {
+					continue;
+				}
+				let index = section_index * SECTION_VOLUME + index;
+				let mut value = chunk.light.get_raw(index);
+				for channel in BLOCK_CHANNELS {
+					if emission[channel] > 0 {
+						value = with_channel(value, channel, emission[channel]);
+						queues[channel].push_back(CENTER * COLUMN_VOLUME + index);
+					}
+				}
+				chunk.light.set_raw(index, value);
+			}
+		}
+
+		let mut area = LightArea::single(chunk);
+		self.propagate(&mut area, SKY, &mut queue);
+		for channel in BLOCK_CHANNELS {
+			self.propagate(&mut area, channel, &mut queues[channel]);
+		}
+		let chunk = area.center();
+		for section in &mut chunk.light.sections {
+			section.compact();
+		}
+		chunk.light.dirty = 0;
+	}
+
+	/// Spreads light across the borders of the middle column of the area, both into its neighbours and out of them. The
+	/// sections whose light changed can be collected with [`ChunkLight::take_dirty`].
+	pub fn stitch(&self, area: &mut LightArea) {
+		let earlier = area.take_dirty();
+		let mut border = Vec::new();
+		for y in 0..CHUNK_HEIGHT {
+			for i in 0..SECTION_SIZE {
+				for (x, z) in [(i, 0), (i, SECTION_SIZE - 1), (0, i), (SECTION_SIZE - 1, i)] {
+					border.push(CENTER * COLUMN_VOLUME + light_index(x, y, z));
+				}
+			}
+		}
+		for channel in [RED, GREEN, BLUE, SKY] {
+			let mut queue = VecDeque::new();
+			for &position in &border {
+				queue.push_back(position);
+				queue.extend(DIRECTIONS.into_iter().filter_map(|direction| area.neighbour(position, direction)).filter(|next| next / COLUMN_VOLUME != CENTER));
+			}
+			self.propagate(area, channel, &mut queue);
+		}
+		area.compact_dirty(earlier);
+	}
+
+	/// Sets a block and incrementally updates the light around it, inside its own column. Returns the previous state.
+	pub fn set_block(&self, chunk: &mut Chunk, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+		let old = chunk.set_block(x, y, z, state);
+		if old != state {
+			self.update_blocks(&mut LightArea::single(chunk), &[(x, y, z)]);
+		}
+		old
+	}
+
+	/// Applies a `ServerSetBlock` to the chunk and updates the light around it. Returns the changed chunk-local position.
+	pub fn apply_set_block(&self, chunk: &mut Chunk, packet: &ServerSetBlock) -> Option<(usize, usize, usize)> {
+		let changed = chunk.apply_set_block(packet)?;
+		self.update_blocks(&mut LightArea::single(chunk), &[changed]);
+		Some(changed)
+	}
+
+	/// Applies a `ServerSetBlocks` to the chunk and updates the light around the changed blocks.
+	/// Returns the changed chunk-local positions.
+	pub fn apply_set_blocks(&self, chunk: &mut Chunk, packet: &ServerSetBlocks) -> Vec<(usize, usize, usize)> {
+		let changed = chunk.apply_set_blocks(packet);
+		self.update_blocks(&mut LightArea::single(chunk), &changed);
+		changed
+	}
+
+	/// Updates the light after the blocks at the given positions of the middle column changed, carrying it into the
+	/// columns around. The sections whose light changed can be collected with [`ChunkLight::take_dirty`].
+	pub fn update_blocks(&self, area: &mut LightArea, positions: &[(usize, usize, usize)]) {
+		let positions: Vec<usize> = positions.iter().filter(|(_, y, _)| *y < CHUNK_HEIGHT).map(|(x, y, z)| CENTER * COLUMN_VOLUME + light_index(*x, *y, *z)).collect();
+		if positions.is_empty() {
+			return;
+		}
+
+		// Sky light changes for every block between the old and new top of a changed column
+		let mut sky_positions = positions.clone();
+		let mut columns: Vec<(usize, usize)> = positions.iter().map(|position| (position & 31, (position >> 5) & 31)).collect();
+		columns.sort_unstable();
+		columns.dedup();
+		let chunk = area.center();
+		for (x, z) in columns {
+			let old_height = chunk.light.sky_heights[column_index(x, z)] as usize;
+			let new_height = self.compute_sky_height(&chunk.sections, x, z);
+			if old_height != new_height {
+				chunk.light.sky_heights[column_index(x, z)] = new_height as u16;
+				sky_positions.extend((old_height.min(new_height)..old_height.max(new_height)).map(|y| CENTER * COLUMN_VOLUME + light_index(x, y, z)));
+			}
+		}
+
+		let earlier = area.take_dirty();
+		for channel in BLOCK_CHANNELS {
+			self.relight(area, channel, &positions);
+		}
+		self.relight(area, SKY, &sky_positions);
+		area.compact_dirty(earlier);
+	}
+
+	/// Removes light that depended on the changed positions, then re-propagates from their new sources and from
+	/// surrounding light that is still valid.
+	fn relight(&self, area: &mut LightArea, channel: usize, positions: &[usize]) {
+		let mut removal = VecDeque::new();
+		let mut queue = VecDeque::new();
+
+		for &position in positions {
+			let value = area.get_raw(position);
+			let level = light_channel(value, channel);
+			if level > 0 {
+				area.set_raw(position, with_channel(value, channel, 0));
+				removal.push_back((position, level));
+			}
+		}
+
+		while let Some((position, level)) = removal.pop_front() {
+			for direction in DIRECTIONS {
+				let Some(next) = area.neighbour(position, direction) else {
+					continue;
+				};
+				let value = area.get_raw(next);
+				let next_level = light_channel(value, channel);
+				if next_level == 0 {
+					continue;
+				}
+				let dependent = next_level < level || (channel == SKY && direction == Direction::Down && level == MAX_LIGHT);
+				if dependent {
+					// Sources keep their own light, and spread it again once everything depending on the old light is gone
+					let source = self.source_level(area, next, channel);
+					area.set_raw(next, with_channel(value, channel, source));
+					removal.push_back((next, next_level));
+					if source > 0 {
+						queue.push_back(next);
+					}
+				} else {
+					queue.push_back(next);
+				}
+			}
+		}
+
+		for &position in positions {
+			let source = self.source_level(area, position, channel);
+			let value = area.get_raw(position);
+			if source > light_channel(value, channel) {
+				area.set_raw(position, with_channel(value, channel, source));
+			}
+			queue.push_back(position);
+			// Light from around the position may now be able to flow into it
+			queue.extend(DIRECTIONS.into_iter().filter_map(|direction| area.neighbour(position, direction)));
+		}
+
+		self.propagate(area, channel, &mut queue);
+	}
+
+	fn source_level(&self, area: &LightArea, position: usize, channel: usize) -> u8 {
+		if channel == SKY {
+			let index = position % COLUMN_VOLUME;
+			let height = area.column(position).light.sky_heights[index & (SECTION_AREA - 1)] as usize;
+			if index >> 10 >= height { MAX_LIGHT } else { 0 }
+		} else {
+			self.lighting.emission(area.block(position))[channel].min(MAX_LIGHT)
+		}
+	}
+
+	fn propagate(&self, area: &mut LightArea, channel: usize, queue: &mut VecDeque<usize>) {
+		while let Some(position) = queue.pop_front() {
+			let level = light_channel(area.get_raw(position), channel);
+			if level <= 1 {
+				continue;
+			}
+			for direction in DIRECTIONS {
+				let Some(next) = area.neighbour(position, direction) else {
+					continue;
+				};
+				let opacity = self.lighting.opacity(area.block(next));
+				let target = if channel == SKY && direction == Direction::Down && level == MAX_LIGHT && opacity == 0 {
+					MAX_LIGHT
+				} else {
+					level.saturating_sub(1).saturating_sub(opacity)
+				};
+				let value = area.get_raw(next);
+				if target > light_channel(value, channel) {
+					area.set_raw(next, with_channel(value, channel, target));
+					queue.push_back(next);
+				}
+			}
+		}
+	}
+
+	/// One above the highest block of the column that isn't fully transparent.
+	fn compute_sky_height(&self, sections: &[ChunkSection], x: usize, z: usize) -> usize {
+		for (section_index, section) in sections.iter().enumerate().rev() {
+			if section.blocks().is_empty() {
+				continue;
+			}
+			for y in (0..SECTION_SIZE).rev() {
+				if self.lighting.opacity(section.get(x, y, z)) > 0 {
+					return section_index * SECTION_SIZE + y + 1;
+				}
+			}
+		}
+		0
+	}
+
+	fn neighbour_max_height(&self, light: &ChunkLight, x: usize, z: usize) -> usize {
+		let mut max = 0;
+		if x > 0 {
+			max = max.max(light.sky_heights[column_index(x - 1, z)]);
+		}
+		if x + 1 < SECTION_SIZE {
+			max = max.max(light.sky_heights[column_index(x + 1, z)]);
+		}
+		if z > 0 {
+			max = max.max(light.sky_heights[column_index(x, z - 1)]);
+		}
+		if z + 1 < SECTION_SIZE {
+			max = max.max(light.sky_heights[column_index(x, z + 1)]);
+		}
+		max as usize
+	}
+}
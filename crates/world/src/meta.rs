@@ -0,0 +1,85 @@
+use std::{
+	collections::BTreeMap,
+	fs,
+	io::ErrorKind,
+	path::Path,
+};
+
+use serde::{
+	Deserialize,
+	Serialize,
+};
+use uuid::Uuid;
+
+use crate::{
+	clock::SUNRISE,
+	error::{
+		MetaError,
+		MetaResult,
+	},
+};
+
+pub const WORLD_META_FILE: &str = "world.json";
+pub const WORLD_META_VERSION: u32 = 1;
+
+/// Everything about a world that has to survive a restart besides its chunks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldMeta {
+	pub version: u32,
+	pub uuid: Uuid,
+	pub seed: u64,
+	/// Block name→id mapping the chunks were saved with.
+	pub block_ids: BTreeMap<String, i32>,
+	/// Fluid name→id mapping the chunks were saved with.
+	#[serde(default)]
+	pub fluid_ids: BTreeMap<String, i32>,
+	/// Environment name→id mapping the chunks were saved with.
+	#[serde(default)]
+	pub environment_ids: BTreeMap<String, i32>,
+	/// Game seconds the world clock was at when the server stopped.
+	#[serde(default = "first_sunrise")]
+	pub game_time: f64,
+}
+
+impl WorldMeta {
+	pub fn new(uuid: Uuid, seed: u64) -> Self {
+		Self {
+			version: WORLD_META_VERSION,
+			uuid,
+			seed,
+			block_ids: BTreeMap::new(),
+			fluid_ids: BTreeMap::new(),
+			environment_ids: BTreeMap::new(),
+			game_time: first_sunrise(),
+		}
+	}
+
+	/// Reads the metadata of the world in `dir`, or returns `None` if the world doesn't exist yet.
+	pub fn load(dir: &Path) -> MetaResult<Option<Self>> {
+		let bytes = match fs::read(dir.join(WORLD_META_FILE)) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+		let meta: WorldMeta = serde_json::from_slice(&bytes)?;
+		if meta.version > WORLD_META_VERSION {
+			return Err(MetaError::UnsupportedVersion(meta.version));
+		}
+		Ok(Some(meta))
+	}
+
+	/// Writes the metadata to `dir`, replacing the old file only once the new one is complete.
+	pub fn save(&self, dir: &Path) -> MetaResult<()> {
+		fs::create_dir_all(dir)?;
+		let tmp = dir.join(format!("{}.tmp", WORLD_META_FILE));
+		fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+		fs::rename(tmp, dir.join(WORLD_META_FILE))?;
+		Ok(())
+	}
+}
+
+/// New worlds start in the morning of their first day.
+fn first_sunrise() -> f64 {
+	SUNRISE
+}
@@ -0,0 +1,458 @@
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	hash::Hash,
+};
+
+use bytes::{
+	Buf,
+	BufMut,
+	BytesMut,
+};
+use protocol::{
+	codec::HytaleCodec,
+	v2::world::PaletteType,
+};
+
+use crate::{
+	error::{
+		ChunkError,
+		ChunkResult,
+	},
+	section::SECTION_VOLUME,
+};
+
+/// A value that can be stored in a [`Palette`].
+/// The default value is what an empty palette implicitly contains everywhere.
+pub trait PaletteValue: Copy + Eq + Hash + Default + Debug {
+	const SIZE: usize;
+
+	fn write(&self, buf: &mut BytesMut);
+	fn read(buf: &mut impl Buf) -> Self;
+}
+
+macro_rules! impl_palette_value {
+    ($($t:ty => $put:ident, $get:ident);* $(;)?) => {
+        $(
+            impl PaletteValue for $t {
+                const SIZE: usize = size_of::<$t>();
+
+                fn write(&self, buf: &mut BytesMut) {
+                    buf.$put(*self);
+                }
+
+                fn read(buf: &mut impl Buf) -> Self {
+                    buf.$get()
+                }
+            }
+        )*
+    };
+}
+
+impl_palette_value! {
+	i32 => put_i32_le, get_i32_le;
+	i16 => put_i16_le, get_i16_le;
+	u8 => put_u8, get_u8;
+}
+
+/// Backing index storage, one internal palette id per block.
+#[derive(Debug, Clone)]
+enum Storage {
+	Empty,
+	HalfByte(Box<[u8]>),
+	Byte(Box<[u8]>),
+	Short(Box<[u16]>),
+}
+
+impl Storage {
+	fn new(palette_type: PaletteType) -> Self {
+		match palette_type {
+			PaletteType::Empty => Storage::Empty,
+			PaletteType::HalfByte => Storage::HalfByte(vec![0; SECTION_VOLUME / 2].into_boxed_slice()),
+			PaletteType::Byte => Storage::Byte(vec![0; SECTION_VOLUME].into_boxed_slice()),
+			PaletteType::Short => Storage::Short(vec![0; SECTION_VOLUME].into_boxed_slice()),
+		}
+	}
+
+	fn palette_type(&self) -> PaletteType {
+		match self {
+			Storage::Empty => PaletteType::Empty,
+			Storage::HalfByte(_) => PaletteType::HalfByte,
+			Storage::Byte(_) => PaletteType::Byte,
+			Storage::Short(_) => PaletteType::Short,
+		}
+	}
+
+	#[inline]
+	fn get(&self, index: usize) -> u16 {
+		match self {
+			Storage::Empty => 0,
+			Storage::HalfByte(data) => {
+				let byte = data[index >> 1];
+				if index & 1 == 0 { (byte & 0x0F) as u16 } else { (byte >> 4) as u16 }
+			}
+			Storage::Byte(data) => data[index] as u16,
+			Storage::Short(data) => data[index],
+		}
+	}
+
+	#[inline]
+	fn set(&mut self, index: usize, value: u16) {
+		match self {
+			Storage::Empty => debug_assert_eq!(value, 0, "Empty storage can only hold the default entry"),
+			Storage::HalfByte(data) => {
+				let byte = &mut data[index >> 1];
+				if index & 1 == 0 {
+					*byte = (*byte & 0xF0) | (value as u8 & 0x0F);
+				} else {
+					*byte = (*byte & 0x0F) | ((value as u8 & 0x0F) << 4);
+				}
+			}
+			Storage::Byte(data) => data[index] = value as u8,
+			Storage::Short(data) => data[index] = value,
+		}
+	}
+
+	fn byte_len(palette_type: PaletteType) -> usize {
+		match palette_type {
+			PaletteType::Empty => 0,
+			PaletteType::HalfByte => SECTION_VOLUME / 2,
+			PaletteType::Byte => SECTION_VOLUME,
+			PaletteType::Short => SECTION_VOLUME * 2,
+		}
+	}
+
+	fn write(&self, buf: &mut BytesMut) {
+		match self {
+			Storage::Empty => {}
+			Storage::HalfByte(data) | Storage::Byte(data) => buf.put_slice(data),
+			Storage::Short(data) => {
+				for value in data.iter() {
+					buf.put_u16_le(*value);
+				}
+			}
+		}
+	}
+
+	fn read(palette_type: PaletteType, buf: &mut impl Buf) -> Self {
+		let mut storage = Self::new(palette_type);
+		match &mut storage {
+			Storage::Empty => {}
+			Storage::HalfByte(data) | Storage::Byte(data) => buf.copy_to_slice(data),
+			Storage::Short(data) => {
+				for value in data.iter_mut() {
+					*value = buf.get_u16_le();
+				}
+			}
+		}
+		storage
+	}
+}
+
+/// Max amount of distinct entries each palette width can address.
+fn capacity(palette_type: PaletteType) -> usize {
+	match palette_type {
+		PaletteType::Empty => 1,
+		PaletteType::HalfByte => 16,
+		PaletteType::Byte => 256,
+		PaletteType::Short => 65536,
+	}
+}
+
+/// The narrowest palette type that can address `entries` distinct values.
+fn palette_type_for(entries: usize) -> PaletteType {
+	match entries {
+		0..=16 => PaletteType::HalfByte,
+		17..=256 => PaletteType::Byte,
+		_ => PaletteType::Short,
+	}
+}
+
+/// The palette type to narrow to once only `entries` distinct values are left. It keeps a quarter of headroom, so that
+/// a section whose values hover around the capacity of a palette type isn't widened and narrowed on every change.
+fn narrowed_type_for(entries: usize) -> PaletteType {
+	palette_type_for(entries + entries / 4)
+}
+
+/// Stores one value per block of a section through a palette of distinct values.
+///
+/// The palette starts out as [`PaletteType::Empty`] (every block holds the default value) and is widened as more distinct
+/// values are written, then narrowed again once entries stop being used.
+#[derive(Debug, Clone)]
+pub struct Palette<T: PaletteValue> {
+	storage: Storage,
+	/// Internal id -> value. Slots with a count of 0 are free.
+	entries: Vec<T>,
+	/// Internal id -> number of blocks using it.
+	counts: Vec<u16>,
+	lookup: HashMap<T, u16>,
+	free: Vec<u16>,
+}
+
+impl<T: PaletteValue> Default for Palette<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: PaletteValue> Palette<T> {
+	pub fn new() -> Self {
+		Self {
+			storage: Storage::Empty,
+			entries: Vec::new(),
+			counts: Vec::new(),
+			lookup: HashMap::new(),
+			free: Vec::new(),
+		}
+	}
+
+	/// Creates a palette where every block holds `value`.
+	pub fn filled(value: T) -> Self {
+		let mut palette = Self::new();
+		if value != T::default() {
+			palette.storage = Storage::new(PaletteType::HalfByte);
+			palette.entries.push(value);
+			palette.counts.push(SECTION_VOLUME as u16);
+			palette.lookup.insert(value, 0);
+		}
+		palette
+	}
+
+	pub fn palette_type(&self) -> PaletteType {
+		self.storage.palette_type()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		matches!(self.storage, Storage::Empty)
+	}
+
+	/// Number of distinct values currently in use.
+	pub fn len(&self) -> usize {
+		match self.storage {
+			Storage::Empty => 1,
+			_ => self.entries.len() - self.free.len(),
+		}
+	}
+
+	/// Iterates over the distinct values currently in use.
+	pub fn values(&self) -> impl Iterator<Item = T> + '_ {
+		let implicit = self.is_empty().then(T::default);
+		let entries = self.entries.iter().zip(&self.counts).filter(|(_, count)| **count > 0).map(|(value, _)| *value);
+		implicit.into_iter().chain(entries)
+	}
+
+	/// Returns whether `value` is used by at least one block.
+	pub fn contains(&self, value: T) -> bool {
+		match self.storage {
+			Storage::Empty => value == T::default(),
+			_ => self.lookup.contains_key(&value),
+		}
+	}
+
+	#[inline]
+	pub fn get(&self, index: usize) -> T {
+		match self.storage {
+			Storage::Empty => T::default(),
+			_ => self.entries[self.storage.get(index) as usize],
+		}
+	}
+
+	/// Sets the value at `index`, returning the previous value.
+	pub fn set(&mut self, index: usize, value: T) -> T {
+		if self.is_empty() {
+			if value == T::default() {
+				return value;
+			}
+			self.storage = Storage::new(PaletteType::HalfByte);
+			self.entries.push(T::default());
+			self.counts.push(SECTION_VOLUME as u16);
+			self.lookup.insert(T::default(), 0);
+		}
+
+		let old_id = self.storage.get(index);
+		let old = self.entries[old_id as usize];
+		if old == value {
+			return old;
+		}
+
+		self.counts[old_id as usize] -= 1;
+		let freed = self.counts[old_id as usize] == 0;
+		if freed {
+			self.lookup.remove(&old);
+			self.free.push(old_id);
+		}
+
+		let new_id = match self.lookup.get(&value) {
+			Some(id) => *id,
+			None => self.allocate(value),
+		};
+		self.storage.set(index, new_id);
+		self.counts[new_id as usize] += 1;
+
+		if freed {
+			self.shrink_to_fit();
+		}
+
+		old
+	}
+
+	fn allocate(&mut self, value: T) -> u16 {
+		let id = match self.free.pop() {
+			Some(id) => {
+				self.entries[id as usize] = value;
+				id
+			}
+			None => {
+				let id = self.entries.len();
+				if id >= capacity(self.palette_type()) {
+					self.resize(palette_type_for(id + 1));
+				}
+				self.entries.push(value);
+				self.counts.push(0);
+				id as u16
+			}
+		};
+		self.lookup.insert(value, id);
+		id
+	}
+
+	/// Widens the storage without touching internal ids.
+	fn resize(&mut self, palette_type: PaletteType) {
+		let mut storage = Storage::new(palette_type);
+		for index in 0..SECTION_VOLUME {
+			storage.set(index, self.storage.get(index));
+		}
+		self.storage = storage;
+	}
+
+	/// Compacts the internal ids and narrows the storage if the live entries comfortably fit into a smaller palette type.
+	fn shrink_to_fit(&mut self) {
+		let live = self.len();
+		if live == 1 && self.lookup.contains_key(&T::default()) {
+			*self = Self::new();
+			return;
+		}
+
+		let target = narrowed_type_for(live);
+		if (target as u8) >= (self.palette_type() as u8) {
+			return;
+		}
+
+		let mut remap = vec![0u16; self.entries.len()];
+		let mut entries = Vec::with_capacity(live);
+		let mut counts = Vec::with_capacity(live);
+		for (old_id, (value, count)) in self.entries.iter().zip(&self.counts).enumerate() {
+			if *count == 0 {
+				continue;
+			}
+			remap[old_id] = entries.len() as u16;
+			entries.push(*value);
+			counts.push(*count);
+		}
+
+		let mut storage = Storage::new(target);
+		for index in 0..SECTION_VOLUME {
+			storage.set(index, remap[self.storage.get(index) as usize]);
+		}
+
+		self.lookup = entries.iter().enumerate().map(|(id, value)| (*value, id as u16)).collect();
+		self.storage = storage;
+		self.entries = entries;
+		self.counts = counts;
+		self.free.clear();
+	}
+
+	/// Writes the palette type, the entry table and the raw index storage.
+	///
+	/// Layout (little endian):
+	/// - `u8` palette type
+	/// - for non-empty palettes: `u16` entry count, then per entry the internal id (`u8`, or `u16` for [`PaletteType::Short`]),
+	///   the value and a `u16` block count, followed by the index storage (nibbles, bytes or shorts).
+	pub fn encode(&self, buf: &mut BytesMut) {
+		let palette_type = self.palette_type();
+		// Encoding a unit-like enum into a buffer can't fail
+		let _ = palette_type.encode(buf);
+		if self.is_empty() {
+			return;
+		}
+
+		buf.reserve(2 + self.len() * (4 + T::SIZE) + Storage::byte_len(palette_type));
+		buf.put_u16_le(self.len() as u16);
+		for (id, (value, count)) in self.entries.iter().zip(&self.counts).enumerate() {
+			if *count == 0 {
+				continue;
+			}
+			match palette_type {
+				PaletteType::Short => buf.put_u16_le(id as u16),
+				_ => buf.put_u8(id as u8),
+			}
+			value.write(buf);
+			buf.put_u16_le(*count);
+		}
+		self.storage.write(buf);
+	}
+
+	pub fn decode(buf: &mut impl Buf) -> ChunkResult<Self> {
+		ChunkError::ensure_remaining(buf.remaining(), 1)?;
+		let raw_type = buf.chunk()[0];
+		let palette_type = PaletteType::decode(buf).map_err(|_| ChunkError::InvalidPaletteType(raw_type))?;
+		if palette_type == PaletteType::Empty {
+			return Ok(Self::new());
+		}
+
+		ChunkError::ensure_remaining(buf.remaining(), 2)?;
+		let entry_count = buf.get_u16_le() as usize;
+		if entry_count == 0 || entry_count > capacity(palette_type) {
+			return Err(ChunkError::InvalidPalette(format!("{} entries in a {} palette", entry_count, palette_type)));
+		}
+
+		let id_size = if palette_type == PaletteType::Short { 2 } else { 1 };
+		ChunkError::ensure_remaining(buf.remaining(), entry_count * (id_size + T::SIZE + 2))?;
+
+		let mut palette = Self::new();
+		for _ in 0..entry_count {
+			let id = if id_size == 2 { buf.get_u16_le() as usize } else { buf.get_u8() as usize };
+			let value = T::read(buf);
+			buf.advance(2); // The block count is recomputed from the storage below
+			if id >= capacity(palette_type) {
+				return Err(ChunkError::InvalidPalette(format!("Entry id {} out of range for a {} palette", id, palette_type)));
+			}
+			if palette.lookup.insert(value, id as u16).is_some() {
+				return Err(ChunkError::InvalidPalette(format!("Duplicate entry {:?}", value)));
+			}
+			if palette.entries.len() <= id {
+				palette.entries.resize(id + 1, T::default());
+				palette.counts.resize(id + 1, 0);
+			}
+			palette.entries[id] = value;
+		}
+
+		let byte_len = Storage::byte_len(palette_type);
+		ChunkError::ensure_remaining(buf.remaining(), byte_len)?;
+		palette.storage = Storage::read(palette_type, buf);
+
+		for index in 0..SECTION_VOLUME {
+			let id = palette.storage.get(index) as usize;
+			match palette.counts.get_mut(id) {
+				Some(count) => *count += 1,
+				None => return Err(ChunkError::InvalidPalette(format!("Block {} references unknown entry {}", index, id))),
+			}
+		}
+
+		for (id, count) in palette.counts.iter().enumerate() {
+			if *count == 0 {
+				if let Some(id_in_lookup) = palette.lookup.get(&palette.entries[id])
+					&& *id_in_lookup as usize == id
+				{
+					palette.lookup.remove(&palette.entries[id]);
+				}
+				palette.free.push(id as u16);
+			}
+		}
+
+		// Senders aren't required to send the narrowest palette, so normalize it
+		palette.shrink_to_fit();
+
+		Ok(palette)
+	}
+}
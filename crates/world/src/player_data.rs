@@ -0,0 +1,219 @@
+use std::{
+	fs,
+	io::ErrorKind,
+	path::PathBuf,
+	sync::Arc,
+};
+
+use protocol::v2::{
+	GameMode,
+	SortType,
+};
+use serde::{
+	Deserialize,
+	Serialize,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+	error::{
+		PlayerDataError,
+		PlayerDataResult,
+	},
+	inventory::PlayerInventory,
+};
+
+pub const PLAYER_DATA_VERSION: u32 = 1;
+
+/// Upgrades player data by one version of its format.
+pub type PlayerDataMigration = fn(Value) -> PlayerDataResult<Value>;
+
+/// Upgrades between consecutive versions, the one at index `n` from version `n + 1` to `n + 2`. Bumping
+/// [`PLAYER_DATA_VERSION`] takes adding the upgrade from the previous version here.
+const MIGRATIONS: [PlayerDataMigration; PLAYER_DATA_VERSION as usize - 1] = [];
+
+/// Everything about a player that has to survive them leaving.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerData {
+	/// Where the player was, `None` until they spawned for the first time.
+	#[serde(default)]
+	pub position: Option<SavedPosition>,
+	/// World the position is in.
+	#[serde(default)]
+	pub world: Option<Uuid>,
+	#[serde(default)]
+	pub inventory: PlayerInventory,
+	/// `None` for players that get the server's default game mode.
+	#[serde(default, with = "game_mode_name")]
+	pub game_mode: Option<GameMode>,
+	#[serde(default)]
+	pub stats: PlayerStats,
+	#[serde(default)]
+	pub preferences: PlayerPreferences,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedPosition {
+	pub x: f64,
+	pub y: f64,
+	pub z: f64,
+}
+
+/// Counters kept over every session of a player.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlayerStats {
+	pub play_time_secs: u64,
+	pub joins: u64,
+	pub blocks_placed: u64,
+	pub blocks_broken: u64,
+	pub items_crafted: u64,
+}
+
+/// Settings the player picked in the client that the server keeps for them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlayerPreferences {
+	/// View radius the client asked for last, in chunks.
+	pub view_radius: Option<i32>,
+	/// How the player last sorted their inventory.
+	#[serde(with = "sort_type_name")]
+	pub sort_type: Option<SortType>,
+}
+
+/// What's written to disk: the data along with the version of its format.
+#[derive(Serialize)]
+struct PlayerDataFile<'a> {
+	version: u32,
+	#[serde(flatten)]
+	data: &'a PlayerData,
+}
+
+/// Keeps the data of every player in its own file, named after their UUID.
+///
+/// Files carry the version of their format and are brought up to date through [`migrate`] when they're read.
+pub struct PlayerDataStore {
+	dir: PathBuf,
+}
+
+impl PlayerDataStore {
+	pub fn new(dir: PathBuf) -> Self {
+		Self { dir }
+	}
+
+	fn path(&self, uuid: Uuid) -> PathBuf {
+		self.dir.join(format!("{uuid}.json"))
+	}
+
+	/// Reads a player's data, or returns `None` if they never had any saved.
+	pub async fn load(self: &Arc<Self>, uuid: Uuid) -> PlayerDataResult<Option<PlayerData>> {
+		let store = self.clone();
+		tokio::task::spawn_blocking(move || store.read(uuid)).await?
+	}
+
+	/// Writes a player's data, replacing the old file only once the new one is complete.
+	pub async fn save(self: &Arc<Self>, uuid: Uuid, data: PlayerData) -> PlayerDataResult<()> {
+		let store = self.clone();
+		tokio::task::spawn_blocking(move || store.write(uuid, &data)).await?
+	}
+
+	fn read(&self, uuid: Uuid) -> PlayerDataResult<Option<PlayerData>> {
+		let bytes = match fs::read(self.path(uuid)) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+		let mut value: Value = serde_json::from_slice(&bytes)?;
+		let version = take_version(&mut value);
+		Ok(Some(serde_json::from_value(migrate(value, version)?)?))
+	}
+
+	fn write(&self, uuid: Uuid, data: &PlayerData) -> PlayerDataResult<()> {
+		fs::create_dir_all(&self.dir)?;
+		let file = PlayerDataFile {
+			version: PLAYER_DATA_VERSION,
+			data,
+		};
+		let tmp = self.dir.join(format!("{uuid}.json.tmp"));
+		fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+		fs::rename(tmp, self.path(uuid))?;
+		Ok(())
+	}
+}
+
+/// Removes the format version from a player data file, 0 for files without one.
+fn take_version(value: &mut Value) -> u32 {
+	let version = value.as_object_mut().and_then(|file| file.remove("version")).and_then(|version| version.as_u64()).unwrap_or(0);
+	u32::try_from(version).unwrap_or(u32::MAX)
+}
+
+/// Brings player data written in an older version of the format up to [`PLAYER_DATA_VERSION`].
+pub fn migrate(mut value: Value, version: u32) -> PlayerDataResult<Value> {
+	if version == 0 || version > PLAYER_DATA_VERSION {
+		return Err(PlayerDataError::UnsupportedVersion(version));
+	}
+	for migration in &MIGRATIONS[version as usize - 1..] {
+		value = migration(value)?;
+	}
+	Ok(value)
+}
+
+/// Stores game modes by name, so the file doesn't depend on their protocol ids.
+mod game_mode_name {
+	use protocol::v2::GameMode;
+	use serde::{
+		de::Error,
+		Deserialize,
+		Deserializer,
+		Serializer,
+	};
+
+	pub fn serialize<S: Serializer>(game_mode: &Option<GameMode>, serializer: S) -> Result<S::Ok, S::Error> {
+		match game_mode {
+			Some(GameMode::Adventure) => serializer.serialize_some("adventure"),
+			Some(GameMode::Creative) => serializer.serialize_some("creative"),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<GameMode>, D::Error> {
+		match Option::<String>::deserialize(deserializer)?.as_deref() {
+			Some("adventure") => Ok(Some(GameMode::Adventure)),
+			Some("creative") => Ok(Some(GameMode::Creative)),
+			Some(other) => Err(D::Error::custom(format!("unknown game mode {other}"))),
+			None => Ok(None),
+		}
+	}
+}
+
+/// Stores sort types by name, like [`game_mode_name`].
+mod sort_type_name {
+	use protocol::v2::SortType;
+	use serde::{
+		de::Error,
+		Deserialize,
+		Deserializer,
+		Serializer,
+	};
+
+	pub fn serialize<S: Serializer>(sort_type: &Option<SortType>, serializer: S) -> Result<S::Ok, S::Error> {
+		match sort_type {
+			Some(SortType::Name) => serializer.serialize_some("name"),
+			Some(SortType::Type) => serializer.serialize_some("type"),
+			Some(SortType::Rarity) => serializer.serialize_some("rarity"),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SortType>, D::Error> {
+		match Option::<String>::deserialize(deserializer)?.as_deref() {
+			Some("name") => Ok(Some(SortType::Name)),
+			Some("type") => Ok(Some(SortType::Type)),
+			Some("rarity") => Ok(Some(SortType::Rarity)),
+			Some(other) => Err(D::Error::custom(format!("unknown sort type {other}"))),
+			None => Ok(None),
+		}
+	}
+}
@@ -0,0 +1,192 @@
+use assets::{
+	Asset,
+	StoreError,
+	StoreResult,
+};
+use protocol::v2::{
+	BenchRequirement,
+	BenchType,
+	CraftingRecipe,
+	MaterialQuantity,
+};
+use serde::Deserialize;
+
+use crate::inventory::ItemStack;
+
+/// A crafting recipe, keyed by an id made from the item that defines it.
+#[derive(Debug, Clone)]
+pub struct RecipeAsset {
+	id: String,
+	/// Item the recipe belongs to, which it makes unless it lists other outputs.
+	item_id: String,
+	pub definition: RecipeDefinition,
+}
+
+impl Asset for RecipeAsset {
+	type Key = String;
+
+	fn key(&self) -> &Self::Key {
+		&self.id
+	}
+}
+
+/// What an item asset contributes to crafting: the recipe making it and the resource types it counts as.
+#[derive(Debug, Clone, Default)]
+pub struct CraftingItem {
+	pub recipe: Option<RecipeAsset>,
+	pub resource_types: Vec<String>,
+}
+
+impl CraftingItem {
+	pub fn from_item_json(name: impl Into<String>, bytes: &[u8]) -> StoreResult<Self> {
+		let item: ItemDefinition = serde_json::from_slice(bytes).map_err(|e| StoreError::Decode(e.to_string()))?;
+		let name = name.into();
+		Ok(Self {
+			recipe: item.recipe.map(|definition| RecipeAsset::new(name, definition)),
+			resource_types: item.resource_types.into_iter().map(|resource| resource.id).collect(),
+		})
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ItemDefinition {
+	recipe: Option<RecipeDefinition>,
+	#[serde(default)]
+	resource_types: Vec<ResourceTypeDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResourceTypeDefinition {
+	id: String,
+}
+
+/// The `Recipe` section of an item asset.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct RecipeDefinition {
+	pub input: Vec<MaterialDefinition>,
+	/// Everything the recipe makes. Empty for recipes that only make their item.
+	pub output: Vec<MaterialDefinition>,
+	/// How many of its item the recipe makes when it has no other outputs.
+	pub output_quantity: Option<i32>,
+	/// Benches the recipe can be crafted at. Without any it can be crafted from the pocket crafting window.
+	pub bench_requirement: Vec<BenchRequirementDefinition>,
+	pub time_seconds: f32,
+	pub knowledge_required: bool,
+}
+
+/// An input or output of a recipe: an item, or any item of a resource type for inputs.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct MaterialDefinition {
+	pub item_id: Option<String>,
+	pub resource_type_id: Option<String>,
+	pub quantity: i32,
+}
+
+impl MaterialDefinition {
+	fn to_protocol(&self) -> MaterialQuantity {
+		MaterialQuantity {
+			item_tag: 0,
+			quantity: self.quantity,
+			item_id: self.item_id.clone(),
+			resource_type_id: self.resource_type_id.clone(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BenchRequirementDefinition {
+	#[serde(rename = "Type")]
+	pub bench_type: Option<String>,
+	pub id: Option<String>,
+	pub categories: Vec<String>,
+	pub required_tier_level: i32,
+}
+
+impl BenchRequirementDefinition {
+	pub fn bench_type(&self) -> BenchType {
+		parse_bench_type(self.bench_type.as_deref())
+	}
+}
+
+pub(crate) fn parse_bench_type(name: Option<&str>) -> BenchType {
+	match name {
+		Some("Processing") => BenchType::Processing,
+		Some("DiagramCrafting") => BenchType::DiagramCrafting,
+		Some("StructuralCrafting") => BenchType::StructuralCrafting,
+		_ => BenchType::Crafting,
+	}
+}
+
+impl RecipeAsset {
+	pub fn new(item_id: impl Into<String>, definition: RecipeDefinition) -> Self {
+		let item_id = item_id.into();
+		Self {
+			id: format!("{item_id}_Recipe_Generated_0"),
+			item_id,
+			definition,
+		}
+	}
+
+	pub fn id(&self) -> &str {
+		&self.id
+	}
+
+	/// What one craft makes.
+	pub fn outputs(&self) -> Vec<ItemStack> {
+		if self.definition.output.is_empty() {
+			return vec![ItemStack::new(&self.item_id, self.definition.output_quantity.unwrap_or(1).max(1))];
+		}
+		self.definition.output.iter().filter_map(|output| Some(ItemStack::new(output.item_id.as_ref()?, output.quantity.max(1)))).collect()
+	}
+
+	/// Whether the recipe can be crafted at a bench, or from the pocket crafting window for `None`.
+	pub fn craftable_at(&self, bench: Option<(BenchType, &str, i32)>) -> bool {
+		let requirements = &self.definition.bench_requirement;
+		match bench {
+			None => requirements.is_empty(),
+			Some((bench_type, id, tier)) => requirements
+				.iter()
+				.any(|requirement| requirement.bench_type() == bench_type && requirement.id.as_deref() == Some(id) && requirement.required_tier_level <= tier),
+		}
+	}
+
+	pub fn to_protocol(&self) -> CraftingRecipe {
+		let definition = &self.definition;
+		let outputs: Vec<MaterialQuantity> = self
+			.outputs()
+			.iter()
+			.map(|output| MaterialQuantity {
+				item_tag: 0,
+				quantity: output.quantity,
+				item_id: Some(output.item_id.clone()),
+				resource_type_id: None,
+			})
+			.collect();
+		CraftingRecipe {
+			knowledge_required: definition.knowledge_required,
+			time_seconds: definition.time_seconds,
+			required_memories_level: 0,
+			id: Some(self.id.clone()),
+			inputs: Some(definition.input.iter().map(MaterialDefinition::to_protocol).collect()),
+			primary_output: outputs.first().cloned(),
+			outputs: Some(outputs),
+			bench_requirement: Some(
+				definition
+					.bench_requirement
+					.iter()
+					.map(|requirement| BenchRequirement {
+						bench_type: requirement.bench_type(),
+						required_tier_level: requirement.required_tier_level,
+						id: requirement.id.clone(),
+						categories: (!requirement.categories.is_empty()).then(|| requirement.categories.clone()),
+					})
+					.collect(),
+			),
+		}
+	}
+}
@@ -0,0 +1,457 @@
+use std::{
+	collections::HashMap,
+	fs::{
+		File,
+		OpenOptions,
+	},
+	io::{
+		Read,
+		Seek,
+		SeekFrom,
+		Write,
+	},
+	path::{
+		Path,
+		PathBuf,
+	},
+	sync::Arc,
+};
+
+use bytes::{
+	Buf,
+	BufMut,
+	BytesMut,
+};
+use parking_lot::Mutex;
+
+use crate::{
+	chunk::Chunk,
+	error::{
+		ChunkError,
+		ChunkResult,
+		RegionError,
+		RegionResult,
+	},
+	fluid::FluidSection,
+	section::ChunkSection,
+};
+
+/// Width and depth of a region in chunks.
+pub const REGION_SIZE: usize = 32;
+pub const REGION_CHUNKS: usize = REGION_SIZE * REGION_SIZE;
+/// How many region files [`RegionStorage`] keeps open at most.
+pub const MAX_OPEN_REGIONS: usize = 64;
+/// Version written into the header of new region files. Files in an older version are upgraded when they're opened.
+///
+/// - 1: blocks, tintmap and environments
+/// - 2: adds the fluid sections
+pub const REGION_FORMAT_VERSION: u16 = 2;
+
+const REGION_MAGIC: [u8; 4] = *b"HTRG";
+/// Magic, version and a reserved `u16`.
+const HEADER_SIZE: u64 = 8;
+/// `u32` offset and `u32` length per chunk.
+const TABLE_ENTRY_SIZE: u64 = 8;
+const TABLE_SIZE: u64 = REGION_CHUNKS as u64 * TABLE_ENTRY_SIZE;
+const DATA_START: u64 = HEADER_SIZE + TABLE_SIZE;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Region coordinate containing the given chunk coordinate.
+#[inline]
+pub fn region_coord(chunk: i32) -> i32 {
+	chunk >> 5
+}
+
+/// Index of a chunk inside its region's offset table.
+#[inline]
+fn region_index(x: i32, z: i32) -> usize {
+	(((z & 31) as usize) << 5) | (x & 31) as usize
+}
+
+/// Encodes a chunk in the uncompressed region layout: a `u8` section count, the sections as in `SetChunk.data`, the
+/// tintmap and environment maps as in their packets, then the fluid sections as in `SetFluids.data`. Light and the heightmap
+/// are derived from the blocks and aren't stored.
+pub fn encode_chunk(chunk: &Chunk, buf: &mut BytesMut) {
+	buf.put_u8(chunk.sections().len() as u8);
+	for section in chunk.sections() {
+		section.encode(buf);
+	}
+	buf.put_slice(&chunk.maps().tintmap_bytes());
+	buf.put_slice(&chunk.maps().environments_bytes());
+	for section in chunk.fluid_sections() {
+		section.encode(buf);
+	}
+}
+
+/// Decodes a chunk written by [`encode_chunk`] in the given version of the region format. The light of the returned chunk
+/// has to be computed by the caller.
+pub fn decode_chunk(x: i32, z: i32, version: u16, buf: &mut impl Buf) -> ChunkResult<Chunk> {
+	let mut chunk = Chunk::new(x, z);
+	ChunkError::ensure_remaining(buf.remaining(), 1)?;
+	let count = buf.get_u8() as usize;
+	if count != chunk.sections.len() {
+		return Err(ChunkError::SectionOutOfRange(count as i32));
+	}
+	for section in &mut chunk.sections {
+		*section = ChunkSection::decode(buf)?;
+	}
+	chunk.maps.apply_tintmap(buf)?;
+	chunk.maps.apply_environments(buf)?;
+	if version >= 2 {
+		for section in &mut chunk.fluids {
+			*section = FluidSection::decode(buf)?;
+		}
+	}
+	chunk.maps.recompute_heights(&chunk.sections);
+	Ok(chunk)
+}
+
+/// A single region file holding up to [`REGION_CHUNKS`] zstd-compressed chunks.
+///
+/// Layout (little endian):
+/// - header: `b"HTRG"`, `u16` format version, `u16` reserved
+/// - offset table: per chunk a `u32` byte offset and `u32` byte length of its compressed data, both zero if absent
+/// - chunk data
+///
+/// Chunks are never written over the data the table points to. They go into free space and the table is only updated
+/// once they're synced, so after a crash every chunk is either in its old or its new state. The old data is freed after
+/// that.
+#[derive(Debug)]
+pub struct RegionFile {
+	file: File,
+	table: Box<[(u32, u32)]>,
+	end: u64,
+	/// Ranges of the data area no chunk uses, as offset and length sorted by offset.
+	free: Vec<(u64, u64)>,
+}
+
+impl RegionFile {
+	/// Opens the region file at `path`, creating an empty one if it doesn't exist.
+	pub fn open(path: &Path) -> RegionResult<Self> {
+		let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+		let length = file.metadata()?.len();
+		if length == 0 {
+			return Self::create(file);
+		}
+
+		if length < DATA_START {
+			return Err(RegionError::Corrupt(format!("File is {} bytes, shorter than its header", length)));
+		}
+		let mut header = vec![0; DATA_START as usize];
+		file.read_exact(&mut header)?;
+		let mut buf = &header[..];
+		if buf[..4] != REGION_MAGIC {
+			return Err(RegionError::InvalidMagic);
+		}
+		buf.advance(4);
+		let version = buf.get_u16_le();
+		if version == 0 || version > REGION_FORMAT_VERSION {
+			return Err(RegionError::UnsupportedVersion(version));
+		}
+		buf.advance(2);
+
+		let mut table = vec![(0, 0); REGION_CHUNKS].into_boxed_slice();
+		for entry in table.iter_mut() {
+			let (offset, len) = (buf.get_u32_le(), buf.get_u32_le());
+			if offset != 0 && ((offset as u64) < DATA_START || offset as u64 + len as u64 > length) {
+				return Err(RegionError::Corrupt(format!("Chunk at offset {} with length {} is out of bounds", offset, len)));
+			}
+			*entry = (offset, len);
+		}
+
+		let free = free_ranges(&table, length);
+		let region = Self { file, table, end: length, free };
+		if version < REGION_FORMAT_VERSION {
+			return region.upgrade(path, version);
+		}
+		Ok(region)
+	}
+
+	/// Writes the header of a new region file without any chunks into an empty file.
+	fn create(mut file: File) -> RegionResult<Self> {
+		let mut header = BytesMut::with_capacity(DATA_START as usize);
+		header.put_slice(&REGION_MAGIC);
+		header.put_u16_le(REGION_FORMAT_VERSION);
+		header.put_u16_le(0);
+		header.put_bytes(0, TABLE_SIZE as usize);
+		file.write_all(&header)?;
+		Ok(Self {
+			file,
+			table: vec![(0, 0); REGION_CHUNKS].into_boxed_slice(),
+			end: DATA_START,
+			free: Vec::new(),
+		})
+	}
+
+	/// Rewrites every chunk of a file written in an older version of the format into a new file, which then replaces
+	/// the old one. Until it does, a crash leaves the old file as it was.
+	fn upgrade(mut self, path: &Path, version: u16) -> RegionResult<Self> {
+		let tmp = path.with_extension("region.tmp");
+		let mut upgraded = Self::create(OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&tmp)?)?;
+		for index in 0..REGION_CHUNKS {
+			let (x, z) = ((index % REGION_SIZE) as i32, (index / REGION_SIZE) as i32);
+			if let Some(chunk) = self.read_chunk_version(x, z, version)? {
+				upgraded.write_chunk(&chunk)?;
+			}
+		}
+		upgraded.sync()?;
+		std::fs::rename(tmp, path)?;
+		Ok(upgraded)
+	}
+
+	pub fn contains(&self, x: i32, z: i32) -> bool {
+		self.table[region_index(x, z)].0 != 0
+	}
+
+	/// Reads and decompresses the chunk at chunk coordinates `(x, z)`, if the region has it.
+	pub fn read_chunk(&mut self, x: i32, z: i32) -> RegionResult<Option<Chunk>> {
+		self.read_chunk_version(x, z, REGION_FORMAT_VERSION)
+	}
+
+	fn read_chunk_version(&mut self, x: i32, z: i32, version: u16) -> RegionResult<Option<Chunk>> {
+		let (offset, len) = self.table[region_index(x, z)];
+		if offset == 0 {
+			return Ok(None);
+		}
+		let mut compressed = vec![0; len as usize];
+		self.file.seek(SeekFrom::Start(offset as u64))?;
+		self.file.read_exact(&mut compressed)?;
+		let data = zstd::decode_all(&compressed[..])?;
+		Ok(Some(decode_chunk(x, z, version, &mut &data[..])?))
+	}
+
+	/// Compresses and writes the chunk, updating the offset table.
+	pub fn write_chunk(&mut self, chunk: &Chunk) -> RegionResult<()> {
+		self.write_chunks([chunk])
+	}
+
+	/// Compresses and writes several chunks, syncing the file once for all of them before the offset table is updated.
+	pub fn write_chunks<'a>(&mut self, chunks: impl IntoIterator<Item = &'a Chunk>) -> RegionResult<()> {
+		let mut written = Vec::new();
+		for chunk in chunks {
+			let mut data = BytesMut::new();
+			encode_chunk(chunk, &mut data);
+			let compressed = zstd::encode_all(&data[..], COMPRESSION_LEVEL)?;
+			let len = u32::try_from(compressed.len()).map_err(|_| RegionError::Corrupt(format!("Chunk is too large: {} bytes", compressed.len())))?;
+			let offset = u32::try_from(self.allocate(len as u64)).map_err(|_| RegionError::Corrupt("Region file exceeds 4 GiB".to_string()))?;
+			self.file.seek(SeekFrom::Start(offset as u64))?;
+			self.file.write_all(&compressed)?;
+			written.push((region_index(chunk.x(), chunk.z()), (offset, len)));
+		}
+		self.sync()?;
+
+		let mut replaced = Vec::with_capacity(written.len());
+		for (index, entry) in written {
+			replaced.push(self.table[index]);
+			self.write_entry(index, entry)?;
+		}
+		self.sync()?;
+		for (offset, len) in replaced {
+			self.release(offset, len);
+		}
+		Ok(())
+	}
+
+	/// Removes the chunk from the offset table and frees its data.
+	pub fn remove_chunk(&mut self, x: i32, z: i32) -> RegionResult<()> {
+		let index = region_index(x, z);
+		let (offset, len) = self.table[index];
+		self.write_entry(index, (0, 0))?;
+		// The data can only be reused once the table no longer points to it on disk
+		self.sync()?;
+		self.release(offset, len);
+		Ok(())
+	}
+
+	/// Finds room for `len` bytes of chunk data, in the first free range that fits or at the end of the file.
+	fn allocate(&mut self, len: u64) -> u64 {
+		let Some(index) = self.free.iter().position(|&(_, free)| free >= len) else {
+			let offset = self.end;
+			self.end += len;
+			return offset;
+		};
+		let (offset, free) = self.free[index];
+		if free == len {
+			self.free.remove(index);
+		} else {
+			self.free[index] = (offset + len, free - len);
+		}
+		offset
+	}
+
+	/// Gives the data of a chunk back, merging it with the free ranges next to it.
+	fn release(&mut self, offset: u32, len: u32) {
+		let (offset, len) = (offset as u64, len as u64);
+		if offset == 0 || len == 0 {
+			return;
+		}
+		let index = self.free.partition_point(|&(free, _)| free < offset);
+		self.free.insert(index, (offset, len));
+		if index + 1 < self.free.len() && self.free[index + 1].0 == offset + len {
+			self.free[index].1 += self.free[index + 1].1;
+			self.free.remove(index + 1);
+		}
+		if index > 0 && self.free[index - 1].0 + self.free[index - 1].1 == offset {
+			self.free[index - 1].1 += self.free[index].1;
+			self.free.remove(index);
+		}
+	}
+
+	fn write_entry(&mut self, index: usize, entry: (u32, u32)) -> RegionResult<()> {
+		let mut buf = BytesMut::with_capacity(TABLE_ENTRY_SIZE as usize);
+		buf.put_u32_le(entry.0);
+		buf.put_u32_le(entry.1);
+		self.file.seek(SeekFrom::Start(HEADER_SIZE + index as u64 * TABLE_ENTRY_SIZE))?;
+		self.file.write_all(&buf)?;
+		self.table[index] = entry;
+		Ok(())
+	}
+
+	pub fn sync(&mut self) -> RegionResult<()> {
+		self.file.sync_data()?;
+		Ok(())
+	}
+}
+
+/// Ranges of the data area between `DATA_START` and `end` that no entry of the table points into.
+fn free_ranges(table: &[(u32, u32)], end: u64) -> Vec<(u64, u64)> {
+	let mut used: Vec<(u64, u64)> = table.iter().filter(|(offset, _)| *offset != 0).map(|&(offset, len)| (offset as u64, len as u64)).collect();
+	used.sort_unstable();
+	let mut free = Vec::new();
+	let mut position = DATA_START;
+	for (offset, len) in used {
+		if offset > position {
+			free.push((position, offset - position));
+		}
+		position = position.max(offset + len);
+	}
+	if end > position {
+		free.push((position, end - position));
+	}
+	free
+}
+
+/// A region file, opened by whoever needs it first. Locking it keeps everyone else waiting until it's open.
+type SharedRegion = Arc<Mutex<Option<RegionFile>>>;
+
+/// The region files [`RegionStorage`] has open, along with when each was used last.
+#[derive(Debug, Default)]
+struct OpenRegions {
+	files: HashMap<(i32, i32), (SharedRegion, u64)>,
+	clock: u64,
+}
+
+impl OpenRegions {
+	/// Closes the least recently used files until at most `keep` are open. Files still being accessed stay open, so that
+	/// no file is ever open twice.
+	fn evict(&mut self, keep: usize) {
+		while self.files.len() > keep {
+			let unused = self.files.iter().filter(|(_, (region, _))| Arc::strong_count(region) == 1).min_by_key(|(_, (_, used))| *used).map(|(key, _)| *key);
+			let Some(key) = unused else {
+				break;
+			};
+			self.files.remove(&key);
+		}
+	}
+}
+
+/// Loads and saves chunks from a directory of region files named `r.<x>.<z>.region`.
+///
+/// File access happens on tokio's blocking pool, so these methods are safe to call from async code.
+/// Up to [`MAX_OPEN_REGIONS`] region files are kept open, closing the least recently used ones beyond that.
+#[derive(Debug)]
+pub struct RegionStorage {
+	dir: PathBuf,
+	regions: Mutex<OpenRegions>,
+}
+
+impl RegionStorage {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self {
+			dir: dir.into(),
+			regions: Mutex::new(OpenRegions::default()),
+		}
+	}
+
+	pub fn dir(&self) -> &Path {
+		&self.dir
+	}
+
+	pub fn region_path(&self, region_x: i32, region_z: i32) -> PathBuf {
+		self.dir.join(format!("r.{}.{}.region", region_x, region_z))
+	}
+
+	/// Runs `f` on the region file containing chunk `(x, z)`, opening or creating it if needed. Returns `None` without
+	/// running it if the file doesn't exist and `create` isn't set.
+	///
+	/// The file is opened, and upgraded if it's in an older format, under its own lock only, so that accessing other
+	/// regions meanwhile doesn't have to wait.
+	fn with_region<R>(&self, x: i32, z: i32, create: bool, f: impl FnOnce(&mut RegionFile) -> RegionResult<R>) -> RegionResult<Option<R>> {
+		let key = (region_coord(x), region_coord(z));
+		let path = self.region_path(key.0, key.1);
+		let region = {
+			let mut regions = self.regions.lock();
+			regions.clock += 1;
+			let clock = regions.clock;
+			match regions.files.get_mut(&key) {
+				Some((region, used)) => {
+					*used = clock;
+					region.clone()
+				}
+				None if !create && !path.exists() => return Ok(None),
+				None => {
+					regions.evict(MAX_OPEN_REGIONS - 1);
+					let region = SharedRegion::default();
+					regions.files.insert(key, (region.clone(), clock));
+					region
+				}
+			}
+		};
+
+		let mut file = region.lock();
+		if file.is_none() {
+			std::fs::create_dir_all(&self.dir)?;
+			*file = Some(RegionFile::open(&path)?);
+		}
+		f(file.as_mut().expect("The region file was just opened")).map(Some)
+	}
+
+	/// Loads the chunk at chunk coordinates `(x, z)`, or `None` if it was never saved.
+	pub async fn load_chunk(self: &Arc<Self>, x: i32, z: i32) -> RegionResult<Option<Chunk>> {
+		let storage = self.clone();
+		tokio::task::spawn_blocking(move || Ok(storage.with_region(x, z, false, |region| region.read_chunk(x, z))?.flatten())).await?
+	}
+
+	/// Saves a chunk, replacing whatever was stored for its coordinates.
+	pub async fn save_chunk(self: &Arc<Self>, chunk: Chunk) -> RegionResult<()> {
+		self.save_chunks(vec![chunk]).await
+	}
+
+	/// Saves several chunks in one go, grouping the writes by region.
+	pub async fn save_chunks(self: &Arc<Self>, chunks: Vec<Chunk>) -> RegionResult<()> {
+		let storage = self.clone();
+		tokio::task::spawn_blocking(move || {
+			let mut by_region: HashMap<_, Vec<&Chunk>> = HashMap::new();
+			for chunk in &chunks {
+				by_region.entry((region_coord(chunk.x()), region_coord(chunk.z()))).or_default().push(chunk);
+			}
+			for chunks in by_region.into_values() {
+				storage.with_region(chunks[0].x(), chunks[0].z(), true, |region| region.write_chunks(chunks))?;
+			}
+			Ok(())
+		})
+		.await?
+	}
+
+	/// Removes a saved chunk, so that it gets generated again the next time it's needed.
+	pub async fn remove_chunk(self: &Arc<Self>, x: i32, z: i32) -> RegionResult<()> {
+		let storage = self.clone();
+		tokio::task::spawn_blocking(move || storage.with_region(x, z, false, |region| region.remove_chunk(x, z)).map(|_| ())).await?
+	}
+
+	/// Closes every open region file that isn't being accessed right now. They are reopened the next time a chunk in them
+	/// is accessed.
+	pub fn close_regions(&self) {
+		self.regions.lock().evict(0);
+	}
+}
@@ -0,0 +1,684 @@
+use std::collections::{
+	BTreeMap,
+	HashMap,
+};
+
+use assets::{
+	AssetCodec,
+	AssetStore,
+	HashMapIndex,
+	LoadOptions,
+	LoadOutcome,
+	StoreError,
+	StoreResult,
+	WithInput,
+};
+use bytes::Bytes;
+use protocol::v2::{
+	assets::{
+		UpdateBlockTypes,
+		UpdateEnvironments,
+		UpdateFluids,
+		UpdateRecipes,
+		UpdateWeathers,
+	},
+	BlockType,
+	UpdateType,
+};
+
+use crate::{
+	block_type::{
+		empty_block_type,
+		unknown_block_type,
+		BlockTypeAsset,
+	},
+	fluid::FluidState,
+	fluid_type::{
+		FluidDefinition,
+		FluidTypeAsset,
+	},
+	generator::TerrainBlocks,
+	light::{
+		BlockLighting,
+		MAX_LIGHT,
+	},
+	recipe::{
+		MaterialDefinition,
+		RecipeAsset,
+	},
+	section::BlockState,
+	weather_type::{
+		EnvironmentAsset,
+		EnvironmentDefinition,
+		WeatherAsset,
+	},
+};
+
+/// Name of the block with id 0, air.
+pub const EMPTY_BLOCK: &str = "Empty";
+/// Name of the block with id 1, which stands in for blocks whose asset is missing.
+pub const UNKNOWN_BLOCK: &str = "Unknown";
+/// Name of the fluid with id 0, no fluid at all.
+pub const EMPTY_FLUID: &str = "Empty";
+/// Name of the environment with id 0 unless configured otherwise.
+pub const DEFAULT_ENVIRONMENT_NAME: &str = "Default";
+/// Weather forecasts are per hour of the day.
+const FORECAST_HOURS: usize = 24;
+
+/// Block types are parsed from item assets before they reach the store, since their key is the file name.
+struct BlockTypeCodec;
+
+impl AssetCodec<BlockTypeAsset> for BlockTypeCodec {
+	fn decode(&self, _bytes: Bytes) -> StoreResult<BlockTypeAsset> {
+		Err(StoreError::Decode("BlockTypeAsset decode not supported, use BlockTypeAsset::from_item_json".into()))
+	}
+}
+
+/// Like block types, fluids are keyed by their file name and parsed before they reach the store.
+struct FluidTypeCodec;
+
+impl AssetCodec<FluidTypeAsset> for FluidTypeCodec {
+	fn decode(&self, _bytes: Bytes) -> StoreResult<FluidTypeAsset> {
+		Err(StoreError::Decode("FluidTypeAsset decode not supported, use FluidTypeAsset::from_json".into()))
+	}
+}
+
+/// Weathers are keyed by their file name and parsed before they reach the store.
+struct WeatherCodec;
+
+impl AssetCodec<WeatherAsset> for WeatherCodec {
+	fn decode(&self, _bytes: Bytes) -> StoreResult<WeatherAsset> {
+		Err(StoreError::Decode("WeatherAsset decode not supported, use WeatherAsset::from_json".into()))
+	}
+}
+
+/// Environments are keyed by their file name and parsed before they reach the store.
+struct EnvironmentCodec;
+
+impl AssetCodec<EnvironmentAsset> for EnvironmentCodec {
+	fn decode(&self, _bytes: Bytes) -> StoreResult<EnvironmentAsset> {
+		Err(StoreError::Decode("EnvironmentAsset decode not supported, use EnvironmentAsset::from_json".into()))
+	}
+}
+
+/// Recipes come out of item assets and are parsed before they reach the store.
+struct RecipeCodec;
+
+impl AssetCodec<RecipeAsset> for RecipeCodec {
+	fn decode(&self, _bytes: Bytes) -> StoreResult<RecipeAsset> {
+		Err(StoreError::Decode("RecipeAsset decode not supported, use CraftingItem::from_item_json".into()))
+	}
+}
+
+/// Name→id mapping that hands out every id once and keeps it, with the names of missing assets still reserving theirs.
+#[derive(Debug, Default)]
+struct IdMap {
+	/// Names by id. `None` marks ids that were freed by a corrupt mapping and can be reused.
+	names: Vec<Option<String>>,
+	ids: HashMap<String, i32>,
+}
+
+impl IdMap {
+	/// Starts from a saved mapping, with `reserved` taking the first ids no matter what was saved.
+	fn new(reserved: &[&str], saved: &BTreeMap<String, i32>) -> Self {
+		let mut map = Self::default();
+		for (id, name) in reserved.iter().enumerate() {
+			map.assign(name, id as i32);
+		}
+
+		// Ids are handed out densely, anything far beyond the number of names can only come from a corrupt file
+		let max_id = (saved.len() + reserved.len()) as i32;
+		let mut saved: Vec<(&String, i32)> = saved.iter().map(|(name, id)| (name, *id)).collect();
+		saved.sort_by_key(|(_, id)| *id);
+		for (name, id) in saved {
+			let free = (0..max_id).contains(&id) && map.names.get(id as usize).is_none_or(Option::is_none);
+			if free && !map.ids.contains_key(name) {
+				map.assign(name, id);
+			}
+		}
+		map
+	}
+
+	/// Gives every name that doesn't have an id yet the next free one. Names are sorted first so a fresh world always
+	/// ends up with the same ids for the same assets.
+	fn assign_new<'a>(&mut self, names: impl Iterator<Item = &'a String>) {
+		let mut new: Vec<&String> = names.filter(|name| !self.ids.contains_key(*name)).collect();
+		new.sort();
+		for name in new {
+			let id = self.names.iter().position(Option::is_none).unwrap_or(self.names.len());
+			self.assign(name, id as i32);
+		}
+	}
+
+	fn assign(&mut self, name: &str, id: i32) {
+		let index = id as usize;
+		if self.names.len() <= index {
+			self.names.resize(index + 1, None);
+		}
+		self.names[index] = Some(name.to_string());
+		self.ids.insert(name.to_string(), id);
+	}
+
+	fn max_id(&self) -> i32 {
+		self.names.len() as i32
+	}
+
+	fn id(&self, name: &str) -> Option<i32> {
+		self.ids.get(name).copied()
+	}
+
+	fn name(&self, id: i32) -> Option<&str> {
+		usize::try_from(id).ok().and_then(|id| self.names.get(id)).and_then(Option::as_deref)
+	}
+
+	fn to_saved(&self) -> BTreeMap<String, i32> {
+		self.ids.iter().map(|(name, id)| (name.clone(), *id)).collect()
+	}
+}
+
+/// Maps block type assets to the numeric ids used in chunks and packets.
+///
+/// Ids are handed out once and saved with the world (see [`BlockRegistry::ids`]), so a block keeps its id across restarts
+/// and asset pack changes. Blocks whose asset disappeared keep their id too and are sent to clients as unknown blocks,
+/// which keeps the chunks that still contain them intact until the asset comes back.
+pub struct BlockRegistry {
+	store: AssetStore<BlockTypeAsset, HashMapIndex<BlockTypeAsset>, Box<dyn AssetCodec<BlockTypeAsset>>>,
+	ids: IdMap,
+	/// Emission and opacity by id, so lighting doesn't have to go through the asset store.
+	lighting: Vec<([u8; 3], u8)>,
+}
+
+impl BlockRegistry {
+	/// Creates a registry that keeps the ids of a previously saved name→id mapping.
+	pub fn new(saved: &BTreeMap<String, i32>) -> Self {
+		let mut registry = Self {
+			store: AssetStore::new(BlockTypeCodec),
+			ids: IdMap::new(&[EMPTY_BLOCK, UNKNOWN_BLOCK], saved),
+			lighting: Vec::new(),
+		};
+		registry.update_lighting();
+		registry
+	}
+
+	/// Loads block types into the store and gives every block that doesn't have an id yet the next free one.
+	pub fn load(&mut self, assets: Vec<WithInput<BlockTypeAsset>>) -> StoreResult<LoadOutcome<String>> {
+		let outcome = self.store.load_assets(assets, LoadOptions::report())?;
+		self.ids.assign_new(self.store.iter().map(|(name, _)| name));
+		self.update_lighting();
+		Ok(outcome)
+	}
+
+	fn update_lighting(&mut self) {
+		self.lighting = (0..self.max_id())
+			.map(|id| match self.get_by_id(id) {
+				Some(asset) => (asset.emission(), asset.light_opacity()),
+				None if id == 0 => ([0; 3], 0),
+				None => ([0; 3], MAX_LIGHT),
+			})
+			.collect();
+	}
+
+	pub fn len(&self) -> usize {
+		self.ids.ids.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.ids.is_empty()
+	}
+
+	/// One past the highest assigned id.
+	pub fn max_id(&self) -> i32 {
+		self.ids.max_id()
+	}
+
+	pub fn id(&self, name: &str) -> Option<i32> {
+		self.ids.id(name)
+	}
+
+	pub fn name(&self, id: i32) -> Option<&str> {
+		self.ids.name(id)
+	}
+
+	pub fn get(&self, name: &str) -> Option<&BlockTypeAsset> {
+		self.store.get(&name.to_string())
+	}
+
+	pub fn get_by_id(&self, id: i32) -> Option<&BlockTypeAsset> {
+		self.name(id).and_then(|name| self.get(name))
+	}
+
+	/// Whether `id` belongs to a block, with or without an asset.
+	pub fn contains_id(&self, id: i32) -> bool {
+		self.name(id).is_some()
+	}
+
+	/// The name→id mapping to save with the world.
+	pub fn ids(&self) -> BTreeMap<String, i32> {
+		self.ids.to_saved()
+	}
+
+	/// Resolves the blocks the built-in generators use. Missing blocks become [`UNKNOWN_BLOCK`].
+	pub fn terrain_blocks(&self, fluids: &FluidRegistry) -> TerrainBlocks {
+		let block = |name: &str| BlockState::new(self.id(name).filter(|id| self.get_by_id(*id).is_some()).unwrap_or(1));
+		TerrainBlocks {
+			bedrock: block("Rock_Bedrock"),
+			stone: block("Rock_Stone"),
+			dirt: block("Soil_Dirt"),
+			grass: block("Soil_Grass"),
+			sand: block("Soil_Sand"),
+			water: fluids.source("Water"),
+		}
+	}
+
+	pub fn block_type(&self, id: i32) -> BlockType {
+		match self.get_by_id(id) {
+			Some(asset) => asset.to_protocol(),
+			None if id == 0 => empty_block_type(),
+			None => BlockType {
+				name: self.name(id).map(str::to_string),
+				..unknown_block_type()
+			},
+		}
+	}
+
+	/// The `UpdateBlockTypes` packet with every block type, as sent when a player joins.
+	pub fn update_block_types(&self) -> UpdateBlockTypes {
+		let block_types = (0..self.max_id()).filter(|id| self.contains_id(*id)).map(|id| (id, self.block_type(id))).collect();
+		UpdateBlockTypes {
+			update_type: UpdateType::Init,
+			max_id: self.max_id(),
+			update_block_textures: true,
+			update_model_textures: true,
+			update_models: true,
+			update_map_geometry: true,
+			block_types: Some(block_types),
+		}
+	}
+}
+
+impl BlockLighting for BlockRegistry {
+	fn emission(&self, block: BlockState) -> [u8; 3] {
+		usize::try_from(block.id).ok().and_then(|id| self.lighting.get(id)).map_or([0; 3], |(emission, _)| *emission)
+	}
+
+	fn opacity(&self, block: BlockState) -> u8 {
+		usize::try_from(block.id).ok().and_then(|id| self.lighting.get(id)).map_or(MAX_LIGHT, |(_, opacity)| *opacity)
+	}
+}
+
+/// How a fluid behaves, looked up by id on every simulation step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FluidRules {
+	pub max_level: u8,
+	/// Seconds between two steps.
+	pub flow_rate: f32,
+	pub can_demote: bool,
+	pub spreads: bool,
+}
+
+/// Maps fluid assets to the numeric ids used in chunks and packets, with the same id stability as the [`BlockRegistry`].
+pub struct FluidRegistry {
+	store: AssetStore<FluidTypeAsset, HashMapIndex<FluidTypeAsset>, Box<dyn AssetCodec<FluidTypeAsset>>>,
+	ids: IdMap,
+	rules: Vec<Option<FluidRules>>,
+	/// Block placed where the first fluid flows into the second one. `None` just stops the flow.
+	collisions: HashMap<(i32, i32), Option<BlockState>>,
+}
+
+impl FluidRegistry {
+	/// Creates a registry that keeps the ids of a previously saved name→id mapping.
+	pub fn new(saved: &BTreeMap<String, i32>) -> Self {
+		Self {
+			store: AssetStore::new(FluidTypeCodec),
+			ids: IdMap::new(&[EMPTY_FLUID], saved),
+			rules: Vec::new(),
+			collisions: HashMap::new(),
+		}
+	}
+
+	/// Loads fluids into the store, assigns ids to new ones and resolves the blocks their collisions place.
+	pub fn load(&mut self, assets: Vec<WithInput<FluidTypeAsset>>, blocks: &BlockRegistry) -> StoreResult<LoadOutcome<String>> {
+		let outcome = self.store.load_assets(assets, LoadOptions::report())?;
+		self.ids.assign_new(self.store.iter().map(|(name, _)| name));
+
+		self.rules = (0..self.max_id())
+			.map(|id| {
+				self.get_by_id(id).map(|fluid| FluidRules {
+					max_level: fluid.max_level(),
+					flow_rate: fluid.flow_rate(),
+					can_demote: fluid.definition.ticker.can_demote,
+					spreads: fluid.definition.ticker.spreads,
+				})
+			})
+			.collect();
+
+		self.collisions.clear();
+		for (name, fluid) in self.store.iter() {
+			let id = self.ids.id(name).expect("Every loaded fluid has an id");
+			for (other, collision) in &fluid.definition.ticker.collisions {
+				let Some(other) = self.ids.id(other) else {
+					continue;
+				};
+				let block = collision.block_to_place.as_deref().and_then(|block| blocks.id(block)).map(BlockState::new);
+				self.collisions.insert((id, other), block);
+			}
+		}
+		Ok(outcome)
+	}
+
+	pub fn len(&self) -> usize {
+		self.ids.ids.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.ids.is_empty()
+	}
+
+	/// One past the highest assigned id.
+	pub fn max_id(&self) -> i32 {
+		self.ids.max_id()
+	}
+
+	pub fn id(&self, name: &str) -> Option<i32> {
+		self.ids.id(name)
+	}
+
+	pub fn name(&self, id: i32) -> Option<&str> {
+		self.ids.name(id)
+	}
+
+	pub fn get(&self, name: &str) -> Option<&FluidTypeAsset> {
+		self.store.get(&name.to_string())
+	}
+
+	pub fn get_by_id(&self, id: i32) -> Option<&FluidTypeAsset> {
+		self.name(id).and_then(|name| self.get(name))
+	}
+
+	/// The rules of a loaded fluid. Fluids without an asset don't move.
+	pub fn rules(&self, id: i32) -> Option<FluidRules> {
+		usize::try_from(id).ok().and_then(|id| self.rules.get(id).copied().flatten())
+	}
+
+	/// What happens when fluid `from` flows into fluid `into`: `None` if they don't interact, otherwise the block that
+	/// replaces them, if any.
+	pub fn collision(&self, from: i32, into: i32) -> Option<Option<BlockState>> {
+		self.collisions.get(&(from, into)).or_else(|| self.collisions.get(&(into, from))).copied()
+	}
+
+	/// A full block of the named fluid, or no fluid if it isn't loaded.
+	pub fn source(&self, name: &str) -> FluidState {
+		match self.id(name).zip(self.get(name)) {
+			Some((id, fluid)) => FluidState::new(id, fluid.max_level()),
+			None => FluidState::EMPTY,
+		}
+	}
+
+	/// The name→id mapping to save with the world.
+	pub fn ids(&self) -> BTreeMap<String, i32> {
+		self.ids.to_saved()
+	}
+
+	/// The `UpdateFluids` packet with every fluid, as sent when a player joins.
+	pub fn update_fluids(&self) -> UpdateFluids {
+		// Fluids whose asset is missing still get a definition, the client can't handle ids it doesn't know
+		let fluids = (1..self.max_id())
+			.filter_map(|id| {
+				let fluid = match self.get_by_id(id) {
+					Some(fluid) => fluid.to_protocol(),
+					None => FluidTypeAsset::new(self.name(id)?, FluidDefinition::default()).to_protocol(),
+				};
+				Some((id, fluid))
+			})
+			.collect();
+		UpdateFluids {
+			update_type: UpdateType::Init,
+			max_id: self.max_id(),
+			fluids: Some(fluids),
+		}
+	}
+}
+
+/// Maps weather assets to the indices used in `UpdateWeather`.
+///
+/// Weathers never end up in chunks, so unlike blocks their ids aren't saved and are simply handed out by name.
+pub struct WeatherRegistry {
+	store: AssetStore<WeatherAsset, HashMapIndex<WeatherAsset>, Box<dyn AssetCodec<WeatherAsset>>>,
+	ids: IdMap,
+}
+
+impl Default for WeatherRegistry {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl WeatherRegistry {
+	pub fn new() -> Self {
+		Self {
+			store: AssetStore::new(WeatherCodec),
+			ids: IdMap::default(),
+		}
+	}
+
+	pub fn load(&mut self, assets: Vec<WithInput<WeatherAsset>>) -> StoreResult<LoadOutcome<String>> {
+		let outcome = self.store.load_assets(assets, LoadOptions::report())?;
+		self.ids.assign_new(self.store.iter().map(|(name, _)| name));
+		Ok(outcome)
+	}
+
+	pub fn len(&self) -> usize {
+		self.ids.ids.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.ids.is_empty()
+	}
+
+	/// One past the highest assigned id.
+	pub fn max_id(&self) -> i32 {
+		self.ids.max_id()
+	}
+
+	pub fn id(&self, name: &str) -> Option<i32> {
+		self.ids.id(name)
+	}
+
+	pub fn name(&self, id: i32) -> Option<&str> {
+		self.ids.name(id)
+	}
+
+	pub fn get(&self, name: &str) -> Option<&WeatherAsset> {
+		self.store.get(&name.to_string())
+	}
+
+	/// The `UpdateWeathers` packet with every weather, as sent when a player joins.
+	pub fn update_weathers(&self) -> UpdateWeathers {
+		let weathers = (0..self.max_id()).filter_map(|id| Some((id, self.get(self.name(id)?)?.to_protocol()))).collect();
+		UpdateWeathers {
+			update_type: UpdateType::Init,
+			max_id: self.max_id(),
+			weathers: Some(weathers),
+		}
+	}
+}
+
+/// Recipes by id, along with the resource types of the items, which recipe inputs can ask for instead of an item.
+pub struct RecipeRegistry {
+	store: AssetStore<RecipeAsset, HashMapIndex<RecipeAsset>, Box<dyn AssetCodec<RecipeAsset>>>,
+	/// Resource types by item id.
+	resource_types: HashMap<String, Vec<String>>,
+}
+
+impl Default for RecipeRegistry {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl RecipeRegistry {
+	pub fn new() -> Self {
+		Self {
+			store: AssetStore::new(RecipeCodec),
+			resource_types: HashMap::new(),
+		}
+	}
+
+	pub fn load(&mut self, assets: Vec<WithInput<RecipeAsset>>, resource_types: HashMap<String, Vec<String>>) -> StoreResult<LoadOutcome<String>> {
+		let outcome = self.store.load_assets(assets, LoadOptions::report())?;
+		self.resource_types.extend(resource_types);
+		Ok(outcome)
+	}
+
+	pub fn len(&self) -> usize {
+		self.store.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.store.len() == 0
+	}
+
+	pub fn get(&self, id: &str) -> Option<&RecipeAsset> {
+		self.store.get(&id.to_string())
+	}
+
+	/// Whether an item can be used for a recipe input.
+	pub fn matches(&self, material: &MaterialDefinition, item_id: &str) -> bool {
+		match (&material.item_id, &material.resource_type_id) {
+			(Some(id), _) => id == item_id,
+			(None, Some(resource_type)) => self.resource_types.get(item_id).is_some_and(|types| types.contains(resource_type)),
+			(None, None) => false,
+		}
+	}
+
+	/// The `UpdateRecipes` packet with every recipe, as sent when a player joins.
+	pub fn update_recipes(&self) -> UpdateRecipes {
+		UpdateRecipes {
+			update_type: UpdateType::Init,
+			recipes: Some(self.store.iter().map(|(id, recipe)| (id.clone(), recipe.to_protocol())).collect()),
+			removed_recipes: None,
+		}
+	}
+}
+
+/// Maps environment assets to the ids stored in the chunk columns, with the same id stability as the [`BlockRegistry`].
+///
+/// Id 0 is what columns have unless a generator assigns something else, and belongs to the configured default
+/// environment.
+pub struct EnvironmentRegistry {
+	store: AssetStore<EnvironmentAsset, HashMapIndex<EnvironmentAsset>, Box<dyn AssetCodec<EnvironmentAsset>>>,
+	ids: IdMap,
+	/// Weather ids and their weights for every hour of the day, by environment id. Empty without forecasts.
+	forecasts: Vec<Vec<Vec<(i32, u32)>>>,
+}
+
+impl EnvironmentRegistry {
+	/// Creates a registry that keeps the ids of a previously saved name→id mapping, with `default` as id 0.
+	pub fn new(saved: &BTreeMap<String, i32>, default: &str) -> Self {
+		Self {
+			store: AssetStore::new(EnvironmentCodec),
+			ids: IdMap::new(&[default], saved),
+			forecasts: Vec::new(),
+		}
+	}
+
+	/// Loads environments into the store, assigns ids to new ones and resolves the weathers of their forecasts.
+	pub fn load(&mut self, assets: Vec<WithInput<EnvironmentAsset>>, weathers: &WeatherRegistry) -> StoreResult<LoadOutcome<String>> {
+		let outcome = self.store.load_assets(assets, LoadOptions::report())?;
+		self.ids.assign_new(self.store.iter().map(|(name, _)| name));
+
+		self.forecasts = (0..self.max_id())
+			.map(|id| {
+				let Some(environment) = self.get_by_id(id) else {
+					return Vec::new();
+				};
+				let mut hours: Vec<Option<Vec<(i32, u32)>>> = vec![None; FORECAST_HOURS];
+				for (hour, forecasts) in &environment.definition.weather_forecasts {
+					let Some(hour) = hour.parse::<usize>().ok().filter(|hour| *hour < FORECAST_HOURS) else {
+						continue;
+					};
+					let weights = forecasts.iter().filter(|forecast| forecast.weight > 0).filter_map(|forecast| Some((weathers.id(&forecast.weather_id)?, forecast.weight)));
+					hours[hour] = Some(weights.collect());
+				}
+				// Hours without a forecast keep the one of the hour before
+				let Some(last) = hours.iter().rposition(Option::is_some) else {
+					return Vec::new();
+				};
+				let mut previous = hours[last].clone().unwrap_or_default();
+				hours
+					.into_iter()
+					.map(|forecast| {
+						if let Some(forecast) = forecast {
+							previous = forecast;
+						}
+						previous.clone()
+					})
+					.collect()
+			})
+			.collect();
+		Ok(outcome)
+	}
+
+	pub fn len(&self) -> usize {
+		self.ids.ids.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.ids.is_empty()
+	}
+
+	/// One past the highest assigned id.
+	pub fn max_id(&self) -> i32 {
+		self.ids.max_id()
+	}
+
+	pub fn id(&self, name: &str) -> Option<i32> {
+		self.ids.id(name)
+	}
+
+	pub fn name(&self, id: i32) -> Option<&str> {
+		self.ids.name(id)
+	}
+
+	pub fn get(&self, name: &str) -> Option<&EnvironmentAsset> {
+		self.store.get(&name.to_string())
+	}
+
+	pub fn get_by_id(&self, id: i32) -> Option<&EnvironmentAsset> {
+		self.name(id).and_then(|name| self.get(name))
+	}
+
+	/// The weathers that can start in the environment at the given hour of the day, with their weights.
+	pub fn forecast(&self, id: i32, hour: usize) -> &[(i32, u32)] {
+		usize::try_from(id).ok().and_then(|id| self.forecasts.get(id)).and_then(|hours| hours.get(hour % FORECAST_HOURS)).map_or(&[], Vec::as_slice)
+	}
+
+	/// Ids of the environments that have any weather at all.
+	pub fn with_weather(&self) -> impl Iterator<Item = i32> + '_ {
+		self.forecasts.iter().enumerate().filter(|(_, hours)| hours.iter().any(|forecast| !forecast.is_empty())).map(|(id, _)| id as i32)
+	}
+
+	/// The name→id mapping to save with the world.
+	pub fn ids(&self) -> BTreeMap<String, i32> {
+		self.ids.to_saved()
+	}
+
+	/// The `UpdateEnvironments` packet with every environment, as sent when a player joins.
+	pub fn update_environments(&self) -> UpdateEnvironments {
+		let environments = (0..self.max_id())
+			.filter_map(|id| {
+				let environment = match self.get_by_id(id) {
+					Some(environment) => environment.to_protocol(),
+					None => EnvironmentAsset::new(self.name(id)?, EnvironmentDefinition::default()).to_protocol(),
+				};
+				Some((id, environment))
+			})
+			.collect();
+		UpdateEnvironments {
+			update_type: UpdateType::Init,
+			max_id: self.max_id(),
+			rebuild_map_geometry: true,
+			environments: Some(environments),
+		}
+	}
+}
@@ -0,0 +1,135 @@
+use bytes::{
+	Buf,
+	Bytes,
+	BytesMut,
+};
+
+use crate::{
+	error::ChunkResult,
+	palette::Palette,
+};
+
+/// Width, depth and height of a section in blocks.
+pub const SECTION_SIZE: usize = 32;
+pub const SECTION_AREA: usize = SECTION_SIZE * SECTION_SIZE;
+pub const SECTION_VOLUME: usize = SECTION_AREA * SECTION_SIZE;
+
+/// Index of a block inside a section, from section-local coordinates.
+/// This is the same index used by `SetBlockCmd` and `SetFluidCmd`.
+#[inline]
+pub fn block_index(x: usize, y: usize, z: usize) -> usize {
+	((y & 31) << 10) | ((z & 31) << 5) | (x & 31)
+}
+
+/// Inverse of [`block_index`], returns `(x, y, z)`.
+#[inline]
+pub fn block_coords(index: usize) -> (usize, usize, usize) {
+	(index & 31, (index >> 10) & 31, (index >> 5) & 31)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BlockState {
+	pub id: i32,
+	pub filler: i16,
+	pub rotation: u8,
+}
+
+impl BlockState {
+	pub const AIR: BlockState = BlockState { id: 0, filler: 0, rotation: 0 };
+
+	pub fn new(id: i32) -> Self {
+		Self { id, ..Self::AIR }
+	}
+
+	pub fn is_air(&self) -> bool {
+		self.id == 0
+	}
+}
+
+/// A 32x32x32 cube of blocks. Block ids, filler and rotation are each stored in their own palette.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkSection {
+	blocks: Palette<i32>,
+	filler: Palette<i16>,
+	rotation: Palette<u8>,
+}
+
+impl ChunkSection {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a section made entirely out of `state`.
+	pub fn filled(state: BlockState) -> Self {
+		Self {
+			blocks: Palette::filled(state.id),
+			filler: Palette::filled(state.filler),
+			rotation: Palette::filled(state.rotation),
+		}
+	}
+
+	/// Whether every block in the section is air with no filler or rotation.
+	pub fn is_empty(&self) -> bool {
+		self.blocks.is_empty() && self.filler.is_empty() && self.rotation.is_empty()
+	}
+
+	pub fn blocks(&self) -> &Palette<i32> {
+		&self.blocks
+	}
+
+	pub fn filler(&self) -> &Palette<i16> {
+		&self.filler
+	}
+
+	pub fn rotation(&self) -> &Palette<u8> {
+		&self.rotation
+	}
+
+	#[inline]
+	pub fn get(&self, x: usize, y: usize, z: usize) -> BlockState {
+		self.get_index(block_index(x, y, z))
+	}
+
+	#[inline]
+	pub fn get_index(&self, index: usize) -> BlockState {
+		BlockState {
+			id: self.blocks.get(index),
+			filler: self.filler.get(index),
+			rotation: self.rotation.get(index),
+		}
+	}
+
+	/// Sets the block at the given section-local coordinates, returning the previous state.
+	pub fn set(&mut self, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+		self.set_index(block_index(x, y, z), state)
+	}
+
+	pub fn set_index(&mut self, index: usize, state: BlockState) -> BlockState {
+		BlockState {
+			id: self.blocks.set(index, state.id),
+			filler: self.filler.set(index, state.filler),
+			rotation: self.rotation.set(index, state.rotation),
+		}
+	}
+
+	/// Encodes the section in the layout used by `SetChunk.data`: the block, filler and rotation palettes, back to back.
+	pub fn encode(&self, buf: &mut BytesMut) {
+		self.blocks.encode(buf);
+		self.filler.encode(buf);
+		self.rotation.encode(buf);
+	}
+
+	pub fn decode(buf: &mut impl Buf) -> ChunkResult<Self> {
+		Ok(Self {
+			blocks: Palette::decode(buf)?,
+			filler: Palette::decode(buf)?,
+			rotation: Palette::decode(buf)?,
+		})
+	}
+
+	pub fn to_bytes(&self) -> Bytes {
+		let mut buf = BytesMut::new();
+		self.encode(&mut buf);
+		buf.freeze()
+	}
+}
@@ -0,0 +1,178 @@
+use std::{
+	sync::{
+		atomic::{
+			AtomicU32,
+			Ordering,
+		},
+		Arc,
+	},
+	time::Duration,
+};
+
+use parking_lot::{
+	Mutex,
+	RwLock,
+};
+use protocol::v2::{
+	setup::{
+		SetTimeDilation,
+		SetUpdateRate,
+	},
+	Packet,
+};
+use tokio::time::Instant;
+
+/// Slowest and fastest the game may run relative to real time.
+pub const TIME_DILATION_RANGE: (f32, f32) = (0.01, 4.0);
+/// Highest supported tick rate.
+pub const MAX_TICKS_PER_SECOND: u32 = 200;
+/// Ticks a long one is averaged over in [`TickStats::average`], roughly.
+const AVERAGE_WINDOW: f64 = 100.0;
+
+/// Something that runs once per server tick.
+pub trait Tickable: Send + Sync {
+	/// Advances by `elapsed` game time, which is the same for every tick no matter how long the last one took.
+	fn tick(&self, elapsed: Duration);
+
+	/// Called when the system is added and whenever the tick rate or time dilation change.
+	fn timing_changed(&self, _ticks_per_second: u32, _time_dilation: f32) {}
+}
+
+/// How the ticks went so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickStats {
+	pub ticks: u64,
+	/// How long the last tick took.
+	pub last: Duration,
+	/// Moving average of how long ticks take.
+	pub average: Duration,
+	/// Longest tick so far.
+	pub max: Duration,
+	/// Ticks that took longer than their slot.
+	pub overruns: u64,
+	/// Ticks left out to catch up after falling behind.
+	pub skipped: u64,
+}
+
+/// The game loop: runs every registered [`Tickable`] at a fixed rate.
+///
+/// Each tick advances the game by `1 / ticks_per_second`. Time dilation stretches the real time between ticks instead
+/// of the game time per tick, so everything slows down or speeds up together. When ticks take too long to keep up, the
+/// missed ones are skipped rather than run back to back.
+pub struct TickScheduler {
+	ticks_per_second: AtomicU32,
+	time_dilation: Mutex<f32>,
+	systems: RwLock<Vec<Arc<dyn Tickable>>>,
+	stats: Mutex<TickStats>,
+}
+
+impl TickScheduler {
+	pub fn new(ticks_per_second: u32) -> Self {
+		Self {
+			ticks_per_second: AtomicU32::new(ticks_per_second.clamp(1, MAX_TICKS_PER_SECOND)),
+			time_dilation: Mutex::new(1.0),
+			systems: RwLock::new(Vec::new()),
+			stats: Mutex::new(TickStats::default()),
+		}
+	}
+
+	pub fn add_system(&self, system: Arc<dyn Tickable>) {
+		system.timing_changed(self.ticks_per_second(), self.time_dilation());
+		self.systems.write().push(system);
+	}
+
+	pub fn ticks_per_second(&self) -> u32 {
+		self.ticks_per_second.load(Ordering::Relaxed)
+	}
+
+	pub fn set_ticks_per_second(&self, ticks_per_second: u32) {
+		self.ticks_per_second.store(ticks_per_second.clamp(1, MAX_TICKS_PER_SECOND), Ordering::Relaxed);
+		self.timing_changed();
+	}
+
+	pub fn time_dilation(&self) -> f32 {
+		*self.time_dilation.lock()
+	}
+
+	pub fn set_time_dilation(&self, time_dilation: f32) {
+		*self.time_dilation.lock() = time_dilation.clamp(TIME_DILATION_RANGE.0, TIME_DILATION_RANGE.1);
+		self.timing_changed();
+	}
+
+	fn timing_changed(&self) {
+		let (ticks_per_second, time_dilation) = (self.ticks_per_second(), self.time_dilation());
+		for system in self.systems.read().iter() {
+			system.timing_changed(ticks_per_second, time_dilation);
+		}
+	}
+
+	/// Game time that passes per tick.
+	pub fn tick_duration(&self) -> Duration {
+		Duration::from_secs(1) / self.ticks_per_second()
+	}
+
+	/// Real time between the starts of two ticks.
+	pub fn tick_interval(&self) -> Duration {
+		self.tick_duration().div_f64(self.time_dilation() as f64)
+	}
+
+	pub fn stats(&self) -> TickStats {
+		*self.stats.lock()
+	}
+
+	pub fn timing_packets(&self) -> Vec<Packet> {
+		timing_packets(self.ticks_per_second(), self.time_dilation())
+	}
+
+	/// Runs a single tick of every system and returns how long it took.
+	pub fn tick(&self) -> Duration {
+		let start = Instant::now();
+		let elapsed = self.tick_duration();
+		let systems = self.systems.read().clone();
+		for system in systems {
+			system.tick(elapsed);
+		}
+		let took = start.elapsed();
+
+		let mut stats = self.stats.lock();
+		stats.ticks += 1;
+		stats.last = took;
+		stats.max = stats.max.max(took);
+		let average = stats.average.as_secs_f64();
+		stats.average = Duration::from_secs_f64(average + (took.as_secs_f64() - average) / AVERAGE_WINDOW.min(stats.ticks as f64));
+		if took > self.tick_interval() {
+			stats.overruns += 1;
+		}
+		took
+	}
+
+	/// Ticks forever at the configured rate.
+	pub async fn run(&self) {
+		let mut next = Instant::now();
+		loop {
+			tokio::time::sleep_until(next).await;
+			self.tick();
+
+			let interval = self.tick_interval();
+			next += interval;
+			let now = Instant::now();
+			if next < now {
+				// Ticks missed entirely are skipped, and the next one runs right away
+				let behind = ((now - next).as_secs_f64() / interval.as_secs_f64()) as u64;
+				self.stats.lock().skipped += behind;
+				next = now;
+			}
+		}
+	}
+}
+
+/// `SetUpdateRate` and `SetTimeDilation`, which tell a client how fast to simulate.
+pub fn timing_packets(ticks_per_second: u32, time_dilation: f32) -> Vec<Packet> {
+	vec![
+		SetUpdateRate {
+			updates_per_second: ticks_per_second as i32,
+		}
+		.into(),
+		SetTimeDilation { time_dilation }.into(),
+	]
+}
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use crate::section::SECTION_SIZE;
+
+/// Converts the block distance sent in `ViewRadius` into a radius in chunks.
+pub fn view_radius_chunks(blocks: i32) -> i32 {
+	(blocks.max(0) + SECTION_SIZE as i32 - 1) / SECTION_SIZE as i32
+}
+
+/// Chunks that entered or left a player's view.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ViewUpdate {
+	/// Newly visible chunks, nearest first.
+	pub load: Vec<(i32, i32)>,
+	pub unload: Vec<(i32, i32)>,
+}
+
+impl ViewUpdate {
+	pub fn is_empty(&self) -> bool {
+		self.load.is_empty() && self.unload.is_empty()
+	}
+}
+
+/// Keeps track of which chunk columns are within a player's view.
+///
+/// The view is the circle of chunks whose center lies within `radius` chunks of the chunk the player stands in.
+#[derive(Debug, Default)]
+pub struct ChunkTracker {
+	center: Option<(i32, i32)>,
+	radius: i32,
+	visible: HashSet<(i32, i32)>,
+}
+
+impl ChunkTracker {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn center(&self) -> Option<(i32, i32)> {
+		self.center
+	}
+
+	pub fn radius(&self) -> i32 {
+		self.radius
+	}
+
+	pub fn contains(&self, x: i32, z: i32) -> bool {
+		self.visible.contains(&(x, z))
+	}
+
+	pub fn visible(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+		self.visible.iter().copied()
+	}
+
+	/// Moves the view to the chunk `center` with the given radius in chunks and returns what changed.
+	pub fn update(&mut self, center: (i32, i32), radius: i32) -> ViewUpdate {
+		let radius = radius.max(0);
+		if self.center == Some(center) && self.radius == radius {
+			return ViewUpdate::default();
+		}
+		self.center = Some(center);
+		self.radius = radius;
+
+		let in_range = |(x, z): (i32, i32)| distance_squared(center, (x, z)) <= radius * radius;
+		let unload: Vec<(i32, i32)> = self.visible.iter().copied().filter(|chunk| !in_range(*chunk)).collect();
+		for chunk in &unload {
+			self.visible.remove(chunk);
+		}
+
+		let mut load = Vec::new();
+		for z in center.1 - radius..=center.1 + radius {
+			for x in center.0 - radius..=center.0 + radius {
+				if in_range((x, z)) && self.visible.insert((x, z)) {
+					load.push((x, z));
+				}
+			}
+		}
+		load.sort_by_key(|chunk| distance_squared(center, *chunk));
+
+		ViewUpdate { load, unload }
+	}
+
+	/// Forgets every visible chunk and returns them, for when the player leaves the world.
+	pub fn clear(&mut self) -> Vec<(i32, i32)> {
+		self.center = None;
+		self.visible.drain().collect()
+	}
+}
+
+#[inline]
+fn distance_squared(a: (i32, i32), b: (i32, i32)) -> i32 {
+	let (dx, dz) = (a.0 - b.0, a.1 - b.1);
+	dx * dx + dz * dz
+}
@@ -0,0 +1,148 @@
+use std::{
+	collections::HashMap,
+	sync::Arc,
+};
+
+use protocol::v2::world::{
+	UpdateEditorWeatherOverride,
+	UpdateWeather,
+};
+use rand::Rng;
+
+use crate::registry::EnvironmentRegistry;
+
+/// Real seconds the client takes to blend from one weather into the next.
+pub const WEATHER_TRANSITION_SECONDS: f32 = 10.0;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+/// Weather index the editor shows when no weather is forced in every environment.
+pub const NO_WEATHER_OVERRIDE: i32 = -1;
+
+/// Picks the weather of every environment in a world.
+///
+/// Each game hour, every environment rolls a new weather from the forecast of that hour, weighted like the environment
+/// asset says. Forced weather takes precedence over whatever was rolled, either for a single environment or for all of
+/// them, until it's cleared again.
+pub struct WeatherScheduler {
+	environments: Arc<EnvironmentRegistry>,
+	/// Rolled weather by environment id.
+	rolled: HashMap<i32, i32>,
+	/// Forced weather by environment id.
+	forced: HashMap<i32, i32>,
+	/// Weather forced in every environment.
+	forced_all: Option<i32>,
+	/// Game hour the weather was last rolled for.
+	hour: Option<u64>,
+}
+
+impl WeatherScheduler {
+	pub fn new(environments: Arc<EnvironmentRegistry>) -> Self {
+		Self {
+			environments,
+			rolled: HashMap::new(),
+			forced: HashMap::new(),
+			forced_all: None,
+			hour: None,
+		}
+	}
+
+	pub fn environments(&self) -> &Arc<EnvironmentRegistry> {
+		&self.environments
+	}
+
+	/// The weather in an environment right now, if it has any.
+	pub fn weather(&self, environment: i32) -> Option<i32> {
+		self.forced.get(&environment).copied().or(self.forced_all).or_else(|| self.rolled.get(&environment).copied())
+	}
+
+	/// Weather forced in an environment, or in all of them for `None`.
+	pub fn forced(&self, environment: Option<i32>) -> Option<i32> {
+		match environment {
+			Some(environment) => self.forced.get(&environment).copied(),
+			None => self.forced_all,
+		}
+	}
+
+	/// The weather forced in every environment, for the weather override of the editor.
+	pub fn editor_override(&self) -> UpdateEditorWeatherOverride {
+		UpdateEditorWeatherOverride {
+			weather_index: self.forced_all.unwrap_or(NO_WEATHER_OVERRIDE),
+		}
+	}
+
+	/// The weather to send to a player entering the environment, without a transition.
+	pub fn update_weather(&self, environment: i32) -> Option<UpdateWeather> {
+		self.weather(environment).map(|weather_index| UpdateWeather {
+			weather_index,
+			transition_seconds: 0.0,
+		})
+	}
+
+	/// Rolls new weather once a new game hour started. Returns the environments whose weather changed.
+	pub fn tick(&mut self, game_time: f64) -> Vec<(i32, UpdateWeather)> {
+		let hour = (game_time / SECONDS_PER_HOUR) as u64;
+		if self.hour == Some(hour) {
+			return Vec::new();
+		}
+		self.hour = Some(hour);
+
+		let environments = self.environments.clone();
+		self.changes(|scheduler| {
+			let mut rng = rand::rng();
+			for environment in environments.with_weather() {
+				let forecast = environments.forecast(environment, (hour % 24) as usize);
+				let total: u32 = forecast.iter().map(|(_, weight)| weight).sum();
+				if total == 0 {
+					continue;
+				}
+				let mut roll = rng.random_range(0..total);
+				for (weather, weight) in forecast {
+					if roll < *weight {
+						scheduler.rolled.insert(environment, *weather);
+						break;
+					}
+					roll -= weight;
+				}
+			}
+		})
+	}
+
+	/// Forces a weather in an environment, or in all of them for `None`, until it's cleared with `None` as weather.
+	/// Clearing it for all environments clears the single ones too. Returns the environments whose weather changed.
+	pub fn force(&mut self, environment: Option<i32>, weather: Option<i32>) -> Vec<(i32, UpdateWeather)> {
+		self.changes(|scheduler| match (environment, weather) {
+			(Some(environment), Some(weather)) => {
+				scheduler.forced.insert(environment, weather);
+			}
+			(Some(environment), None) => {
+				scheduler.forced.remove(&environment);
+			}
+			(None, weather) => {
+				scheduler.forced_all = weather;
+				if weather.is_none() {
+					scheduler.forced.clear();
+				}
+			}
+		})
+	}
+
+	/// Runs `f` and returns an `UpdateWeather` for every environment whose weather is different afterwards.
+	fn changes(&mut self, f: impl FnOnce(&mut Self)) -> Vec<(i32, UpdateWeather)> {
+		let environments: Vec<i32> = (0..self.environments.max_id()).collect();
+		let before: Vec<Option<i32>> = environments.iter().map(|environment| self.weather(*environment)).collect();
+		f(self);
+		environments
+			.into_iter()
+			.zip(before)
+			.filter_map(|(environment, before)| {
+				let weather_index = self.weather(environment).filter(|weather| Some(*weather) != before)?;
+				Some((
+					environment,
+					UpdateWeather {
+						weather_index,
+						transition_seconds: WEATHER_TRANSITION_SECONDS,
+					},
+				))
+			})
+			.collect()
+	}
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use assets::{
+	Asset,
+	StoreError,
+	StoreResult,
+};
+use ordered_float::OrderedFloat;
+use protocol::v2::{
+	Cloud,
+	Color,
+	ColorAlpha,
+	NearFar,
+	Weather,
+	WorldEnvironment,
+};
+use serde::Deserialize;
+
+use crate::block_type::parse_rgb;
+
+/// A weather asset, keyed by its file name.
+#[derive(Debug, Clone)]
+pub struct WeatherAsset {
+	name: String,
+	pub definition: WeatherDefinition,
+}
+
+impl Asset for WeatherAsset {
+	type Key = String;
+
+	fn key(&self) -> &Self::Key {
+		&self.name
+	}
+}
+
+impl WeatherAsset {
+	pub fn new(name: impl Into<String>, definition: WeatherDefinition) -> Self {
+		Self { name: name.into(), definition }
+	}
+
+	pub fn from_json(name: impl Into<String>, bytes: &[u8]) -> StoreResult<Self> {
+		let definition = serde_json::from_slice(bytes).map_err(|e| StoreError::Decode(e.to_string()))?;
+		Ok(Self::new(name, definition))
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn to_protocol(&self) -> Weather {
+		let definition = &self.definition;
+		Weather {
+			fog: definition.fog.map(|fog| NearFar { near: fog.near, far: fog.far }),
+			fog_options: None,
+			id: Some(self.name.clone()),
+			tag_indexes: None,
+			stars: definition.stars.clone(),
+			moons: (!definition.moons.is_empty()).then(|| definition.moons.iter().filter_map(|moon| Some((moon.day, moon.texture.clone()?))).collect()),
+			clouds: (!definition.clouds.is_empty()).then(|| definition.clouds.iter().map(CloudDefinition::to_protocol).collect()),
+			sunlight_damping_multiplier: values(&definition.sunlight_damping_multipliers),
+			sunlight_colors: colors(&definition.sunlight_colors),
+			sky_top_colors: alpha_colors(&definition.sky_top_colors),
+			sky_bottom_colors: alpha_colors(&definition.sky_bottom_colors),
+			sky_sunset_colors: alpha_colors(&definition.sky_sunset_colors),
+			sun_colors: colors(&definition.sun_colors),
+			sun_scales: values(&definition.sun_scales),
+			sun_glow_colors: alpha_colors(&definition.sun_glow_colors),
+			moon_colors: alpha_colors(&definition.moon_colors),
+			moon_scales: values(&definition.moon_scales),
+			moon_glow_colors: alpha_colors(&definition.moon_glow_colors),
+			fog_colors: colors(&definition.fog_colors),
+			fog_height_falloffs: values(&definition.fog_height_falloffs),
+			fog_densities: values(&definition.fog_densities),
+			screen_effect: definition.screen_effect.clone(),
+			screen_effect_colors: alpha_colors(&definition.screen_effect_colors),
+			color_filters: colors(&definition.color_filters),
+			water_tints: colors(&definition.water_tints),
+			particle: None,
+		}
+	}
+}
+
+/// The parts of a weather definition the client renders. Keyframes are by hour of the day.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WeatherDefinition {
+	pub fog: Option<FogDefinition>,
+	pub stars: Option<String>,
+	pub moons: Vec<MoonDefinition>,
+	pub clouds: Vec<CloudDefinition>,
+	pub sunlight_damping_multipliers: Vec<ValueKeyframe>,
+	pub sunlight_colors: Vec<ColorKeyframe>,
+	pub sky_top_colors: Vec<ColorKeyframe>,
+	pub sky_bottom_colors: Vec<ColorKeyframe>,
+	pub sky_sunset_colors: Vec<ColorKeyframe>,
+	pub sun_colors: Vec<ColorKeyframe>,
+	pub sun_scales: Vec<ValueKeyframe>,
+	pub sun_glow_colors: Vec<ColorKeyframe>,
+	pub moon_colors: Vec<ColorKeyframe>,
+	pub moon_scales: Vec<ValueKeyframe>,
+	pub moon_glow_colors: Vec<ColorKeyframe>,
+	pub fog_colors: Vec<ColorKeyframe>,
+	pub fog_height_falloffs: Vec<ValueKeyframe>,
+	pub fog_densities: Vec<ValueKeyframe>,
+	pub screen_effect: Option<String>,
+	pub screen_effect_colors: Vec<ColorKeyframe>,
+	pub color_filters: Vec<ColorKeyframe>,
+	pub water_tints: Vec<ColorKeyframe>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct FogDefinition {
+	pub near: f32,
+	pub far: f32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct MoonDefinition {
+	/// Day of the moon cycle the texture is shown on.
+	pub day: i32,
+	pub texture: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct CloudDefinition {
+	pub texture: Option<String>,
+	pub colors: Vec<ColorKeyframe>,
+	pub speeds: Vec<ValueKeyframe>,
+}
+
+impl CloudDefinition {
+	fn to_protocol(&self) -> Cloud {
+		Cloud {
+			texture: self.texture.clone(),
+			speeds: values(&self.speeds),
+			colors: alpha_colors(&self.colors),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ColorKeyframe {
+	pub hour: f32,
+	/// `#RRGGBB`, `#RRGGBBAA` or `rgba(#RRGGBB, alpha)` with alpha in `0..=1`.
+	pub color: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ValueKeyframe {
+	pub hour: f32,
+	pub value: f32,
+}
+
+fn values(keyframes: &[ValueKeyframe]) -> Option<HashMap<OrderedFloat<f32>, f32>> {
+	(!keyframes.is_empty()).then(|| keyframes.iter().map(|keyframe| (OrderedFloat(keyframe.hour), keyframe.value)).collect())
+}
+
+fn colors(keyframes: &[ColorKeyframe]) -> Option<HashMap<OrderedFloat<f32>, Color>> {
+	let colors: HashMap<_, _> = keyframes
+		.iter()
+		.filter_map(|keyframe| parse_rgba(&keyframe.color).map(|[red, green, blue, _]| (OrderedFloat(keyframe.hour), Color { red, green, blue })))
+		.collect();
+	(!colors.is_empty()).then_some(colors)
+}
+
+fn alpha_colors(keyframes: &[ColorKeyframe]) -> Option<HashMap<OrderedFloat<f32>, ColorAlpha>> {
+	let colors: HashMap<_, _> = keyframes
+		.iter()
+		.filter_map(|keyframe| {
+			let [red, green, blue, alpha] = parse_rgba(&keyframe.color)?;
+			Some((OrderedFloat(keyframe.hour), ColorAlpha { alpha, red, green, blue }))
+		})
+		.collect();
+	(!colors.is_empty()).then_some(colors)
+}
+
+/// Parses `#RRGGBB`, `#RRGGBBAA` or `rgba(#RRGGBB, alpha)` into 8-bit channels, opaque unless it says otherwise.
+fn parse_rgba(color: &str) -> Option<[u8; 4]> {
+	if let Some(args) = color.trim().strip_prefix("rgba(").and_then(|rest| rest.strip_suffix(')')) {
+		let (rgb, alpha) = args.split_once(',')?;
+		let [red, green, blue] = parse_rgb(rgb.trim())?;
+		let alpha = alpha.trim().parse::<f32>().ok()?.clamp(0.0, 1.0);
+		return Some([red, green, blue, (alpha * 255.0).round() as u8]);
+	}
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	if hex.len() == 8 {
+		let value = u32::from_str_radix(hex, 16).ok()?;
+		return Some(value.to_be_bytes());
+	}
+	parse_rgb(color).map(|[red, green, blue]| [red, green, blue, 255])
+}
+
+/// An environment asset, keyed by its file name. Environments are assigned to block columns and decide which weather
+/// happens there.
+#[derive(Debug, Clone)]
+pub struct EnvironmentAsset {
+	name: String,
+	pub definition: EnvironmentDefinition,
+}
+
+impl Asset for EnvironmentAsset {
+	type Key = String;
+
+	fn key(&self) -> &Self::Key {
+		&self.name
+	}
+}
+
+impl EnvironmentAsset {
+	pub fn new(name: impl Into<String>, definition: EnvironmentDefinition) -> Self {
+		Self { name: name.into(), definition }
+	}
+
+	pub fn from_json(name: impl Into<String>, bytes: &[u8]) -> StoreResult<Self> {
+		let definition = serde_json::from_slice(bytes).map_err(|e| StoreError::Decode(e.to_string()))?;
+		Ok(Self::new(name, definition))
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn to_protocol(&self) -> WorldEnvironment {
+		WorldEnvironment {
+			water_tint: self.definition.water_tint.as_deref().and_then(parse_rgb).map(|[red, green, blue]| Color { red, green, blue }),
+			id: Some(self.name.clone()),
+			fluid_particles: None,
+			tag_indexes: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct EnvironmentDefinition {
+	pub water_tint: Option<String>,
+	/// Weathers that can start at each hour of the day, keyed by the hour.
+	pub weather_forecasts: HashMap<String, Vec<WeatherForecast>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WeatherForecast {
+	pub weather_id: String,
+	/// Chance of the weather relative to the others of the same hour.
+	pub weight: u32,
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use protocol::v2::{
+	window::{
+		OpenWindow,
+		UpdateWindow,
+	},
+	BenchType,
+	WindowType,
+};
+
+use crate::inventory::ItemContainer;
+
+/// Tier of a bench that was just placed.
+pub const BASE_BENCH_TIER: i32 = 1;
+
+/// A bench players craft at, as recipes name it in their bench requirements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CraftingBench {
+	pub bench_type: BenchType,
+	pub id: String,
+	pub tier: i32,
+}
+
+/// What a window shows and what its actions work on.
+#[derive(Debug, Clone)]
+pub enum WindowContent {
+	/// Items players move in and out of, like the contents of a chest.
+	Container(ItemContainer),
+	/// Recipes craftable at a bench, or from the pocket crafting window without one.
+	Crafting(Option<CraftingBench>),
+}
+
+/// A window opened for a player. Container windows are item sections of their own, addressed by the window id.
+#[derive(Debug, Clone)]
+pub struct Window {
+	id: i32,
+	window_type: WindowType,
+	content: WindowContent,
+}
+
+impl Window {
+	pub fn container(container: ItemContainer) -> Self {
+		Self::new(WindowType::Container, WindowContent::Container(container))
+	}
+
+	/// The crafting window every player can open, for recipes that don't need a bench.
+	pub fn pocket_crafting() -> Self {
+		Self::new(WindowType::PocketCrafting, WindowContent::Crafting(None))
+	}
+
+	pub fn at_bench(bench: CraftingBench) -> Self {
+		let window_type = match bench.bench_type {
+			BenchType::Crafting => WindowType::BasicCrafting,
+			BenchType::Processing => WindowType::Processing,
+			BenchType::DiagramCrafting => WindowType::DiagramCrafting,
+			BenchType::StructuralCrafting => WindowType::StructuralCrafting,
+		};
+		Self::new(window_type, WindowContent::Crafting(Some(bench)))
+	}
+
+	fn new(window_type: WindowType, content: WindowContent) -> Self {
+		Self { id: 0, window_type, content }
+	}
+
+	/// Assigned once the window is opened.
+	pub fn id(&self) -> i32 {
+		self.id
+	}
+
+	pub fn window_type(&self) -> WindowType {
+		self.window_type
+	}
+
+	pub fn content(&self) -> &WindowContent {
+		&self.content
+	}
+
+	pub fn items(&self) -> Option<&ItemContainer> {
+		match &self.content {
+			WindowContent::Container(container) => Some(container),
+			WindowContent::Crafting(_) => None,
+		}
+	}
+
+	pub fn items_mut(&mut self) -> Option<&mut ItemContainer> {
+		match &mut self.content {
+			WindowContent::Container(container) => Some(container),
+			WindowContent::Crafting(_) => None,
+		}
+	}
+
+	/// The bench a crafting window belongs to, `None` for pocket crafting and other windows.
+	pub fn bench(&self) -> Option<&CraftingBench> {
+		match &self.content {
+			WindowContent::Crafting(bench) => bench.as_ref(),
+			WindowContent::Container(_) => None,
+		}
+	}
+
+	pub fn is_crafting(&self) -> bool {
+		matches!(self.content, WindowContent::Crafting(_))
+	}
+
+	fn window_data(&self) -> Option<String> {
+		self.bench().map(|bench| serde_json::json!({ "id": bench.id, "tierLevel": bench.tier }).to_string())
+	}
+
+	pub fn open_packet(&self) -> OpenWindow {
+		OpenWindow {
+			id: self.id,
+			window_type: self.window_type,
+			window_data: self.window_data(),
+			inventory: self.items().map(ItemContainer::to_section),
+			extra_resources: None,
+		}
+	}
+
+	pub fn update_packet(&self) -> UpdateWindow {
+		UpdateWindow {
+			id: self.id,
+			window_data: self.window_data(),
+			inventory: self.items().map(ItemContainer::to_section),
+			extra_resources: None,
+		}
+	}
+}
+
+/// The windows a player has open, by id.
+#[derive(Debug, Clone, Default)]
+pub struct WindowManager {
+	next_id: i32,
+	windows: BTreeMap<i32, Window>,
+}
+
+impl WindowManager {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Gives a window the next id and keeps it open until it's closed.
+	pub fn open(&mut self, mut window: Window) -> &Window {
+		self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+		window.id = self.next_id;
+		self.windows.entry(window.id).insert_entry(window).into_mut()
+	}
+
+	/// Closes a window, handing back what it held.
+	pub fn close(&mut self, id: i32) -> Option<Window> {
+		self.windows.remove(&id)
+	}
+
+	pub fn close_all(&mut self) -> Vec<Window> {
+		std::mem::take(&mut self.windows).into_values().collect()
+	}
+
+	pub fn get(&self, id: i32) -> Option<&Window> {
+		self.windows.get(&id)
+	}
+
+	pub fn get_mut(&mut self, id: i32) -> Option<&mut Window> {
+		self.windows.get_mut(&id)
+	}
+
+	/// The open container window items are sent to by a quick move, the newest one if there are several.
+	pub fn container_id(&self) -> Option<i32> {
+		self.windows.values().rev().find(|window| window.items().is_some()).map(Window::id)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.windows.is_empty()
+	}
+}
@@ -0,0 +1,762 @@
+use std::{
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	sync::{
+		atomic::{
+			AtomicBool,
+			AtomicI32,
+			Ordering,
+		},
+		Arc,
+	},
+	time::Duration,
+};
+
+use parking_lot::{
+	Mutex,
+	RwLock,
+	RwLockWriteGuard,
+};
+use protocol::v2::{
+	world::{
+		ServerSetBlock,
+		ServerSetBlocks,
+		SetBlockCmd,
+		SetPaused,
+	},
+	Packet,
+	Vector3i,
+};
+use tokio::sync::{
+	broadcast,
+	OnceCell,
+};
+use uuid::Uuid;
+
+use crate::{
+	block_damage::BlockDamageTracker,
+	chunk::{
+		chunk_coord,
+		local_coord,
+		Chunk,
+		CHUNK_HEIGHT,
+	},
+	clock::WorldClock,
+	entity::{
+		EntityState,
+		EntityTracker,
+	},
+	error::{
+		GeneratorResult,
+		RegionResult,
+	},
+	fluid::FluidState,
+	fluid_sim::{
+		FluidAccess,
+		FluidSimulator,
+	},
+	generator::GeneratorPool,
+	light::{
+		BlockLighting,
+		LightArea,
+		LightEngine,
+	},
+	region::RegionStorage,
+	registry::FluidRegistry,
+	section::{
+		block_index,
+		BlockState,
+		SECTION_SIZE,
+	},
+	tick::{
+		timing_packets,
+		Tickable,
+	},
+	weather::WeatherScheduler,
+};
+
+/// Tick rate of the server unless configured otherwise.
+pub const DEFAULT_TICKS_PER_SECOND: u32 = 30;
+/// How many world updates a lagging subscriber can fall behind before it misses some.
+const UPDATE_CHANNEL_CAPACITY: usize = 4096;
+
+pub type SharedChunk = Arc<RwLock<Chunk>>;
+pub type WorldLightEngine = LightEngine<Arc<dyn BlockLighting>>;
+type ChunkCell = Arc<OnceCell<SharedChunk>>;
+
+#[derive(Debug)]
+struct ChunkSlot {
+	chunk: ChunkCell,
+	/// Number of players that currently have the chunk in view.
+	viewers: usize,
+}
+
+/// Which players in a world a [`WorldUpdate`] is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateTarget {
+	All,
+	/// Players that have the chunk column at `(x, z)` loaded.
+	Chunk(i32, i32),
+	/// Players standing in the environment with this id.
+	Environment(i32),
+	/// A single player.
+	Player(Uuid),
+}
+
+/// A packet for the players in a world.
+#[derive(Debug, Clone)]
+pub struct WorldUpdate {
+	pub target: UpdateTarget,
+	pub packet: Packet,
+}
+
+/// A world: its loaded chunks, where missing chunks come from and where they go when nobody needs them anymore.
+///
+/// Chunks are loaded from the region storage if they were saved before, and generated otherwise. Both, and the initial
+/// light calculation, happen on the generator pool.
+pub struct World {
+	name: String,
+	uuid: Uuid,
+	generator: Arc<GeneratorPool>,
+	storage: Arc<RegionStorage>,
+	light: Arc<WorldLightEngine>,
+	chunks: Mutex<HashMap<(i32, i32), ChunkSlot>>,
+	/// Chunks that were unloaded but are still being saved. Always locked after `chunks`.
+	unloading: Mutex<HashMap<(i32, i32), SharedChunk>>,
+	/// Chunks that changed, or were generated, since they were last saved.
+	unsaved: Mutex<HashSet<(i32, i32)>>,
+	fluids: Mutex<FluidSimulator>,
+	clock: Mutex<WorldClock>,
+	weather: Mutex<WeatherScheduler>,
+	block_damage: Mutex<BlockDamageTracker>,
+	entities: Mutex<EntityTracker>,
+	players: Mutex<HashSet<Uuid>>,
+	/// Stops the simulation while set, the whole world stands still.
+	paused: AtomicBool,
+	next_entity_id: AtomicI32,
+	updates: broadcast::Sender<WorldUpdate>,
+}
+
+impl World {
+	#[allow(clippy::too_many_arguments)]
+	pub fn new(
+		name: impl Into<String>,
+		uuid: Uuid,
+		generator: Arc<GeneratorPool>,
+		storage: Arc<RegionStorage>,
+		lighting: Arc<dyn BlockLighting>,
+		fluids: Arc<FluidRegistry>,
+		clock: WorldClock,
+		weather: WeatherScheduler,
+	) -> Self {
+		Self {
+			name: name.into(),
+			uuid,
+			generator,
+			storage,
+			light: Arc::new(LightEngine::new(lighting)),
+			chunks: Mutex::new(HashMap::new()),
+			unloading: Mutex::new(HashMap::new()),
+			unsaved: Mutex::new(HashSet::new()),
+			fluids: Mutex::new(FluidSimulator::new(fluids, DEFAULT_TICKS_PER_SECOND)),
+			clock: Mutex::new(clock),
+			weather: Mutex::new(weather),
+			block_damage: Mutex::new(BlockDamageTracker::new()),
+			entities: Mutex::new(EntityTracker::new()),
+			players: Mutex::new(HashSet::new()),
+			paused: AtomicBool::new(false),
+			next_entity_id: AtomicI32::new(1),
+			updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn uuid(&self) -> Uuid {
+		self.uuid
+	}
+
+	pub fn generator(&self) -> &Arc<GeneratorPool> {
+		&self.generator
+	}
+
+	pub fn storage(&self) -> &Arc<RegionStorage> {
+		&self.storage
+	}
+
+	pub fn light_engine(&self) -> &Arc<WorldLightEngine> {
+		&self.light
+	}
+
+	pub fn fluids(&self) -> &Mutex<FluidSimulator> {
+		&self.fluids
+	}
+
+	pub fn clock(&self) -> &Mutex<WorldClock> {
+		&self.clock
+	}
+
+	/// Changes the clock and tells every player about its new time and settings.
+	pub fn update_clock<R>(&self, f: impl FnOnce(&mut WorldClock) -> R) -> R {
+		let (result, packets) = {
+			let mut clock = self.clock.lock();
+			let result = f(&mut clock);
+			(result, clock.time_packets())
+		};
+		for packet in packets {
+			self.broadcast_all(packet);
+		}
+		result
+	}
+
+	pub fn weather(&self) -> &Mutex<WeatherScheduler> {
+		&self.weather
+	}
+
+	/// Forces a weather in an environment, or in all of them for `None`, and tells the players where it changed.
+	/// `None` as weather goes back to the forecast. Weather forced everywhere also shows up as the editor override.
+	pub fn force_weather(&self, environment: Option<i32>, weather: Option<i32>) {
+		let (changes, editor_override) = {
+			let mut scheduler = self.weather.lock();
+			let changes = scheduler.force(environment, weather);
+			(changes, environment.is_none().then(|| scheduler.editor_override()))
+		};
+		for (environment, packet) in changes {
+			self.broadcast_environment(environment, packet);
+		}
+		if let Some(packet) = editor_override {
+			self.broadcast_all(packet);
+		}
+	}
+
+	/// The environment of the block column at world coordinates `(x, z)`, if its chunk is loaded.
+	pub fn environment_at(&self, x: i32, z: i32) -> Option<i32> {
+		self.get_chunk(chunk_coord(x), chunk_coord(z)).map(|chunk| chunk.read().maps().environment(local_coord(x), local_coord(z)))
+	}
+
+	pub fn entities(&self) -> &Mutex<EntityTracker> {
+		&self.entities
+	}
+
+	/// Receives every [`WorldUpdate`] sent from now on. Connections forward the ones for chunks their player has loaded.
+	pub fn subscribe(&self) -> broadcast::Receiver<WorldUpdate> {
+		self.updates.subscribe()
+	}
+
+	/// Sends a packet to every player that has the chunk column at `(x, z)` loaded.
+	pub fn broadcast(&self, x: i32, z: i32, packet: impl Into<Packet>) {
+		self.send(UpdateTarget::Chunk(x, z), packet);
+	}
+
+	/// Sends a packet to every player in the world.
+	pub fn broadcast_all(&self, packet: impl Into<Packet>) {
+		self.send(UpdateTarget::All, packet);
+	}
+
+	/// Sends a packet to the players in the given environment.
+	pub fn broadcast_environment(&self, environment: i32, packet: impl Into<Packet>) {
+		self.send(UpdateTarget::Environment(environment), packet);
+	}
+
+	/// Sends a packet to one player in the world.
+	pub fn send_to(&self, uuid: Uuid, packet: impl Into<Packet>) {
+		self.send(UpdateTarget::Player(uuid), packet);
+	}
+
+	fn send(&self, target: UpdateTarget, packet: impl Into<Packet>) {
+		// Nobody listening is fine
+		let _ = self.updates.send(WorldUpdate { target, packet: packet.into() });
+	}
+
+	pub fn add_player(&self, uuid: Uuid) {
+		self.players.lock().insert(uuid);
+	}
+
+	/// Removes a player from the world. A world its last player paused runs again once they're gone.
+	pub fn remove_player(&self, uuid: Uuid) {
+		let (packets, empty) = {
+			let mut players = self.players.lock();
+			players.remove(&uuid);
+			(self.clock.lock().remove_player(uuid, &players), players.is_empty())
+		};
+		for packet in packets {
+			self.broadcast_all(packet);
+		}
+		if empty {
+			self.set_paused(false);
+		}
+	}
+
+	/// Hands out a network id for an entity in this world, players included.
+	pub fn next_entity_id(&self) -> i32 {
+		self.next_entity_id.fetch_add(1, Ordering::Relaxed)
+	}
+
+	/// Adds an entity to the world under a new network id, players see it from the next tick on.
+	pub fn spawn_entity(&self, state: EntityState) -> i32 {
+		let network_id = self.next_entity_id();
+		self.entities.lock().spawn(network_id, state);
+		network_id
+	}
+
+	pub fn player_count(&self) -> usize {
+		self.players.lock().len()
+	}
+
+	/// Puts a player to sleep or wakes them up, which skips the night once enough players sleep.
+	pub fn set_sleeping(&self, uuid: Uuid, sleeping: bool) {
+		let packets = {
+			let players = self.players.lock();
+			self.clock.lock().set_sleeping(uuid, sleeping, &players)
+		};
+		for packet in packets {
+			self.broadcast_all(packet);
+		}
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused.load(Ordering::Relaxed)
+	}
+
+	/// Pauses or resumes everything that happens in the world, including its clock.
+	pub fn set_paused(&self, paused: bool) {
+		if self.paused.swap(paused, Ordering::Relaxed) != paused {
+			self.broadcast_all(SetPaused { paused });
+			let packets = {
+				let mut clock = self.clock.lock();
+				clock.set_paused(paused);
+				clock.time_packets()
+			};
+			for packet in packets {
+				self.broadcast_all(packet);
+			}
+		}
+	}
+
+	/// Advances the world by `elapsed` real time, unless it's paused. Players keep seeing the entities around them
+	/// either way.
+	pub fn tick(&self, elapsed: Duration) {
+		let updates = self.entities.lock().tick();
+		for (uuid, packet) in updates {
+			self.send_to(uuid, packet);
+		}
+		if self.is_paused() {
+			return;
+		}
+		let (packets, game_time) = {
+			let mut clock = self.clock.lock();
+			(clock.tick(elapsed), clock.game_time())
+		};
+		for packet in packets {
+			self.broadcast_all(packet);
+		}
+		let changes = self.weather.lock().tick(game_time);
+		for (environment, packet) in changes {
+			self.broadcast_environment(environment, packet);
+		}
+		let healed = self.block_damage.lock().tick(elapsed);
+		for packet in healed {
+			let Some(Vector3i { x, z, .. }) = packet.block_position else {
+				continue;
+			};
+			self.broadcast(chunk_coord(x), chunk_coord(z), packet);
+		}
+		self.tick_fluids();
+	}
+
+	/// Returns the block at a world position, if its chunk is loaded.
+	pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+		LoadedChunks::new(self).block(x, y, z)
+	}
+
+	/// Sets blocks in loaded chunks and sends the changes to the players that have them loaded. The light around them
+	/// is updated, their damage is forgotten and nearby fluids react. Returns the blocks that actually changed.
+	pub fn set_blocks(&self, blocks: &[BlockChange]) -> Vec<BlockChange> {
+		let (changed, light) = {
+			let mut access = LoadedChunks::new(self);
+			let changed = access.set_blocks(blocks);
+			(changed, access.light_packets())
+		};
+
+		for &((x, y, z), _) in &changed {
+			let cleared = self.block_damage.lock().clear(x, y, z);
+			if let Some(packet) = cleared {
+				self.broadcast(chunk_coord(x), chunk_coord(z), packet);
+			}
+			self.schedule_fluid_update(x, y, z);
+		}
+		for (x, z, packet) in block_packets(&changed).into_iter().chain(light) {
+			self.broadcast(x, z, packet);
+		}
+		changed
+	}
+
+	/// Damages a block by a share of its health and shows the cracks to nearby players. Once it has no health left it
+	/// breaks into air. Returns whether it broke.
+	pub fn damage_block(&self, x: i32, y: i32, z: i32, amount: f32) -> bool {
+		let (packet, broken) = self.block_damage.lock().hit(x, y, z, amount);
+		self.broadcast(chunk_coord(x), chunk_coord(z), packet);
+		if broken {
+			self.set_blocks(&[((x, y, z), BlockState::AIR)]);
+		}
+		broken
+	}
+
+	/// Lets the fluids around a block react to it changing. Must not be called while holding a chunk lock.
+	pub fn schedule_fluid_update(&self, x: i32, y: i32, z: i32) {
+		self.fluids.lock().schedule_around(x, y, z, 1);
+	}
+
+	/// Runs a tick of the fluid simulation and sends the changes to the players that can see them.
+	pub fn tick_fluids(&self) {
+		let (changes, light) = {
+			let mut access = LoadedChunks::new(self);
+			let changes = self.fluids.lock().tick(&mut access);
+			(changes, access.light_packets())
+		};
+		for (x, z, packet) in changes.into_packets().into_iter().chain(light) {
+			self.broadcast(x, z, packet);
+		}
+	}
+
+	/// Returns the chunk at chunk coordinates `(x, z)` if it's loaded.
+	pub fn get_chunk(&self, x: i32, z: i32) -> Option<SharedChunk> {
+		self.chunks.lock().get(&(x, z)).and_then(|slot| slot.chunk.get().cloned())
+	}
+
+	/// Returns the chunk at chunk coordinates `(x, z)`, loading or generating it first if needed.
+	/// Concurrent calls for the same chunk share a single load.
+	pub async fn load_chunk(&self, x: i32, z: i32) -> RegionResult<SharedChunk> {
+		let cell = self.slot(&mut self.chunks.lock(), (x, z)).chunk.clone();
+		let loaded = AtomicBool::new(false);
+		let result = cell
+			.get_or_try_init(|| async {
+				let chunk = match self.storage.load_chunk(x, z).await? {
+					Some(chunk) => {
+						let light = self.light.clone();
+						self.generator
+							.run(move || {
+								let mut chunk = chunk;
+								light.light_chunk(&mut chunk);
+								chunk
+							})
+							.await?
+					}
+					None => {
+						let chunk = self.generate_chunk(x, z).await?;
+						self.unsaved.lock().insert((x, z));
+						chunk
+					}
+				};
+				loaded.store(true, Ordering::Relaxed);
+				RegionResult::Ok(Arc::new(RwLock::new(chunk)))
+			})
+			.await;
+		match result {
+			Ok(chunk) => {
+				if loaded.load(Ordering::Relaxed) {
+					self.stitch_light(x, z).await?;
+				}
+				Ok(chunk.clone())
+			}
+			Err(e) => {
+				// Nobody waits for the chunk anymore, the next request tries again with a new slot
+				let mut chunks = self.chunks.lock();
+				if chunks.get(&(x, z)).is_some_and(|slot| slot.viewers == 0 && Arc::ptr_eq(&slot.chunk, &cell) && !cell.initialized()) {
+					chunks.remove(&(x, z));
+				}
+				Err(e)
+			}
+		}
+	}
+
+	/// Spreads light between a freshly loaded chunk and the loaded chunks around it, and sends the sections whose light
+	/// changed to the players that have them loaded.
+	async fn stitch_light(&self, x: i32, z: i32) -> RegionResult<()> {
+		let columns: Vec<((i32, i32), SharedChunk)> = light_area_keys(x, z).filter_map(|key| Some((key, self.get_chunk(key.0, key.1)?))).collect();
+		let light = self.light.clone();
+		let packets = self
+			.generator
+			.run(move || {
+				let mut guards = lock_columns(&columns);
+				with_light_area(&mut guards, (x, z), |area| light.stitch(area));
+				guards.iter_mut().flat_map(|((x, z), chunk)| light_packets(*x, *z, chunk)).collect::<Vec<_>>()
+			})
+			.await?;
+		for (x, z, packet) in packets {
+			self.broadcast(x, z, packet);
+		}
+		Ok(())
+	}
+
+	/// Returns the slot of a chunk, creating it if needed. A chunk that's still being saved after it was unloaded goes
+	/// right back into its new slot, so it's never read from disk before it's saved.
+	fn slot<'a>(&self, chunks: &'a mut HashMap<(i32, i32), ChunkSlot>, key: (i32, i32)) -> &'a mut ChunkSlot {
+		chunks.entry(key).or_insert_with(|| ChunkSlot {
+			chunk: Arc::new(OnceCell::new_with(self.unloading.lock().get(&key).cloned())),
+			viewers: 0,
+		})
+	}
+
+	/// Generates and lights a chunk without touching the loaded chunks or the storage.
+	pub async fn generate_chunk(&self, x: i32, z: i32) -> GeneratorResult<Chunk> {
+		let generator = self.generator.generator().clone();
+		let seed = self.generator.seed();
+		let light = self.light.clone();
+		self.generator
+			.run(move || {
+				let mut chunk = Chunk::new(x, z);
+				generator.generate(seed, &mut chunk);
+				light.light_chunk(&mut chunk);
+				chunk
+			})
+			.await
+	}
+
+	/// Marks the chunk as viewed by one more player, which keeps it loaded.
+	pub fn add_viewer(&self, x: i32, z: i32) {
+		self.slot(&mut self.chunks.lock(), (x, z)).viewers += 1;
+	}
+
+	pub fn remove_viewer(&self, x: i32, z: i32) {
+		if let Some(slot) = self.chunks.lock().get_mut(&(x, z)) {
+			slot.viewers = slot.viewers.saturating_sub(1);
+		}
+	}
+
+	pub fn loaded_chunks(&self) -> usize {
+		self.chunks.lock().values().filter(|slot| slot.chunk.initialized()).count()
+	}
+
+	/// Drops every loaded chunk that no player has in view and saves it. Returns the number of unloaded chunks.
+	///
+	/// The chunks are dropped before they're saved, so no edit can sneak in after the save. Until it's done, a chunk that's
+	/// needed again is taken back as it is instead of being read from disk, and if the save fails they're all put back.
+	pub async fn unload_idle(&self) -> RegionResult<usize> {
+		let idle: Vec<((i32, i32), SharedChunk)> = {
+			let mut chunks = self.chunks.lock();
+			let keys: Vec<(i32, i32)> = chunks.iter().filter(|(_, slot)| slot.viewers == 0 && slot.chunk.initialized()).map(|(key, _)| *key).collect();
+			let mut unloading = self.unloading.lock();
+			keys.into_iter()
+				.filter_map(|key| {
+					let chunk = chunks.remove(&key)?.chunk.get()?.clone();
+					unloading.insert(key, chunk.clone());
+					Some((key, chunk))
+				})
+				.collect()
+		};
+		if idle.is_empty() {
+			return Ok(0);
+		}
+		let snapshots = idle.iter().map(|(_, chunk)| chunk.read().clone()).collect();
+		let result = self.storage.save_chunks(snapshots).await;
+
+		let mut chunks = self.chunks.lock();
+		let mut unloading = self.unloading.lock();
+		for (key, chunk) in &idle {
+			if result.is_err() {
+				// A chunk that was needed again already has its slot back
+				chunks.entry(*key).or_insert_with(|| ChunkSlot {
+					chunk: Arc::new(OnceCell::new_with(Some(chunk.clone()))),
+					viewers: 0,
+				});
+			}
+			unloading.remove(key);
+		}
+		result.map(|()| idle.len())
+	}
+
+	/// Saves the loaded chunks that changed since they were last saved. Returns the number of saved chunks.
+	///
+	/// Chunks that change again while they're being saved stay unsaved, and if saving fails they all do.
+	pub async fn save_unsaved(&self) -> RegionResult<usize> {
+		let keys: Vec<(i32, i32)> = self.unsaved.lock().drain().collect();
+		let snapshots: Vec<Chunk> = keys.iter().filter_map(|(x, z)| self.get_chunk(*x, *z)).map(|chunk| chunk.read().clone()).collect();
+		if snapshots.is_empty() {
+			return Ok(0);
+		}
+		let count = snapshots.len();
+		let result = self.storage.save_chunks(snapshots).await;
+		if result.is_err() {
+			self.unsaved.lock().extend(keys);
+		}
+		result.map(|()| count)
+	}
+
+	/// Saves every loaded chunk, and the ones still being unloaded, without unloading anything.
+	pub async fn save_all(&self) -> RegionResult<()> {
+		let loaded: Vec<SharedChunk> = {
+			let chunks = self.chunks.lock();
+			let unloading = self.unloading.lock();
+			chunks.values().filter_map(|slot| slot.chunk.get().cloned()).chain(unloading.values().cloned()).collect()
+		};
+		self.storage.save_chunks(loaded.iter().map(|chunk| chunk.read().clone()).collect()).await
+	}
+}
+
+impl Tickable for World {
+	fn tick(&self, elapsed: Duration) {
+		World::tick(self, elapsed);
+	}
+
+	fn timing_changed(&self, ticks_per_second: u32, time_dilation: f32) {
+		self.fluids.lock().set_ticks_per_second(ticks_per_second);
+		for packet in timing_packets(ticks_per_second, time_dilation) {
+			self.broadcast_all(packet);
+		}
+	}
+}
+
+/// A block and the state it's set to.
+pub type BlockChange = ((i32, i32, i32), BlockState);
+
+/// Batches block changes into a `ServerSetBlock` for a single block in a section or a `ServerSetBlocks` for more, each
+/// with the chunk column it belongs to.
+fn block_packets(changed: &[BlockChange]) -> Vec<(i32, i32, Packet)> {
+	let mut sections: HashMap<(i32, i32, i32), Vec<BlockChange>> = HashMap::new();
+	for &((x, y, z), state) in changed {
+		sections.entry((chunk_coord(x), y / SECTION_SIZE as i32, chunk_coord(z))).or_default().push(((x, y, z), state));
+	}
+	sections
+		.into_iter()
+		.map(|((cx, sy, cz), blocks)| {
+			let packet = match blocks.as_slice() {
+				[((x, y, z), state)] => ServerSetBlock {
+					pos: Vector3i { x: *x, y: *y, z: *z },
+					block_id: state.id,
+					filler: state.filler,
+					rotation: state.rotation,
+				}
+				.into(),
+				_ => ServerSetBlocks {
+					pos: Vector3i { x: cx, y: sy, z: cz },
+					cmds: blocks
+						.iter()
+						.map(|((x, y, z), state)| SetBlockCmd {
+							index: block_index(local_coord(*x), *y as usize, local_coord(*z)) as i16,
+							block_id: state.id,
+							filler: state.filler,
+							rotation: state.rotation,
+						})
+						.collect(),
+				}
+				.into(),
+			};
+			(cx, cz, packet)
+		})
+		.collect()
+}
+
+/// The chunk column at `(x, z)` and the ones around it.
+fn light_area_keys(x: i32, z: i32) -> impl Iterator<Item = (i32, i32)> {
+	(-1..=1).flat_map(move |dz| (-1..=1).map(move |dx| (x + dx, z + dz)))
+}
+
+/// Write-locks chunk columns, always in the same order so that two overlapping groups can't deadlock.
+fn lock_columns(columns: &[((i32, i32), SharedChunk)]) -> Vec<((i32, i32), RwLockWriteGuard<'_, Chunk>)> {
+	let mut columns: Vec<_> = columns.iter().collect();
+	columns.sort_unstable_by_key(|(key, _)| *key);
+	columns.into_iter().map(|(key, chunk)| (*key, chunk.write())).collect()
+}
+
+/// Runs `f` on the light area around the locked column at `center`.
+fn with_light_area<R>(columns: &mut [((i32, i32), RwLockWriteGuard<'_, Chunk>)], center: (i32, i32), f: impl FnOnce(&mut LightArea) -> R) -> R {
+	let index = columns.iter().position(|(key, _)| *key == center).expect("the middle column is locked");
+	let (before, rest) = columns.split_at_mut(index);
+	let (middle, after) = rest.split_first_mut().expect("the middle column is locked");
+	let mut area = LightArea::new(&mut middle.1, before.iter_mut().chain(after).map(|(_, chunk)| &mut **chunk));
+	f(&mut area)
+}
+
+/// `SetChunk` packets for the sections of a chunk whose light changed.
+fn light_packets(x: i32, z: i32, chunk: &mut Chunk) -> Vec<(i32, i32, Packet)> {
+	let sections = chunk.light_mut().take_dirty();
+	sections.into_iter().filter_map(|section| chunk.to_set_chunk(section)).map(|packet| (x, z, packet.into())).collect()
+}
+
+/// [`FluidAccess`] to the loaded chunks of a world, caching the chunk lookups of a tick.
+struct LoadedChunks<'a> {
+	world: &'a World,
+	chunks: HashMap<(i32, i32), Option<SharedChunk>>,
+}
+
+impl<'a> LoadedChunks<'a> {
+	fn new(world: &'a World) -> Self {
+		Self { world, chunks: HashMap::new() }
+	}
+
+	/// The chunk column at `(x, z)` if it's loaded.
+	fn chunk(&mut self, x: i32, z: i32) -> Option<&SharedChunk> {
+		let world = self.world;
+		self.chunks.entry((x, z)).or_insert_with(|| world.get_chunk(x, z)).as_ref()
+	}
+
+	/// The chunk containing the block and its chunk-local coordinates.
+	fn locate(&mut self, x: i32, y: i32, z: i32) -> Option<(&SharedChunk, usize, usize, usize)> {
+		let y = usize::try_from(y).ok().filter(|y| *y < CHUNK_HEIGHT)?;
+		let chunk = self.chunk(chunk_coord(x), chunk_coord(z))?;
+		Some((chunk, local_coord(x), y, local_coord(z)))
+	}
+
+	/// Sets blocks, updating the light around each chunk once for all of its blocks. Returns the blocks that actually
+	/// changed.
+	fn set_blocks(&mut self, blocks: &[BlockChange]) -> Vec<BlockChange> {
+		let mut changed = Vec::new();
+		let mut positions: HashMap<(i32, i32), Vec<_>> = HashMap::new();
+		for &((x, y, z), state) in blocks {
+			let Some((chunk, local_x, local_y, local_z)) = self.locate(x, y, z) else {
+				continue;
+			};
+			if chunk.write().set_block(local_x, local_y, local_z, state) != state {
+				changed.push(((x, y, z), state));
+				positions.entry((chunk_coord(x), chunk_coord(z))).or_default().push((local_x, local_y, local_z));
+			}
+		}
+		self.world.unsaved.lock().extend(positions.keys().copied());
+		for ((x, z), positions) in positions {
+			// Light spreads into the loaded chunks around, which are cached too so their changed sections get sent
+			let columns: Vec<((i32, i32), SharedChunk)> = light_area_keys(x, z).filter_map(|key| Some((key, self.chunk(key.0, key.1)?.clone()))).collect();
+			let mut guards = lock_columns(&columns);
+			with_light_area(&mut guards, (x, z), |area| self.world.light.update_blocks(area, &positions));
+		}
+		changed
+	}
+
+	/// `SetChunk` packets for the sections of the chunks accessed so far whose light changed.
+	fn light_packets(&self) -> Vec<(i32, i32, Packet)> {
+		let mut packets = Vec::new();
+		for (&(x, z), chunk) in &self.chunks {
+			if let Some(chunk) = chunk {
+				packets.extend(light_packets(x, z, &mut chunk.write()));
+			}
+		}
+		packets
+	}
+}
+
+impl FluidAccess for LoadedChunks<'_> {
+	fn block(&mut self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+		self.locate(x, y, z).map(|(chunk, x, y, z)| chunk.read().get_block(x, y, z))
+	}
+
+	fn fluid(&mut self, x: i32, y: i32, z: i32) -> Option<FluidState> {
+		self.locate(x, y, z).map(|(chunk, x, y, z)| chunk.read().get_fluid(x, y, z))
+	}
+
+	fn set_block(&mut self, x: i32, y: i32, z: i32, state: BlockState) {
+		self.set_blocks(&[((x, y, z), state)]);
+	}
+
+	fn set_fluid(&mut self, x: i32, y: i32, z: i32, state: FluidState) {
+		let world = self.world;
+		if let Some((chunk, local_x, local_y, local_z)) = self.locate(x, y, z)
+			&& chunk.write().set_fluid(local_x, local_y, local_z, state) != state
+		{
+			world.unsaved.lock().insert((chunk_coord(x), chunk_coord(z)));
+		}
+	}
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use world::{
+	BlockDamageTracker,
+	BLOCK_DAMAGE_RESET,
+};
+
+#[test]
+fn blocks_break_at_full_damage_and_heal_when_left_alone() {
+	let mut damage = BlockDamageTracker::new();
+	let (update, broken) = damage.hit(1, 2, 3, 0.6);
+	assert!(!broken);
+	assert_eq!((update.damage, update.delta), (0.6, 0.6));
+
+	let (update, broken) = damage.hit(1, 2, 3, 0.6);
+	assert!(broken, "Damage past the block's health breaks it");
+	assert_eq!(update.damage, 1.0);
+	assert_eq!(damage.damage(1, 2, 3), 0.0, "Broken blocks are forgotten");
+
+	damage.hit(4, 5, 6, 0.25);
+	assert!(damage.tick(BLOCK_DAMAGE_RESET - Duration::from_millis(1)).is_empty());
+	let healed = damage.tick(Duration::from_millis(1));
+	assert_eq!(healed.len(), 1);
+	assert_eq!((healed[0].damage, healed[0].delta), (0.0, -0.25));
+	assert!(damage.clear(4, 5, 6).is_none(), "Healed blocks have nothing left to clear");
+}
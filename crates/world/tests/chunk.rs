@@ -0,0 +1,49 @@
+use world::{
+	BlockState,
+	Chunk,
+	CHUNK_HEIGHT,
+};
+
+const STONE: BlockState = BlockState { id: 1, filler: 0, rotation: 0 };
+const DIRT: BlockState = BlockState { id: 2, filler: 0, rotation: 0 };
+
+#[test]
+fn heightmap_follows_placed_blocks() {
+	let mut chunk = Chunk::new(0, 0);
+	assert_eq!(chunk.maps().height(3, 4), None);
+
+	chunk.set_block(3, 10, 4, STONE);
+	assert_eq!(chunk.maps().height(3, 4), Some(10));
+	chunk.set_block(3, 100, 4, STONE);
+	assert_eq!(chunk.maps().height(3, 4), Some(100), "A block above the top raises it");
+	chunk.set_block(3, 50, 4, STONE);
+	assert_eq!(chunk.maps().height(3, 4), Some(100), "A block below the top leaves it");
+	chunk.set_block(3, 100, 4, DIRT);
+	assert_eq!(chunk.maps().height(3, 4), Some(100), "Replacing the top block leaves it");
+	chunk.set_block(3, CHUNK_HEIGHT - 1, 4, STONE);
+	assert_eq!(chunk.maps().height(3, 4), Some(CHUNK_HEIGHT - 1));
+	chunk.set_block(3, CHUNK_HEIGHT, 4, STONE);
+	assert_eq!(chunk.maps().height(3, 4), Some(CHUNK_HEIGHT - 1), "Blocks above the world height are ignored");
+
+	assert_eq!(chunk.maps().height(4, 3), None, "Other columns are untouched");
+}
+
+#[test]
+fn heightmap_drops_with_removed_blocks() {
+	let mut chunk = Chunk::new(-2, 5);
+	for y in [10, 50, 100] {
+		chunk.set_block(7, y, 31, STONE);
+	}
+
+	chunk.set_block(7, 50, 31, BlockState::AIR);
+	assert_eq!(chunk.maps().height(7, 31), Some(100), "Removing a block below the top leaves it");
+	chunk.set_block(7, 100, 31, BlockState::AIR);
+	assert_eq!(chunk.maps().height(7, 31), Some(10), "Removing the top finds the next block down, across empty sections");
+	chunk.set_block(7, 10, 31, BlockState::AIR);
+	assert_eq!(chunk.maps().height(7, 31), None, "Removing the last block empties the column");
+
+	chunk.set_block(7, 0, 31, STONE);
+	assert_eq!(chunk.maps().height(7, 31), Some(0));
+	chunk.set_block(7, 0, 31, BlockState::AIR);
+	assert_eq!(chunk.maps().height(7, 31), None);
+}
@@ -0,0 +1,69 @@
+use std::{
+	collections::HashSet,
+	time::Duration,
+};
+
+use protocol::v2::Packet;
+use uuid::Uuid;
+use world::{
+	parse_time_of_day,
+	ClockSettings,
+	WorldClock,
+	SUNRISE,
+	SUNSET,
+};
+
+fn settings() -> ClockSettings {
+	ClockSettings {
+		daytime_seconds: 120,
+		nighttime_seconds: 60,
+		moon_phases: 8,
+		sleep_percentage: 50,
+	}
+}
+
+#[test]
+fn days_and_nights_take_their_configured_time() {
+	let mut clock = WorldClock::new(settings(), SUNRISE);
+	clock.tick(Duration::from_secs(60));
+	assert!((clock.time_of_day() - 12.0 * 3600.0).abs() < 1e-6, "Half the daytime should reach noon");
+
+	clock.set_time_of_day(SUNSET);
+	clock.tick(Duration::from_secs(30));
+	assert!(clock.time_of_day() < 1e-6, "Half the night should reach midnight");
+	assert_eq!(clock.day(), 1);
+
+	clock.set_paused(true);
+	let before = clock.game_time();
+	clock.tick(Duration::from_secs(30));
+	assert_eq!(clock.game_time(), before);
+}
+
+#[test]
+fn enough_sleepers_skip_the_night() {
+	let mut clock = WorldClock::new(settings(), SUNSET + 3600.0);
+	let players: HashSet<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+	let mut sleepers = players.iter().copied();
+
+	clock.set_sleeping(sleepers.next().unwrap(), true, &players);
+	assert!(!clock.is_skipping_night(), "One of four players isn't enough");
+	let packets = clock.set_sleeping(sleepers.next().unwrap(), true, &players);
+	assert!(clock.is_skipping_night());
+	assert!(matches!(&packets[..], [Packet::UpdateSleepState(state)] if state.clock.is_some()));
+
+	let packets = clock.tick(Duration::from_secs(10));
+	assert!(!clock.is_skipping_night());
+	assert_eq!(clock.sleeping(), 0, "Everyone wakes up in the morning");
+	assert_eq!(clock.time_of_day(), SUNRISE);
+	assert_eq!(clock.day(), 1);
+	assert!(packets.iter().any(|packet| matches!(packet, Packet::UpdateTime(_))));
+}
+
+#[test]
+fn times_of_day_parse() {
+	assert_eq!(parse_time_of_day("noon"), Some(12.0 * 3600.0));
+	assert_eq!(parse_time_of_day("06:30"), Some(6.5 * 3600.0));
+	assert_eq!(parse_time_of_day("18"), Some(SUNSET));
+	assert_eq!(parse_time_of_day("24:00"), None);
+	assert_eq!(parse_time_of_day("later"), None);
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+/// A fresh directory under the system temp dir, removed when dropped.
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+	pub fn new(name: &str) -> Self {
+		let path = std::env::temp_dir().join(format!("hightale-{}-{}-{}", name, std::process::id(), rand::random::<u32>()));
+		std::fs::create_dir_all(&path).unwrap();
+		Self(path)
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use assets::{
+	InputRef,
+	WithInput,
+};
+use protocol::v2::BenchType;
+use world::{
+	CraftingBench,
+	CraftingItem,
+	InventoryError,
+	ItemStack,
+	PlayerInventory,
+	RecipeRegistry,
+	Window,
+	BASE_BENCH_TIER,
+};
+
+fn recipes() -> RecipeRegistry {
+	let items = [
+		("Plank", r#"{ "Recipe": { "Input": [{ "ResourceTypeId": "Wood", "Quantity": 1 }], "OutputQuantity": 4 } }"#),
+		("Table", r#"{ "Recipe": { "Input": [{ "ItemId": "Plank", "Quantity": 6 }], "BenchRequirement": [{ "Type": "Crafting", "Id": "Workbench" }] } }"#),
+		("Log", r#"{ "ResourceTypes": [{ "Id": "Wood" }] }"#),
+	];
+	let mut recipes = Vec::new();
+	let mut resource_types = HashMap::new();
+	for (name, json) in items {
+		let item = CraftingItem::from_item_json(name, json.as_bytes()).unwrap();
+		recipes.extend(item.recipe.map(|recipe| WithInput::new(InputRef::label(name), recipe)));
+		resource_types.insert(name.to_string(), item.resource_types);
+	}
+	let mut registry = RecipeRegistry::new();
+	registry.load(recipes, resource_types).unwrap();
+	registry
+}
+
+#[test]
+fn crafting_takes_inputs_and_needs_the_right_window() {
+	let recipes = recipes();
+	let mut inventory = PlayerInventory::new();
+	inventory.storage.set(3, Some(ItemStack::new("Log", 2)));
+
+	assert!(matches!(inventory.craft(1, "Plank_Recipe_Generated_0", 1, &recipes), Err(InventoryError::NotCrafting(1))));
+	let pocket = inventory.windows.open(Window::pocket_crafting()).id();
+	assert!(matches!(inventory.craft(pocket, "Plank_Recipe_Generated_0", 3, &recipes), Err(InventoryError::MissingIngredients)));
+	assert_eq!(inventory.storage.get(3).unwrap().quantity, 2, "Nothing is taken from a failed craft");
+
+	inventory.craft(pocket, "Plank_Recipe_Generated_0", 2, &recipes).unwrap();
+	assert!(inventory.storage.get(3).is_none());
+	assert_eq!(inventory.hotbar.get(0).unwrap().quantity, 8);
+
+	assert!(matches!(inventory.craft(pocket, "Table_Recipe_Generated_0", 1, &recipes), Err(InventoryError::WrongBench(_))));
+	let bench = inventory
+		.windows
+		.open(Window::at_bench(CraftingBench {
+			bench_type: BenchType::Crafting,
+			id: "Workbench".to_string(),
+			tier: BASE_BENCH_TIER,
+		}))
+		.id();
+	inventory.craft(bench, "Table_Recipe_Generated_0", 1, &recipes).unwrap();
+	assert_eq!(inventory.hotbar.get(0).unwrap().quantity, 2);
+	assert_eq!(inventory.hotbar.get(1).unwrap().item_id, "Table");
+}
@@ -0,0 +1,51 @@
+use protocol::v2::{
+	ComponentUpdateType,
+	ModelTransform,
+	PositionF,
+};
+use uuid::Uuid;
+use world::{
+	EntityState,
+	EntityTracker,
+};
+
+fn at(x: f64, z: f64) -> ModelTransform {
+	ModelTransform {
+		position: Some(PositionF { x, y: 64.0, z }),
+		body_orientation: None,
+		look_orientation: None,
+	}
+}
+
+#[test]
+fn viewers_get_entities_in_view_and_their_changes() {
+	let viewer = Uuid::from_u128(1);
+	let mut entities = EntityTracker::new();
+	entities.spawn(1, EntityState::player("viewer", None, at(0.0, 0.0)));
+	entities.spawn(2, EntityState::player("near", None, at(40.0, 0.0)));
+	entities.spawn(3, EntityState::new(at(500.0, 0.0)));
+	entities.add_viewer(viewer, 1, 2);
+
+	let updates = entities.tick();
+	assert_eq!(updates.len(), 1);
+	let (uuid, packet) = &updates[0];
+	assert_eq!(*uuid, viewer);
+	let spawned = packet.updates.as_ref().unwrap();
+	assert_eq!(spawned.len(), 1, "Viewers don't get their own entity or ones out of view");
+	assert_eq!(spawned[0].network_id, 2);
+	let components: Vec<_> = spawned[0].updates.as_ref().unwrap().iter().map(|update| update.update_type).collect();
+	assert_eq!(components, [ComponentUpdateType::Transform, ComponentUpdateType::Nameplate]);
+
+	assert!(entities.tick().is_empty(), "Nothing changed");
+
+	entities.set_transform(2, at(41.0, 0.0), None);
+	let updates = entities.tick();
+	let changed = updates[0].1.updates.as_ref().unwrap();
+	let components: Vec<_> = changed[0].updates.as_ref().unwrap().iter().map(|update| update.update_type).collect();
+	assert_eq!(components, [ComponentUpdateType::Transform], "Only changed components are sent");
+
+	entities.remove(2);
+	let updates = entities.tick();
+	assert_eq!(updates[0].1.removed, Some(vec![2]));
+	assert!(updates[0].1.updates.is_none());
+}
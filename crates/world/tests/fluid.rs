@@ -0,0 +1,121 @@
+use std::{
+	collections::{
+		BTreeMap,
+		HashMap,
+	},
+	sync::Arc,
+};
+
+use assets::{
+	InputRef,
+	WithInput,
+};
+use world::{
+	BlockRegistry,
+	BlockState,
+	BlockTypeAsset,
+	FluidAccess,
+	FluidRegistry,
+	FluidSimulator,
+	FluidState,
+	FluidTypeAsset,
+};
+
+const FLOOR: i32 = 0;
+
+/// A stone floor at y = 0 with air above it, stretching as far as the fluids flow.
+#[derive(Default)]
+struct Floor {
+	blocks: HashMap<(i32, i32, i32), BlockState>,
+	fluids: HashMap<(i32, i32, i32), FluidState>,
+}
+
+impl FluidAccess for Floor {
+	fn block(&mut self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+		if y < FLOOR {
+			return None;
+		}
+		Some(self.blocks.get(&(x, y, z)).copied().unwrap_or(if y == FLOOR { BlockState::new(2) } else { BlockState::AIR }))
+	}
+
+	fn fluid(&mut self, x: i32, y: i32, z: i32) -> Option<FluidState> {
+		(y >= FLOOR).then(|| self.fluids.get(&(x, y, z)).copied().unwrap_or_default())
+	}
+
+	fn set_block(&mut self, x: i32, y: i32, z: i32, state: BlockState) {
+		self.blocks.insert((x, y, z), state);
+	}
+
+	fn set_fluid(&mut self, x: i32, y: i32, z: i32, state: FluidState) {
+		self.fluids.insert((x, y, z), state);
+	}
+}
+
+fn registries() -> (BlockRegistry, Arc<FluidRegistry>) {
+	let mut blocks = BlockRegistry::new(&BTreeMap::new());
+	let stone = BlockTypeAsset::from_item_json("Rock_Stone", br#"{ "BlockType": {} }"#).unwrap().unwrap();
+	blocks.load(vec![WithInput::new(InputRef::label("Rock_Stone"), stone)]).unwrap();
+
+	let water = r#"{ "MaxFluidLevel": 8, "Ticker": { "FlowRate": 0.1 } }"#;
+	let lava = r#"{ "MaxFluidLevel": 4, "Ticker": { "FlowRate": 0.3, "Collisions": { "Water": { "BlockToPlace": "Rock_Stone" } } } }"#;
+	let mut fluids = FluidRegistry::new(&BTreeMap::new());
+	let assets = [("Water", water), ("Lava", lava)].map(|(name, json)| WithInput::new(InputRef::label(name), FluidTypeAsset::from_json(name, json.as_bytes()).unwrap()));
+	fluids.load(assets.into(), &blocks).unwrap();
+	(blocks, Arc::new(fluids))
+}
+
+fn run(simulator: &mut FluidSimulator, floor: &mut Floor, ticks: usize) {
+	for _ in 0..ticks {
+		simulator.tick(floor);
+	}
+}
+
+fn place(simulator: &mut FluidSimulator, floor: &mut Floor, (x, y, z): (i32, i32, i32), state: FluidState) {
+	floor.set_fluid(x, y, z, state);
+	simulator.schedule_around(x, y, z, 1);
+}
+
+#[test]
+fn water_spreads_and_drains() {
+	let (_, fluids) = registries();
+	let water = fluids.source("Water");
+	let mut simulator = FluidSimulator::new(fluids.clone(), 30);
+	let mut floor = Floor::default();
+
+	// Falls down to the floor first, then spreads one level less per block
+	place(&mut simulator, &mut floor, (0, 5, 0), water);
+	run(&mut simulator, &mut floor, 200);
+	assert_eq!(floor.fluid(0, 3, 0).unwrap(), FluidState::new(water.id, 7));
+	assert_eq!(floor.fluid(0, 1, 0).unwrap(), FluidState::new(water.id, 7));
+	assert_eq!(floor.fluid(1, 1, 0).unwrap(), FluidState::new(water.id, 6));
+	assert_eq!(floor.fluid(6, 1, 0).unwrap(), FluidState::new(water.id, 1));
+	assert!(floor.fluid(7, 1, 0).unwrap().is_empty());
+	assert!(floor.fluid(1, 2, 0).unwrap().is_empty());
+	assert_eq!(simulator.pending(), 0, "Water should come to rest");
+
+	// Without its source everything flows away
+	place(&mut simulator, &mut floor, (0, 5, 0), FluidState::EMPTY);
+	run(&mut simulator, &mut floor, 400);
+	assert!(floor.fluids.values().all(FluidState::is_empty), "Water should drain");
+}
+
+#[test]
+fn lava_meeting_water_turns_into_stone() {
+	let (blocks, fluids) = registries();
+	let (water, lava) = (fluids.source("Water"), fluids.source("Lava"));
+	assert_eq!(lava.level, 4);
+	let mut simulator = FluidSimulator::new(fluids, 30);
+	let mut floor = Floor::default();
+
+	place(&mut simulator, &mut floor, (0, 1, 0), lava);
+	place(&mut simulator, &mut floor, (10, 1, 0), water);
+	run(&mut simulator, &mut floor, 300);
+
+	// Lava reaches 3 blocks, water 7, so they meet in between
+	let stone = BlockState::new(blocks.id("Rock_Stone").unwrap());
+	assert!(floor.blocks.values().any(|block| *block == stone), "Lava and water should have met");
+	for ((x, y, z), block) in &floor.blocks {
+		assert_eq!(*block, stone);
+		assert!(floor.fluids.get(&(*x, *y, *z)).is_none_or(FluidState::is_empty));
+	}
+}
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use world::{
+	GeneratorError,
+	GeneratorPool,
+	VoidGenerator,
+};
+
+#[tokio::test]
+async fn panicking_jobs_return_an_error() {
+	let pool = GeneratorPool::new(Arc::new(VoidGenerator), 0, 1).unwrap();
+	let result = pool.run(|| -> i32 { panic!("broken generator") }).await;
+	assert!(matches!(result, Err(GeneratorError::Panicked(message)) if message == "broken generator"));
+
+	// The pool keeps working afterwards
+	assert_eq!(pool.run(|| 1 + 1).await.unwrap(), 2);
+}
@@ -0,0 +1,96 @@
+use protocol::v2::{
+	inventory::SmartMoveType,
+	ItemQuantity,
+};
+use world::{
+	InventoryError,
+	ItemStack,
+	PlayerInventory,
+	DEFAULT_MAX_STACK,
+	HOTBAR_SECTION_ID,
+	SAVED_HOTBAR_ROWS,
+	STORAGE_SECTION_ID,
+};
+
+#[test]
+fn moves_merge_swap_and_get_validated() {
+	let mut inventory = PlayerInventory::new();
+	inventory.hotbar.set(0, Some(ItemStack::new("Rock", 10)));
+	inventory.hotbar.set(1, Some(ItemStack::new("Rock", DEFAULT_MAX_STACK - 4)));
+	inventory.storage.set(0, Some(ItemStack::new("Stick", 3)));
+
+	inventory.move_item(HOTBAR_SECTION_ID, 0, 6, HOTBAR_SECTION_ID, 1).unwrap();
+	assert_eq!(inventory.hotbar.get(0).unwrap().quantity, 6, "Only what fits is merged");
+	assert_eq!(inventory.hotbar.get(1).unwrap().quantity, DEFAULT_MAX_STACK);
+
+	inventory.move_item(HOTBAR_SECTION_ID, 0, 6, STORAGE_SECTION_ID, 0).unwrap();
+	assert_eq!(inventory.storage.get(0).unwrap().item_id, "Rock", "Whole stacks swap");
+	assert_eq!(inventory.hotbar.get(0).unwrap().item_id, "Stick");
+
+	assert!(matches!(inventory.move_item(HOTBAR_SECTION_ID, 0, 1, STORAGE_SECTION_ID, 0), Err(InventoryError::SlotFull { .. })));
+	assert!(matches!(inventory.move_item(HOTBAR_SECTION_ID, 0, 4, STORAGE_SECTION_ID, 1), Err(InventoryError::InvalidQuantity(4))));
+	assert!(matches!(inventory.move_item(HOTBAR_SECTION_ID, 0, 1, STORAGE_SECTION_ID, 99), Err(InventoryError::InvalidSlot { .. })));
+	assert!(matches!(inventory.move_item(HOTBAR_SECTION_ID, 5, 1, STORAGE_SECTION_ID, 1), Err(InventoryError::EmptySlot { .. })));
+
+	inventory.smart_move(HOTBAR_SECTION_ID, 0, 3, SmartMoveType::PutInHotbarOrWindow).unwrap();
+	assert!(inventory.hotbar.get(0).is_none());
+	assert_eq!(inventory.storage.get(1).unwrap().item_id, "Stick");
+}
+
+#[test]
+fn tools_wear_down_and_break() {
+	let mut inventory = PlayerInventory::new();
+	inventory.hotbar.set(0, Some(ItemStack::new("Pickaxe", 1).with_durability(2.0)));
+	assert!(inventory.damage_held_item(1.0));
+	assert_eq!(inventory.held_item().unwrap().durability, 1.0);
+	assert!(inventory.damage_held_item(1.0));
+	assert!(inventory.held_item().is_none(), "Broken tools are gone");
+	assert!(!inventory.damage_held_item(1.0));
+}
+
+#[test]
+fn creative_items_are_validated() {
+	let item = |item_id: &str, quantity| ItemQuantity {
+		quantity,
+		item_id: Some(item_id.to_string()),
+	};
+	assert!(matches!(ItemStack::creative(&item("Rock", 0)), Err(InventoryError::InvalidQuantity(0))));
+	assert!(matches!(ItemStack::creative(&item("Rock", DEFAULT_MAX_STACK + 1)), Err(InventoryError::InvalidQuantity(_))));
+
+	let mut inventory = PlayerInventory::new();
+	let rocks = ItemStack::creative(&item("Rock", 60)).unwrap();
+	inventory.set_creative_item(HOTBAR_SECTION_ID, 0, rocks.clone(), false).unwrap();
+	inventory.set_creative_item(HOTBAR_SECTION_ID, 0, rocks, false).unwrap();
+	assert_eq!(inventory.hotbar.get(0).unwrap().quantity, DEFAULT_MAX_STACK, "Stacks are topped up to their limit");
+
+	let sticks = ItemStack::creative(&item("Stick", 1)).unwrap();
+	assert!(matches!(inventory.set_creative_item(HOTBAR_SECTION_ID, 0, sticks.clone(), false), Err(InventoryError::SlotFull { .. })));
+	inventory.set_creative_item(HOTBAR_SECTION_ID, 0, sticks, true).unwrap();
+	assert_eq!(inventory.hotbar.get(0).unwrap().item_id, "Stick");
+	inventory.set_creative_item(HOTBAR_SECTION_ID, 0, None, false).unwrap();
+	assert!(inventory.hotbar.get(0).is_none());
+	assert!(matches!(inventory.set_creative_item(HOTBAR_SECTION_ID, 9, None, true), Err(InventoryError::InvalidSlot { .. })));
+}
+
+#[test]
+fn hotbar_rows_are_saved_and_loaded() {
+	let mut inventory = PlayerInventory::new();
+	inventory.hotbar.set(2, Some(ItemStack::new("Rock_Stone", 50)));
+	inventory.save_hotbar(3).unwrap();
+	assert!(matches!(inventory.save_hotbar(SAVED_HOTBAR_ROWS), Err(InventoryError::InvalidHotbarRow(_))));
+	inventory.load_hotbar(0).unwrap();
+	assert!(inventory.hotbar.is_empty(), "Rows that were never saved are empty");
+	inventory.load_hotbar(3).unwrap();
+	assert_eq!(inventory.hotbar.get(2).unwrap().item_id, "Rock_Stone");
+}
+
+#[test]
+fn placing_takes_from_the_held_stack() {
+	let mut inventory = PlayerInventory::new();
+	inventory.hotbar.set(0, Some(ItemStack::new("Rock_Stone", 2)));
+	assert_eq!(inventory.take_held_item(1).unwrap().quantity, 1);
+	assert_eq!(inventory.held_item().unwrap().quantity, 1);
+	inventory.take_held_item(1).unwrap();
+	assert!(inventory.held_item().is_none(), "Empty stacks are gone");
+	assert!(matches!(inventory.take_held_item(1), Err(InventoryError::EmptySlot { .. })));
+}
@@ -0,0 +1,151 @@
+use world::{
+	chunk_coord,
+	local_coord,
+	BlockChange,
+	BlockLighting,
+	BlockState,
+	Chunk,
+	LightArea,
+	LightEngine,
+	CHUNK_HEIGHT,
+	SECTION_SIZE,
+};
+
+const STONE: BlockState = BlockState { id: 1, filler: 0, rotation: 0 };
+const TORCH: BlockState = BlockState { id: 2, filler: 0, rotation: 0 };
+const GLASS: BlockState = BlockState { id: 3, filler: 0, rotation: 0 };
+
+struct TestLighting;
+
+impl BlockLighting for TestLighting {
+	fn emission(&self, block: BlockState) -> [u8; 3] {
+		if block == TORCH { [15, 10, 5] } else { [0; 3] }
+	}
+
+	fn opacity(&self, block: BlockState) -> u8 {
+		match block.id {
+			0 | 2 => 0,
+			3 => 2,
+			_ => 15,
+		}
+	}
+}
+
+/// A three by three grid of chunks around the origin, with a stone floor.
+fn grid() -> Vec<Chunk> {
+	let mut chunks = Vec::new();
+	for z in -1..=1 {
+		for x in -1..=1 {
+			let mut chunk = Chunk::new(x, z);
+			for y in 0..4 {
+				for z in 0..SECTION_SIZE {
+					for x in 0..SECTION_SIZE {
+						chunk.set_block(x, y, z, STONE);
+					}
+				}
+			}
+			chunks.push(chunk);
+		}
+	}
+	chunks
+}
+
+fn grid_index(x: i32, z: i32) -> usize {
+	((chunk_coord(z) + 1) * 3 + chunk_coord(x) + 1) as usize
+}
+
+fn with_area<R>(chunks: &mut [Chunk], center: usize, f: impl FnOnce(&mut LightArea) -> R) -> R {
+	let (before, rest) = chunks.split_at_mut(center);
+	let (middle, after) = rest.split_first_mut().unwrap();
+	f(&mut LightArea::new(middle, before.iter_mut().chain(after)))
+}
+
+/// Lights every chunk from scratch, as if they were all loaded one after another.
+fn light_all(engine: &LightEngine<TestLighting>, chunks: &mut [Chunk]) {
+	for chunk in chunks.iter_mut() {
+		engine.light_chunk(chunk);
+	}
+	for center in 0..chunks.len() {
+		with_area(chunks, center, |area| engine.stitch(area));
+	}
+}
+
+/// Sets blocks at world positions, then relights each changed chunk once together with the chunks around it.
+fn set_blocks(engine: &LightEngine<TestLighting>, chunks: &mut [Chunk], blocks: &[BlockChange]) {
+	let mut changed: Vec<(usize, Vec<_>)> = Vec::new();
+	for &((x, y, z), state) in blocks {
+		let index = grid_index(x, z);
+		let position = (local_coord(x), y as usize, local_coord(z));
+		chunks[index].set_block(position.0, position.1, position.2, state);
+		match changed.iter_mut().find(|(changed, _)| *changed == index) {
+			Some((_, positions)) => positions.push(position),
+			None => changed.push((index, vec![position])),
+		}
+	}
+	for (index, positions) in changed {
+		with_area(chunks, index, |area| engine.update_blocks(area, &positions));
+	}
+}
+
+fn assert_same_light(incremental: &[Chunk], full: &[Chunk], step: &str) {
+	for (incremental, full) in incremental.iter().zip(full) {
+		for z in 0..SECTION_SIZE {
+			for x in 0..SECTION_SIZE {
+				assert_eq!(incremental.light().sky_height(x, z), full.light().sky_height(x, z), "{step}: sky height at {x} {z} of chunk {} {}", full.x(), full.z());
+				for y in 0..CHUNK_HEIGHT {
+					assert_eq!(
+						incremental.light().get(x, y, z),
+						full.light().get(x, y, z),
+						"{step}: light at {x} {y} {z} of chunk {} {}",
+						full.x(),
+						full.z()
+					);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn light_spreads_across_column_borders() {
+	let engine = LightEngine::new(TestLighting);
+	let mut chunks = grid();
+	light_all(&engine, &mut chunks);
+	set_blocks(&engine, &mut chunks, &[((31, 10, 5), TORCH), ((31, 10, 31), TORCH)]);
+
+	let east = &chunks[grid_index(32, 5)];
+	assert_eq!(east.light().block_light(0, 10, 5), [14, 9, 4]);
+	assert_eq!(east.light().block_light(5, 10, 5), [9, 4, 0]);
+	assert_eq!(chunks[grid_index(32, 32)].light().block_light(0, 10, 0), [13, 8, 3], "Light reaches diagonal neighbours");
+
+	set_blocks(&engine, &mut chunks, &[((31, 10, 5), BlockState::AIR)]);
+	assert_eq!(chunks[grid_index(32, 5)].light().block_light(0, 10, 5), [0; 3], "Removed light is gone from the neighbour too");
+}
+
+#[test]
+fn incremental_relight_matches_full_relight() {
+	let engine = LightEngine::new(TestLighting);
+	let mut chunks = grid();
+	light_all(&engine, &mut chunks);
+
+	let roof: Vec<_> = (20..44).flat_map(|x| (10..20).map(move |z| ((x, 20, z), STONE))).collect();
+	let hole: Vec<_> = (28..36).flat_map(|x| (12..16).map(move |z| ((x, 20, z), BlockState::AIR))).collect();
+	let steps: Vec<(&str, Vec<BlockChange>)> = vec![
+		("torches at borders", vec![((31, 10, 5), TORCH), ((0, 12, 0), TORCH), ((-1, 6, 31), TORCH), ((40, 8, -3), TORCH)]),
+		("roof over a border", roof),
+		("torch under the roof", vec![((32, 18, 15), TORCH)]),
+		("glass walls", (4..12).flat_map(|y| [((30, y, 5), GLASS), ((32, y, 6), GLASS), ((-1, y, 0), GLASS)]).collect()),
+		("torches removed", vec![((31, 10, 5), BlockState::AIR), ((0, 12, 0), BlockState::AIR)]),
+		("hole in the roof", hole),
+		("tower above the top", (20..40).map(|y| ((-2, y, -2), STONE)).chain((20..30).map(|y| ((33, y, 1), STONE))).collect()),
+		("floor dug out at a corner", (0..4).flat_map(|y| [((0, y, 0), BlockState::AIR), ((-1, y, -1), BlockState::AIR), ((-1, y, 0), BlockState::AIR)]).collect()),
+		("everything removed", vec![((32, 18, 15), BlockState::AIR), ((-1, 6, 31), BlockState::AIR), ((40, 8, -3), BlockState::AIR)]),
+	];
+
+	for (step, blocks) in steps {
+		set_blocks(&engine, &mut chunks, &blocks);
+		let mut full = chunks.clone();
+		light_all(&engine, &mut full);
+		assert_same_light(&chunks, &full, step);
+	}
+}
@@ -0,0 +1,146 @@
+use bytes::BytesMut;
+use protocol::v2::world::PaletteType;
+use world::{
+	ChunkError,
+	Palette,
+	SECTION_VOLUME,
+};
+
+/// A palette where the first `count` blocks each hold a distinct value and the rest hold the default.
+fn distinct(count: usize) -> Palette<i32> {
+	let mut palette = Palette::new();
+	for index in 0..count {
+		palette.set(index, index as i32 + 1);
+	}
+	palette
+}
+
+fn round_trip(palette: &Palette<i32>) -> Palette<i32> {
+	let mut buf = BytesMut::new();
+	palette.encode(&mut buf);
+	let mut bytes = buf.freeze();
+	let decoded = Palette::decode(&mut bytes).unwrap();
+	assert!(bytes.is_empty(), "The whole encoding is read back");
+	decoded
+}
+
+fn assert_same(palette: &Palette<i32>, other: &Palette<i32>) {
+	assert_eq!(palette.palette_type(), other.palette_type());
+	assert_eq!(palette.len(), other.len());
+	for index in 0..SECTION_VOLUME {
+		assert_eq!(palette.get(index), other.get(index), "value at {index}");
+	}
+}
+
+#[test]
+fn widens_with_distinct_values() {
+	let mut palette = Palette::new();
+	assert_eq!(palette.palette_type(), PaletteType::Empty);
+	assert_eq!(palette.set(0, 0), 0, "Setting the default keeps the palette empty");
+	assert_eq!(palette.palette_type(), PaletteType::Empty);
+
+	for (count, palette_type) in [(1, PaletteType::HalfByte), (15, PaletteType::HalfByte), (16, PaletteType::Byte), (255, PaletteType::Byte), (256, PaletteType::Short)] {
+		while palette.len() < count + 1 {
+			let index = palette.len() - 1;
+			palette.set(index, index as i32 + 1);
+		}
+		assert_eq!(palette.palette_type(), palette_type, "{} values besides the default", count);
+	}
+	for index in 0..256 {
+		assert_eq!(palette.get(index), index as i32 + 1);
+	}
+	assert_eq!(palette.get(256), 0);
+}
+
+#[test]
+fn narrows_with_headroom() {
+	let mut palette = distinct(300);
+	assert_eq!(palette.palette_type(), PaletteType::Short);
+
+	// Narrowing waits until a quarter of the smaller capacity is left free
+	for (live, palette_type) in [(206, PaletteType::Short), (205, PaletteType::Byte), (14, PaletteType::Byte), (13, PaletteType::HalfByte), (2, PaletteType::HalfByte)] {
+		while palette.len() > live {
+			let index = palette.len() - 2;
+			palette.set(index, 0);
+		}
+		assert_eq!(palette.palette_type(), palette_type, "{} live entries", live);
+		for index in 0..live - 1 {
+			assert_eq!(palette.get(index), index as i32 + 1);
+		}
+	}
+	palette.set(0, 0);
+	assert_eq!(palette.palette_type(), PaletteType::Empty, "Only the default left");
+	assert!(palette.is_empty());
+}
+
+#[test]
+fn hovering_at_a_boundary_keeps_the_wider_type() {
+	let mut palette = distinct(15);
+	assert_eq!(palette.palette_type(), PaletteType::HalfByte);
+	for _ in 0..4 {
+		palette.set(15, 16);
+		assert_eq!(palette.palette_type(), PaletteType::Byte);
+		palette.set(15, 0);
+		assert_eq!(palette.palette_type(), PaletteType::Byte, "One value less doesn't narrow right away");
+	}
+}
+
+#[test]
+fn freed_ids_are_reused() {
+	let mut palette = distinct(15);
+	assert_eq!(palette.len(), 16);
+
+	// Replacing the last block of a value frees its id right away for the new value
+	assert_eq!(palette.set(3, 100), 4);
+	assert_eq!(palette.palette_type(), PaletteType::HalfByte);
+
+	// A freed id stays free until a new value needs it
+	palette.set(5, 1);
+	assert_eq!(palette.len(), 15);
+	palette.set(SECTION_VOLUME - 1, 200);
+	assert_eq!(palette.len(), 16);
+	assert_eq!(palette.palette_type(), PaletteType::HalfByte, "The new value takes the freed id instead of widening");
+	assert_eq!((palette.get(3), palette.get(5), palette.get(SECTION_VOLUME - 1)), (100, 1, 200));
+	assert!(!palette.contains(4) && !palette.contains(6));
+}
+
+#[test]
+fn round_trips_every_palette_type() {
+	for count in [0, 1, 15, 200, 1000] {
+		let palette = distinct(count);
+		assert_same(&palette, &round_trip(&palette));
+	}
+	let filled = Palette::filled(7);
+	assert_same(&filled, &round_trip(&filled));
+}
+
+#[test]
+fn round_trips_palettes_with_freed_ids() {
+	// Freed ids in the middle of a palette that isn't narrowed yet are left out of the entry table
+	let mut palette = distinct(40);
+	for index in (0..40).step_by(3) {
+		palette.set(index, 0);
+	}
+	assert_eq!(palette.palette_type(), PaletteType::Byte);
+	let mut decoded = round_trip(&palette);
+	assert_same(&palette, &decoded);
+
+	// The decoded palette knows which ids are free
+	for value in 1000..1010 {
+		decoded.set(value as usize, value);
+	}
+	assert_eq!(decoded.palette_type(), PaletteType::Byte);
+	assert_eq!(decoded.len(), palette.len() + 10);
+	assert_eq!(decoded.get(1), 2);
+	assert_eq!(decoded.get(1005), 1005);
+}
+
+#[test]
+fn rejects_storage_referencing_unknown_entries() {
+	let mut buf = BytesMut::new();
+	distinct(1).encode(&mut buf);
+	// The default and the value take ids 0 and 1, nothing has id 2
+	let storage = buf.len() - SECTION_VOLUME / 2;
+	buf[storage + 10] = 0x22;
+	assert!(matches!(Palette::<i32>::decode(&mut buf.freeze()), Err(ChunkError::InvalidPalette(_))));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::sync::Arc;
+
+use protocol::v2::GameMode;
+use uuid::Uuid;
+use world::{
+	ItemStack,
+	PlayerData,
+	PlayerDataError,
+	PlayerDataStore,
+	SavedPosition,
+	PLAYER_DATA_VERSION,
+};
+
+use crate::common::TempDir;
+
+#[tokio::test]
+async fn player_data_round_trips() {
+	let dir = TempDir::new("player-data");
+	let store = Arc::new(PlayerDataStore::new(dir.0.join("players")));
+	let uuid = Uuid::new_v4();
+	assert!(store.load(uuid).await.unwrap().is_none());
+
+	let mut data = PlayerData {
+		position: Some(SavedPosition { x: 1.5, y: 80.0, z: -3.25 }),
+		world: Some(Uuid::new_v4()),
+		game_mode: Some(GameMode::Creative),
+		..PlayerData::default()
+	};
+	data.inventory.hotbar.set(4, Some(ItemStack::new("Rock_Stone", 12)));
+	data.inventory.save_hotbar(1).unwrap();
+	data.stats.blocks_placed = 7;
+	data.preferences.view_radius = Some(9);
+	store.save(uuid, data.clone()).await.unwrap();
+
+	let loaded = store.load(uuid).await.unwrap().unwrap();
+	assert_eq!(loaded.position, data.position);
+	assert_eq!(loaded.world, data.world);
+	assert_eq!(loaded.game_mode, Some(GameMode::Creative));
+	assert_eq!(loaded.inventory.hotbar.get(4), data.inventory.hotbar.get(4));
+	assert!(loaded.inventory.saved_hotbars.contains_key(&1));
+	assert_eq!(loaded.stats, data.stats);
+	assert_eq!(loaded.preferences, data.preferences);
+}
+
+#[tokio::test]
+async fn unknown_versions_are_refused() {
+	let dir = TempDir::new("player-data-versions");
+	let store = Arc::new(PlayerDataStore::new(dir.0.clone()));
+	let uuid = Uuid::new_v4();
+
+	std::fs::write(dir.0.join(format!("{uuid}.json")), format!(r#"{{ "version": {} }}"#, PLAYER_DATA_VERSION + 1)).unwrap();
+	assert!(matches!(store.load(uuid).await, Err(PlayerDataError::UnsupportedVersion(_))));
+	std::fs::write(dir.0.join(format!("{uuid}.json")), r#"{ "inventory": {} }"#).unwrap();
+	assert!(matches!(store.load(uuid).await, Err(PlayerDataError::UnsupportedVersion(0))), "Files without a version aren't guessed at");
+}
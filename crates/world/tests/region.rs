@@ -0,0 +1,226 @@
+mod common;
+
+use std::sync::Arc;
+
+use rand::{
+	Rng,
+	SeedableRng,
+	rngs::StdRng,
+};
+use world::{
+	BlockState,
+	Chunk,
+	FluidState,
+	RegionError,
+	RegionFile,
+	RegionStorage,
+	CHUNK_HEIGHT,
+	MAX_OPEN_REGIONS,
+	REGION_FORMAT_VERSION,
+	REGION_SIZE,
+	SECTION_SIZE,
+};
+
+use crate::common::TempDir;
+
+/// Fills a chunk with random blocks. `variety` controls how many distinct block ids show up, so that every palette type
+/// gets exercised.
+fn random_chunk(rng: &mut StdRng, x: i32, z: i32, variety: i32) -> Chunk {
+	let mut chunk = Chunk::new(x, z);
+	let blocks = rng.random_range(0..20_000);
+	for _ in 0..blocks {
+		let state = BlockState {
+			id: rng.random_range(0..variety),
+			filler: if rng.random_bool(0.1) { rng.random_range(-8..8) } else { 0 },
+			rotation: if rng.random_bool(0.2) { rng.random_range(0..24) } else { 0 },
+		};
+		chunk.set_block(rng.random_range(0..SECTION_SIZE), rng.random_range(0..CHUNK_HEIGHT), rng.random_range(0..SECTION_SIZE), state);
+	}
+	if rng.random_bool(0.5) {
+		chunk.maps_mut().fill_tint(rng.random());
+	}
+	for _ in 0..rng.random_range(0..64) {
+		let (cx, cz) = (rng.random_range(0..SECTION_SIZE), rng.random_range(0..SECTION_SIZE));
+		chunk.maps_mut().set_tint(cx, cz, rng.random());
+		chunk.maps_mut().set_environment(cx, cz, rng.random_range(0..16));
+	}
+	for _ in 0..rng.random_range(0..2_000) {
+		let fluid = FluidState::new(rng.random_range(1..4), rng.random_range(1..9));
+		chunk.set_fluid(rng.random_range(0..SECTION_SIZE), rng.random_range(0..CHUNK_HEIGHT), rng.random_range(0..SECTION_SIZE), fluid);
+	}
+	chunk
+}
+
+fn assert_same(expected: &Chunk, actual: &Chunk) {
+	assert_eq!((expected.x(), expected.z()), (actual.x(), actual.z()));
+	for y in 0..CHUNK_HEIGHT {
+		for z in 0..SECTION_SIZE {
+			for x in 0..SECTION_SIZE {
+				assert_eq!(expected.get_block(x, y, z), actual.get_block(x, y, z), "Block at {} {} {} of chunk {} {}", x, y, z, expected.x(), expected.z());
+				assert_eq!(expected.get_fluid(x, y, z), actual.get_fluid(x, y, z), "Fluid at {} {} {} of chunk {} {}", x, y, z, expected.x(), expected.z());
+			}
+		}
+	}
+	for z in 0..SECTION_SIZE {
+		for x in 0..SECTION_SIZE {
+			assert_eq!(expected.maps().height(x, z), actual.maps().height(x, z));
+			assert_eq!(expected.maps().tint(x, z), actual.maps().tint(x, z));
+			assert_eq!(expected.maps().environment(x, z), actual.maps().environment(x, z));
+		}
+	}
+}
+
+#[tokio::test]
+async fn round_trips_random_chunks() {
+	let dir = TempDir::new("round-trip");
+	let mut rng = StdRng::seed_from_u64(0x5EED);
+	let storage = Arc::new(RegionStorage::new(&dir.0));
+
+	// Spread over several regions, including negative coordinates
+	let mut chunks = Vec::new();
+	for (index, variety) in [1, 2, 16, 17, 255, 300, 2000].into_iter().enumerate() {
+		let x = rng.random_range(-2 * REGION_SIZE as i32..2 * REGION_SIZE as i32);
+		let z = rng.random_range(-2 * REGION_SIZE as i32..2 * REGION_SIZE as i32);
+		if chunks.iter().any(|chunk: &Chunk| (chunk.x(), chunk.z()) == (x, z)) {
+			continue;
+		}
+		chunks.push(random_chunk(&mut rng, x, z, variety + index as i32));
+	}
+	storage.save_chunks(chunks.clone()).await.unwrap();
+
+	for chunk in &chunks {
+		let loaded = storage.load_chunk(chunk.x(), chunk.z()).await.unwrap().expect("Saved chunk is missing");
+		assert_same(chunk, &loaded);
+	}
+
+	// A fresh storage has to read everything back from disk
+	let reopened = Arc::new(RegionStorage::new(&dir.0));
+	for chunk in &chunks {
+		let loaded = reopened.load_chunk(chunk.x(), chunk.z()).await.unwrap().expect("Saved chunk is missing after reopening");
+		assert_same(chunk, &loaded);
+	}
+}
+
+#[tokio::test]
+async fn overwrites_chunks() {
+	let dir = TempDir::new("overwrite");
+	let mut rng = StdRng::seed_from_u64(42);
+	let storage = Arc::new(RegionStorage::new(&dir.0));
+
+	// Alternate between small and large chunks so that both the in-place and the append path are taken
+	let mut latest = None;
+	for variety in [2000, 1, 500, 3, 3000] {
+		let chunk = random_chunk(&mut rng, 3, -7, variety);
+		storage.save_chunk(chunk.clone()).await.unwrap();
+		latest = Some(chunk);
+	}
+	let neighbour = random_chunk(&mut rng, 4, -7, 40);
+	storage.save_chunk(neighbour.clone()).await.unwrap();
+
+	storage.close_regions();
+	assert_same(latest.as_ref().unwrap(), &storage.load_chunk(3, -7).await.unwrap().unwrap());
+	assert_same(&neighbour, &storage.load_chunk(4, -7).await.unwrap().unwrap());
+}
+
+#[tokio::test]
+async fn missing_and_removed_chunks() {
+	let dir = TempDir::new("missing");
+	let mut rng = StdRng::seed_from_u64(7);
+	let storage = Arc::new(RegionStorage::new(&dir.0));
+
+	assert!(storage.load_chunk(0, 0).await.unwrap().is_none());
+
+	storage.save_chunk(random_chunk(&mut rng, 0, 0, 10)).await.unwrap();
+	assert!(storage.load_chunk(1, 0).await.unwrap().is_none());
+	assert!(storage.load_chunk(0, 0).await.unwrap().is_some());
+
+	storage.remove_chunk(0, 0).await.unwrap();
+	assert!(storage.load_chunk(0, 0).await.unwrap().is_none());
+}
+
+#[test]
+fn upgrades_files_without_fluids() {
+	let dir = TempDir::new("upgrade");
+	let path = dir.0.join("old.region");
+	let mut chunk = Chunk::new(5, 9);
+	chunk.set_block(1, 2, 3, BlockState::new(7));
+	chunk.set_fluid(1, 3, 3, FluidState::new(1, 8));
+
+	// Version 1 files end with the environments, so whatever follows them has to be ignored
+	let mut region = RegionFile::open(&path).unwrap();
+	region.write_chunk(&chunk).unwrap();
+	drop(region);
+	let mut data = std::fs::read(&path).unwrap();
+	data[4..6].copy_from_slice(&1u16.to_le_bytes());
+	std::fs::write(&path, data).unwrap();
+	// An upgrade that crashed halfway leaves its new file behind, next to the untouched old one
+	let tmp = dir.0.join("old.region.tmp");
+	std::fs::write(&tmp, vec![0xAB; 1024]).unwrap();
+
+	let upgraded = RegionFile::open(&path).unwrap().read_chunk(5, 9).unwrap().unwrap();
+	assert_eq!(std::fs::read(&path).unwrap()[4..6], REGION_FORMAT_VERSION.to_le_bytes());
+	assert!(!tmp.exists(), "The upgraded file replaces the old one");
+	assert_eq!(upgraded.get_block(1, 2, 3), BlockState::new(7));
+	assert_eq!(upgraded.get_fluid(1, 3, 3), FluidState::EMPTY);
+	chunk.set_fluid(1, 3, 3, FluidState::EMPTY);
+	assert_same(&chunk, &RegionFile::open(&path).unwrap().read_chunk(5, 9).unwrap().unwrap());
+}
+
+#[test]
+fn rejects_foreign_files() {
+	let dir = TempDir::new("invalid");
+
+	let garbage = dir.0.join("garbage.region");
+	std::fs::write(&garbage, vec![0xAB; 16 * 1024]).unwrap();
+	assert!(matches!(RegionFile::open(&garbage), Err(RegionError::InvalidMagic)));
+
+	let future = dir.0.join("future.region");
+	drop(RegionFile::open(&future).unwrap());
+	let mut data = std::fs::read(&future).unwrap();
+	data[4] = 0xFF;
+	std::fs::write(&future, data).unwrap();
+	assert!(matches!(RegionFile::open(&future), Err(RegionError::UnsupportedVersion(_))));
+
+	let truncated = dir.0.join("truncated.region");
+	std::fs::write(&truncated, b"HTRG").unwrap();
+	assert!(matches!(RegionFile::open(&truncated), Err(RegionError::Corrupt(_))));
+}
+
+#[test]
+fn rewrites_reuse_freed_space() {
+	let dir = TempDir::new("reuse");
+	let path = dir.0.join("reuse.region");
+	let mut rng = StdRng::seed_from_u64(3);
+	let mut region = RegionFile::open(&path).unwrap();
+
+	let mut latest = None;
+	for _ in 0..20 {
+		let chunk = random_chunk(&mut rng, 1, 1, 100);
+		region.write_chunk(&chunk).unwrap();
+		latest = Some(chunk);
+	}
+	// Removing the chunk frees exactly the room it takes to write it again
+	let largest = std::fs::metadata(&path).unwrap().len();
+	region.remove_chunk(1, 1).unwrap();
+	let chunk = latest.unwrap();
+	region.write_chunk(&chunk).unwrap();
+	assert_eq!(std::fs::metadata(&path).unwrap().len(), largest);
+	drop(region);
+
+	assert_same(&chunk, &RegionFile::open(&path).unwrap().read_chunk(1, 1).unwrap().unwrap());
+}
+
+#[tokio::test]
+async fn keeps_a_bounded_number_of_regions_open() {
+	let dir = TempDir::new("many-regions");
+	let mut rng = StdRng::seed_from_u64(11);
+	let storage = Arc::new(RegionStorage::new(&dir.0));
+
+	let chunks: Vec<Chunk> = (0..MAX_OPEN_REGIONS as i32 + 8).map(|region| random_chunk(&mut rng, region * REGION_SIZE as i32, 0, 4)).collect();
+	for chunk in &chunks {
+		storage.save_chunk(chunk.clone()).await.unwrap();
+	}
+	for chunk in &chunks {
+		assert_same(chunk, &storage.load_chunk(chunk.x(), chunk.z()).await.unwrap().unwrap());
+	}
+}
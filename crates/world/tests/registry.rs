@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use assets::{
+	InputRef,
+	WithInput,
+};
+use world::{
+	BlockLighting,
+	BlockRegistry,
+	BlockState,
+	BlockTypeAsset,
+	EMPTY_BLOCK,
+	UNKNOWN_BLOCK,
+};
+
+fn block(name: &str, json: &str) -> WithInput<BlockTypeAsset> {
+	let asset = BlockTypeAsset::from_item_json(name, json.as_bytes()).unwrap().expect("item should be a block");
+	WithInput::new(InputRef::label(name), asset)
+}
+
+fn pack(names: &[&str]) -> Vec<WithInput<BlockTypeAsset>> {
+	names.iter().map(|name| block(name, r#"{ "BlockType": { "Material": "Solid", "Textures": [{ "All": "Stone.png" }] } }"#)).collect()
+}
+
+#[test]
+fn ids_survive_pack_changes() {
+	let mut registry = BlockRegistry::new(&BTreeMap::new());
+	registry.load(pack(&["Rock_Stone", "Soil_Dirt", "Soil_Grass"])).unwrap();
+	assert_eq!(registry.id(EMPTY_BLOCK), Some(0));
+	assert_eq!(registry.id(UNKNOWN_BLOCK), Some(1));
+	let saved = registry.ids();
+
+	// A block was removed and two were added, one of which sorts before the existing ones
+	let mut reloaded = BlockRegistry::new(&saved);
+	reloaded.load(pack(&["Rock_Bedrock", "Rock_Stone", "Soil_Grass", "Soil_Sand"])).unwrap();
+	for name in ["Rock_Stone", "Soil_Dirt", "Soil_Grass"] {
+		assert_eq!(reloaded.id(name), saved.get(name).copied(), "{} changed its id", name);
+	}
+	assert!(reloaded.get("Soil_Dirt").is_none());
+	assert!(reloaded.id("Rock_Bedrock").unwrap() >= registry.max_id());
+	assert!(reloaded.id("Soil_Sand").unwrap() >= registry.max_id());
+
+	let packet = reloaded.update_block_types();
+	assert_eq!(packet.max_id, reloaded.max_id());
+	let types = packet.block_types.unwrap();
+	assert_eq!(types.len(), reloaded.max_id() as usize);
+	assert!(types[&saved["Soil_Dirt"]].unknown);
+	assert!(!types[&saved["Rock_Stone"]].unknown);
+}
+
+#[test]
+fn lighting_follows_block_types() {
+	let mut registry = BlockRegistry::new(&BTreeMap::new());
+	registry
+		.load(vec![
+			block("Rock_Stone", r#"{ "BlockType": { "Material": "Solid" } }"#),
+			block("Glass", r#"{ "BlockType": { "Opacity": "Transparent" } }"#),
+			block("Torch", r##"{ "BlockType": { "DrawType": "Model", "Opacity": "Transparent", "Light": { "Color": "#f80" } } }"##),
+		])
+		.unwrap();
+	assert!(BlockTypeAsset::from_item_json("Stick", br#"{ "MaxStack": 64 }"#).unwrap().is_none());
+
+	let state = |name: &str| BlockState::new(registry.id(name).unwrap());
+	assert_eq!(registry.opacity(BlockState::AIR), 0);
+	assert_eq!(registry.opacity(state("Rock_Stone")), 15);
+	assert_eq!(registry.opacity(state("Glass")), 0);
+	assert_eq!(registry.emission(state("Torch")), [15, 8, 0]);
+	assert_eq!(registry.block_type(registry.id("Torch").unwrap()).light.map(|light| light.radius), Some(15));
+}
@@ -0,0 +1,47 @@
+use std::{
+	sync::Arc,
+	time::Duration,
+};
+
+use parking_lot::Mutex;
+use world::{
+	TickScheduler,
+	Tickable,
+};
+
+#[derive(Default)]
+struct Recorder {
+	elapsed: Mutex<Vec<Duration>>,
+	timing: Mutex<Vec<(u32, f32)>>,
+}
+
+impl Tickable for Recorder {
+	fn tick(&self, elapsed: Duration) {
+		self.elapsed.lock().push(elapsed);
+	}
+
+	fn timing_changed(&self, ticks_per_second: u32, time_dilation: f32) {
+		self.timing.lock().push((ticks_per_second, time_dilation));
+	}
+}
+
+#[test]
+fn dilation_stretches_real_time_only() {
+	let ticks = TickScheduler::new(20);
+	let recorder = Arc::new(Recorder::default());
+	ticks.add_system(recorder.clone());
+	assert_eq!(*recorder.timing.lock(), [(20, 1.0)], "Systems learn the timing when they're added");
+
+	ticks.set_time_dilation(0.5);
+	assert_eq!(ticks.tick_duration(), Duration::from_millis(50));
+	assert_eq!(ticks.tick_interval(), Duration::from_millis(100));
+	ticks.set_time_dilation(100.0);
+	assert_eq!(ticks.time_dilation(), 4.0, "Dilation is clamped");
+
+	ticks.set_ticks_per_second(10);
+	ticks.tick();
+	ticks.tick();
+	assert_eq!(*recorder.elapsed.lock(), [Duration::from_millis(100); 2]);
+	assert_eq!(recorder.timing.lock().last(), Some(&(10, 4.0)));
+	assert_eq!(ticks.stats().ticks, 2);
+}
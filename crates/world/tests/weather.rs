@@ -0,0 +1,73 @@
+use std::{
+	collections::BTreeMap,
+	sync::Arc,
+};
+
+use assets::{
+	InputRef,
+	WithInput,
+};
+use world::{
+	EnvironmentAsset,
+	EnvironmentRegistry,
+	WeatherAsset,
+	WeatherRegistry,
+	WeatherScheduler,
+	NO_WEATHER_OVERRIDE,
+};
+
+fn registries() -> (WeatherRegistry, Arc<EnvironmentRegistry>) {
+	let mut weathers = WeatherRegistry::new();
+	let assets = ["Clear", "Rain", "Storm"].map(|name| WithInput::new(InputRef::label(name), WeatherAsset::from_json(name, b"{}").unwrap()));
+	weathers.load(assets.into()).unwrap();
+
+	let plains = r#"{ "WeatherForecasts": {
+		"6": [{ "WeatherId": "Clear", "Weight": 1 }],
+		"18": [{ "WeatherId": "Rain", "Weight": 1 }, { "WeatherId": "Missing", "Weight": 5 }]
+	} }"#;
+	let mut environments = EnvironmentRegistry::new(&BTreeMap::new(), "Plains");
+	let assets = [("Plains", plains), ("Cave", "{}")].map(|(name, json)| WithInput::new(InputRef::label(name), EnvironmentAsset::from_json(name, json.as_bytes()).unwrap()));
+	environments.load(assets.into(), &weathers).unwrap();
+	(weathers, Arc::new(environments))
+}
+
+#[test]
+fn forecasts_fill_every_hour() {
+	let (weathers, environments) = registries();
+	let (clear, rain) = (weathers.id("Clear").unwrap(), weathers.id("Rain").unwrap());
+	assert_eq!(environments.id("Plains"), Some(0), "The default environment gets id 0");
+
+	assert_eq!(environments.forecast(0, 6), [(clear, 1)]);
+	assert_eq!(environments.forecast(0, 12), [(clear, 1)]);
+	assert_eq!(environments.forecast(0, 18), [(rain, 1)], "Unknown weathers are left out");
+	assert_eq!(environments.forecast(0, 2), [(rain, 1)], "Early hours keep the forecast of the evening before");
+	assert!(environments.forecast(environments.id("Cave").unwrap(), 6).is_empty());
+	assert_eq!(environments.with_weather().collect::<Vec<_>>(), [0]);
+}
+
+#[test]
+fn forced_weather_wins_until_cleared() {
+	let (weathers, environments) = registries();
+	let (clear, storm) = (weathers.id("Clear").unwrap(), weathers.id("Storm").unwrap());
+	let mut scheduler = WeatherScheduler::new(environments);
+
+	let changes = scheduler.tick(12.0 * 3600.0);
+	assert_eq!(changes.len(), 1);
+	assert_eq!(scheduler.weather(0), Some(clear));
+	assert!(scheduler.tick(12.5 * 3600.0).is_empty(), "Weather is only rolled once per hour");
+
+	assert_eq!(scheduler.editor_override().weather_index, NO_WEATHER_OVERRIDE);
+	let changes = scheduler.force(None, Some(storm));
+	assert_eq!(changes.iter().map(|(environment, _)| *environment).collect::<Vec<_>>(), [0, 1]);
+	assert_eq!(scheduler.weather(1), Some(storm));
+	assert_eq!(scheduler.editor_override().weather_index, storm);
+	scheduler.force(Some(1), Some(clear));
+	assert_eq!(scheduler.editor_override().weather_index, storm, "Only weather forced everywhere is an override");
+	scheduler.tick(13.0 * 3600.0);
+	assert_eq!(scheduler.weather(0), Some(storm));
+
+	scheduler.force(None, None);
+	assert_eq!(scheduler.weather(0), Some(clear));
+	assert_eq!(scheduler.weather(1), None);
+	assert_eq!(scheduler.editor_override().weather_index, NO_WEATHER_OVERRIDE);
+}